@@ -0,0 +1,128 @@
+use llvm_ir::{Type, TypeRef};
+
+use crate::{
+    sexp::{Sexp, ToSexp},
+    z3_decl::extract,
+    VerifierState,
+};
+
+// The value of a struct or an array is the concatenation of the values of its fields,
+// the first field being the most significant. Padding is not part of the value, and
+// pointer fields keep their provenance. In memory, each field is stored at its offset
+// according to the data layout, and a store makes the bytes of the padding between and
+// after the fields poison. A zero-sized field, like `[0 x i8]`, has no bits in the value,
+// and a value with no bits at all, like that of `{}`, is `no_bits`.
+
+/// The value of a zero-sized type. It is never given to the solver.
+pub fn no_bits() -> Sexp {
+    Sexp::s3("_", "bv0", "0")
+}
+
+impl VerifierState {
+    /// The fields of an aggregate type with their byte offsets in memory, or `None` if
+    /// the type is not an aggregate.
+    pub fn aggregate_fields(&self, ty: &TypeRef) -> Option<Vec<(TypeRef, usize)>> {
+        match &**ty {
+            Type::StructType { .. } | Type::NamedStructType { .. } => {
+                let (offsets, _) = self.struct_layout(ty);
+                Some(self.struct_fields(ty).into_iter().zip(offsets).collect())
+            }
+            Type::ArrayType {
+                element_type,
+                num_elements,
+            } => {
+                let stride = self.alloc_size_of_ty(element_type);
+                Some(
+                    (0..*num_elements)
+                        .map(|i| (element_type.clone(), i * stride))
+                        .collect(),
+                )
+            }
+            Type::VectorType { .. } => {
+                let (lanes, lane_ty) = self.vector_lanes(ty).unwrap();
+                let stride = self.size_of_ty(&lane_ty);
+                Some((0..lanes).map(|i| (lane_ty.clone(), i * stride)).collect())
+            }
+            _ => None,
+        }
+    }
+
+    /// Offsets of the bytes of an aggregate of type `ty` in memory that none of its
+    /// `fields` covers.
+    pub fn padding_bytes(&self, ty: &TypeRef, fields: &[(TypeRef, usize)]) -> Vec<usize> {
+        let covered = |i: usize| {
+            fields.iter().any(|(field_ty, offset)| {
+                (*offset..offset + self.size_of_ty(field_ty)).contains(&i)
+            })
+        };
+        (0..self.size_of_ty(ty)).filter(|i| !covered(*i)).collect()
+    }
+
+    pub fn aggregate_value(&self, fields: Vec<Sexp>) -> Sexp {
+        let mut fields: Vec<_> = fields.into_iter().filter(|x| *x != no_bits()).collect();
+        match fields.len() {
+            0 => no_bits(),
+            1 => fields.pop().unwrap(),
+            _ => Sexp::List([vec!["concat".to_sexp()], fields].concat()),
+        }
+    }
+
+    /// Bits of the value of an aggregate of type `ty` that hold the field `index`, or
+    /// `None` if the field is zero-sized.
+    fn field_bits(&self, ty: &TypeRef, index: usize) -> Option<(usize, usize)> {
+        let fields = self.aggregate_fields(ty).unwrap();
+        let bits = self.value_bits(&fields[index].0);
+        if bits == 0 {
+            return None;
+        }
+        let low = fields[index + 1..]
+            .iter()
+            .map(|(x, _)| self.value_bits(x))
+            .sum::<usize>();
+        Some((low + bits - 1, low))
+    }
+
+    pub fn extract_field(&self, value: Sexp, ty: &TypeRef, index: usize) -> Sexp {
+        match self.field_bits(ty, index) {
+            Some((high, low)) => extract(value, high, low),
+            None => no_bits(),
+        }
+    }
+
+    pub fn insert_field(&self, value: Sexp, ty: &TypeRef, index: usize, field: Sexp) -> Sexp {
+        let Some((high, low)) = self.field_bits(ty, index) else {
+            return value;
+        };
+        let bits = self.value_bits(ty);
+        let mut r = vec![];
+        if high + 1 < bits {
+            r.push(extract(value.clone(), bits - 1, high + 1));
+        }
+        r.push(field);
+        if low > 0 {
+            r.push(extract(value, low - 1, 0));
+        }
+        self.aggregate_value(r)
+    }
+
+    /// Value at the path `indices` into an aggregate, as in `extractvalue`.
+    pub fn extract_path(&self, value: Sexp, ty: &TypeRef, indices: &[u32]) -> Sexp {
+        let Some((&index, rest)) = indices.split_first() else {
+            return value;
+        };
+        let field_ty = self.aggregate_fields(ty).unwrap()[index as usize].0.clone();
+        let field = self.extract_field(value, ty, index as usize);
+        self.extract_path(field, &field_ty, rest)
+    }
+
+    /// Replaces the value at the path `indices` into an aggregate, as in `insertvalue`.
+    pub fn insert_path(&self, value: Sexp, ty: &TypeRef, indices: &[u32], element: Sexp) -> Sexp {
+        let Some((&index, rest)) = indices.split_first() else {
+            return element;
+        };
+        let field_ty = self.aggregate_fields(ty).unwrap()[index as usize].0.clone();
+        let field = self.extract_field(value.clone(), ty, index as usize);
+        let field = self.insert_path(field, &field_ty, rest, element);
+        self.insert_field(value, ty, index as usize, field)
+    }
+}
@@ -0,0 +1,411 @@
+use llvm_ir::{
+    function::{FunctionAttribute, MemoryEffect, Parameter, ParameterAttribute},
+    instruction::Call,
+    Constant, Function, Name, Operand,
+};
+
+use crate::VerifierState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Access {
+    pub read: bool,
+    pub write: bool,
+}
+
+impl Access {
+    const NONE: Access = Access {
+        read: false,
+        write: false,
+    };
+    const READ: Access = Access {
+        read: true,
+        write: false,
+    };
+    const WRITE: Access = Access {
+        read: false,
+        write: true,
+    };
+    const ALL: Access = Access {
+        read: true,
+        write: true,
+    };
+
+    fn limit(&mut self, other: Access) {
+        self.read &= other.read;
+        self.write &= other.write;
+    }
+
+    fn includes(&self, other: Access) -> bool {
+        (self.read || !other.read) && (self.write || !other.write)
+    }
+}
+
+impl From<&MemoryEffect> for Access {
+    fn from(value: &MemoryEffect) -> Self {
+        match value {
+            MemoryEffect::None => Access::NONE,
+            MemoryEffect::Read => Access::READ,
+            MemoryEffect::Write => Access::WRITE,
+            MemoryEffect::ReadWrite => Access::ALL,
+        }
+    }
+}
+
+/// What a call may do, as far as its attributes and the attributes of its callee tell.
+/// Inaccessible memory is not observable by the caller, so it is not tracked.
+///
+/// llvm-ir has no function attributes on `FunctionDeclaration`, so those of a callee
+/// that is only declared, like `declare void @f() nounwind`, are not seen. Only the
+/// attributes of the call site and of defined callees are used, which may make a call
+/// look like it does more than it can.
+#[derive(Debug, Clone)]
+pub struct CallEffects {
+    /// Access to memory pointed to by pointer arguments.
+    pub argmem: Access,
+    /// Access to any other memory.
+    pub other: Access,
+    pub will_return: bool,
+    pub no_unwind: bool,
+    pub no_capture: Vec<bool>,
+    pub noalias_return: bool,
+    /// The returned pointer is known not to be null.
+    pub nonnull_return: bool,
+}
+
+impl CallEffects {
+    /// Whether the call only computes a value from its arguments, which takes returning
+    /// normally, without unwinding.
+    pub fn is_pure(&self) -> bool {
+        self.argmem == Access::NONE
+            && self.other == Access::NONE
+            && self.will_return
+            && self.no_unwind
+    }
+
+    /// Whether every guarantee of `self` is one of `other` too, so that a call with these
+    /// effects assumes no more about its callee than a call with `other`.
+    pub fn no_stronger_than(&self, other: &CallEffects) -> bool {
+        let implies = |a: bool, b: bool| !a || b;
+        self.argmem.includes(other.argmem)
+            && self.other.includes(other.other)
+            && implies(self.will_return, other.will_return)
+            && implies(self.no_unwind, other.no_unwind)
+            && self
+                .no_capture
+                .iter()
+                .zip(&other.no_capture)
+                .all(|(a, b)| implies(*a, *b))
+            && implies(self.noalias_return, other.noalias_return)
+            && implies(self.nonnull_return, other.nonnull_return)
+    }
+}
+
+impl VerifierState {
+    pub fn may_unwind(&self, call: &Call) -> bool {
+        !self.call_effects(call).no_unwind
+    }
+
+    pub fn callee_name(&self, call: &Call) -> Option<String> {
+        self.function_name(call.function.as_ref().right()?)
+    }
+
+    pub fn function_name(&self, function: &Operand) -> Option<String> {
+        let Operand::ConstantOperand(c) = function else {
+            return None;
+        };
+        match &**c {
+            Constant::GlobalReference {
+                name: Name::Name(name),
+                ..
+            } => Some((**name).clone()),
+            _ => None,
+        }
+    }
+
+    /// Whether the function is one of the configured diverging functions.
+    pub fn is_diverging(&self, function: &Operand) -> bool {
+        let Some(name) = self.function_name(function) else {
+            return false;
+        };
+        let path = demangle(&name);
+        self.config
+            .diverging_functions
+            .iter()
+            .any(|pattern| match pattern.split_once('*') {
+                Some((prefix, suffix)) => {
+                    path.len() >= prefix.len() + suffix.len()
+                        && path.starts_with(prefix)
+                        && path.ends_with(suffix)
+                }
+                None => path == *pattern,
+            })
+    }
+
+    pub fn callee(&self, call: &Call) -> Option<&Function> {
+        let name = self.callee_name(call)?;
+        self.module.get_func_by_name(&name)
+    }
+
+    /// Parameters and return attributes of the callee, from its definition or declaration.
+    fn callee_signature(&self, call: &Call) -> (&[Parameter], &[ParameterAttribute]) {
+        let Some(name) = self.callee_name(call) else {
+            return (&[], &[]);
+        };
+        if let Some(f) = self.module.get_func_by_name(&name) {
+            return (&f.parameters, &f.return_attributes);
+        }
+        if let Some(f) = self.module.get_func_decl_by_name(&name) {
+            return (&f.parameters, &f.return_attributes);
+        }
+        (&[], &[])
+    }
+
+    pub fn call_effects(&self, call: &Call) -> CallEffects {
+        let callee = self.callee(call);
+        let mut function_attributes = call.function_attributes.clone();
+        if let Some(callee) = callee {
+            function_attributes.extend(callee.function_attributes.iter().cloned());
+        }
+        let mut effects = CallEffects {
+            argmem: Access::ALL,
+            other: Access::ALL,
+            will_return: false,
+            no_unwind: false,
+            no_capture: vec![],
+            noalias_return: false,
+            nonnull_return: false,
+        };
+        for attr in &function_attributes {
+            match attr {
+                FunctionAttribute::ReadNone => {
+                    effects.argmem = Access::NONE;
+                    effects.other = Access::NONE;
+                }
+                FunctionAttribute::ReadOnly => {
+                    effects.argmem.limit(Access::READ);
+                    effects.other.limit(Access::READ);
+                }
+                FunctionAttribute::WriteOnly => {
+                    effects.argmem.limit(Access::WRITE);
+                    effects.other.limit(Access::WRITE);
+                }
+                FunctionAttribute::ArgMemOnly | FunctionAttribute::InaccessibleMemOrArgMemOnly => {
+                    effects.other = Access::NONE;
+                }
+                FunctionAttribute::InaccessibleMemOnly => {
+                    effects.argmem = Access::NONE;
+                    effects.other = Access::NONE;
+                }
+                FunctionAttribute::Memory {
+                    default, argmem, ..
+                } => {
+                    effects.argmem.limit(argmem.into());
+                    effects.other.limit(default.into());
+                }
+                FunctionAttribute::WillReturn => effects.will_return = true,
+                FunctionAttribute::NoUnwind => effects.no_unwind = true,
+                _ => {}
+            }
+        }
+        let (declared_params, declared_return) = self.callee_signature(call);
+        effects.no_capture = call
+            .arguments
+            .iter()
+            .enumerate()
+            .map(|(i, (_, attrs))| {
+                let declared = declared_params.get(i).map(|x| &*x.attributes);
+                attrs.iter().chain(declared.unwrap_or_default()).any(|x| {
+                    matches!(
+                        x,
+                        ParameterAttribute::NoCapture | ParameterAttribute::ByVal(_)
+                    )
+                })
+            })
+            .collect();
+        let return_attributes = || call.return_attributes.iter().chain(declared_return);
+        effects.noalias_return = return_attributes().any(|x| *x == ParameterAttribute::NoAlias);
+        effects.nonnull_return = return_attributes().any(|x| {
+            matches!(
+                x,
+                ParameterAttribute::NonNull | ParameterAttribute::Dereferenceable(_)
+            )
+        });
+        effects
+    }
+}
+
+/// The path of a symbol mangled with either Rust scheme, without its hash, like
+/// `core::panicking::panic`. Other symbols are returned unchanged.
+fn demangle(name: &str) -> String {
+    if let Some(path) = name.strip_prefix("_R").and_then(demangle_v0) {
+        return path;
+    }
+    let Some(mut rest) = name.strip_prefix("_ZN") else {
+        return name.to_owned();
+    };
+    let mut segments = vec![];
+    while let Some(len_end) = rest.find(|c: char| !c.is_ascii_digit()) {
+        let Ok(len) = rest[..len_end].parse::<usize>() else {
+            break;
+        };
+        let Some(segment) = rest[len_end..].get(..len) else {
+            return name.to_owned();
+        };
+        segments.push(segment);
+        rest = &rest[len_end + len..];
+    }
+    if rest != "E" {
+        return name.to_owned();
+    }
+    if let Some(hash) = segments.last() {
+        if hash.len() == 17 && hash.starts_with('h') {
+            segments.pop();
+        }
+    }
+    segments.join("::")
+}
+
+/// The path of a symbol mangled with the v0 Rust scheme, given without its `_R`, like
+/// `NvNtCs1234_4core9panicking5panic`. Crate disambiguators and the generic arguments of
+/// the function are left out. Symbols of impls and other paths that the patterns of
+/// diverging functions do not name are not demangled.
+fn demangle_v0(symbol: &str) -> Option<String> {
+    let mut parser = V0Parser {
+        symbol: symbol.as_bytes(),
+        pos: 0,
+    };
+    // An optional encoding version comes first.
+    while parser.symbol.get(parser.pos)?.is_ascii_digit() {
+        parser.pos += 1;
+    }
+    if parser.eat(b'I') {
+        // Generic arguments follow the path, and are left out.
+        return parser.path();
+    }
+    parser.path()
+}
+
+struct V0Parser<'a> {
+    symbol: &'a [u8],
+    pos: usize,
+}
+
+impl V0Parser<'_> {
+    fn eat(&mut self, byte: u8) -> bool {
+        let r = self.symbol.get(self.pos) == Some(&byte);
+        if r {
+            self.pos += 1;
+        }
+        r
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let r = *self.symbol.get(self.pos)?;
+        self.pos += 1;
+        Some(r)
+    }
+
+    /// A path of crates and namespaces. Back references, which only stand for paths that
+    /// came earlier, like in generic arguments, are not demangled.
+    fn path(&mut self) -> Option<String> {
+        match self.next()? {
+            b'C' => self.identifier(),
+            b'N' => {
+                let namespace = self.next()?;
+                let parent = self.path()?;
+                let identifier = self.identifier()?;
+                let segment = match namespace {
+                    b'C' => "{closure}".to_owned(),
+                    b'S' => "{shim}".to_owned(),
+                    b'a'..=b'z' => identifier,
+                    _ => return None,
+                };
+                Some(format!("{parent}::{segment}"))
+            }
+            _ => None,
+        }
+    }
+
+    /// An identifier, which may start with a disambiguator and is not punycode.
+    fn identifier(&mut self) -> Option<String> {
+        if self.eat(b's') {
+            self.base_62()?;
+        }
+        let digits = self.symbol[self.pos..]
+            .iter()
+            .take_while(|x| x.is_ascii_digit())
+            .count();
+        let len: usize = std::str::from_utf8(&self.symbol[self.pos..self.pos + digits])
+            .ok()?
+            .parse()
+            .ok()?;
+        self.pos += digits;
+        // Separates identifiers that start with a digit or `_` from their length.
+        self.eat(b'_');
+        let bytes = self.symbol.get(self.pos..self.pos + len)?;
+        self.pos += len;
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+
+    /// A number in base 62 ending in `_`, which is 0 for a lone `_` and one more than
+    /// the digits otherwise.
+    fn base_62(&mut self) -> Option<usize> {
+        if self.eat(b'_') {
+            return Some(0);
+        }
+        let mut r: usize = 0;
+        loop {
+            let digit = match self.next()? {
+                b'_' => return r.checked_add(1),
+                x @ b'0'..=b'9' => x - b'0',
+                x @ b'a'..=b'z' => x - b'a' + 10,
+                x @ b'A'..=b'Z' => x - b'A' + 36,
+                _ => return None,
+            };
+            r = r.checked_mul(62)?.checked_add(digit as usize)?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn demangle_legacy_symbols() {
+        assert_eq!(
+            demangle("_ZN4core9panicking5panic17h0123456789abcdefE"),
+            "core::panicking::panic"
+        );
+        assert_eq!(demangle("_ZN3foo3barE"), "foo::bar");
+    }
+
+    #[test]
+    fn demangle_v0_symbols() {
+        assert_eq!(
+            demangle("_RNvNtCs1234_4core9panicking18panic_bounds_check"),
+            "core::panicking::panic_bounds_check"
+        );
+        // Generic, with a back reference to the crate among its arguments.
+        assert_eq!(
+            demangle("_RINvNtCsd4xuWBkFATJ_3std9panicking11begin_panicReEB6_"),
+            "std::panicking::begin_panic"
+        );
+        assert_eq!(
+            demangle("_RNCNvNtCs1234_4core6option13expect_failed0"),
+            "core::option::expect_failed::{closure}"
+        );
+    }
+
+    #[test]
+    fn demangle_keeps_other_symbols() {
+        assert_eq!(demangle("malloc"), "malloc");
+        assert_eq!(demangle("_ZN3fooE3bar"), "_ZN3fooE3bar");
+        assert_eq!(demangle("_ZN9foo"), "_ZN9foo");
+        assert_eq!(
+            demangle("_RNvNtCs1234_4core9panic"),
+            "_RNvNtCs1234_4core9panic"
+        );
+        assert_eq!(demangle("_RNvB0_3foo"), "_RNvB0_3foo");
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::{HashMap, HashSet};
+
+use llvm_ir::{Function, Name, Terminator};
+
+pub fn successors(term: &Terminator) -> Vec<&Name> {
+    match term {
+        Terminator::Br(br) => vec![&br.dest],
+        Terminator::CondBr(br) => vec![&br.true_dest, &br.false_dest],
+        Terminator::Switch(switch) => switch
+            .dests
+            .iter()
+            .map(|(_, x)| x)
+            .chain([&switch.default_dest])
+            .collect(),
+        Terminator::IndirectBr(br) => br.possible_dests.iter().collect(),
+        Terminator::Invoke(invoke) => vec![&invoke.return_label, &invoke.exception_label],
+        Terminator::CleanupRet(ret) => ret.unwind_dest.iter().collect(),
+        Terminator::CatchRet(ret) => vec![&ret.successor],
+        Terminator::CatchSwitch(switch) => switch
+            .catch_handlers
+            .iter()
+            .chain(&switch.default_unwind_dest)
+            .collect(),
+        Terminator::CallBr(br) => vec![&br.return_label],
+        Terminator::Ret(_) | Terminator::Resume(_) | Terminator::Unreachable(_) => vec![],
+    }
+}
+
+pub type BackEdges = HashSet<(usize, usize)>;
+
+/// Edges `(from, to)` between basic block indices that go back to a block which is
+/// still being visited by a depth first search from the entry, i.e. the edges that
+/// close a loop.
+pub fn back_edges(f: &Function) -> BackEdges {
+    let index: HashMap<&Name, usize> = f
+        .basic_blocks
+        .iter()
+        .enumerate()
+        .map(|(i, bb)| (&bb.name, i))
+        .collect();
+    let successors: Vec<Vec<usize>> = f
+        .basic_blocks
+        .iter()
+        .map(|bb| successors(&bb.term).into_iter().map(|x| index[x]).collect())
+        .collect();
+    let mut r = HashSet::new();
+    let mut visited = vec![false; f.basic_blocks.len()];
+    let mut on_stack = vec![false; f.basic_blocks.len()];
+    let mut stack = vec![(0, 0)];
+    visited[0] = true;
+    on_stack[0] = true;
+    while let Some((bb, next)) = stack.last_mut() {
+        let bb = *bb;
+        let Some(&succ) = successors[bb].get(*next) else {
+            on_stack[bb] = false;
+            stack.pop();
+            continue;
+        };
+        *next += 1;
+        if on_stack[succ] {
+            r.insert((bb, succ));
+        } else if !visited[succ] {
+            visited[succ] = true;
+            on_stack[succ] = true;
+            stack.push((succ, 0));
+        }
+    }
+    r
+}
+
+/// Blocks of the loop with the header `header`: the header and the blocks from which a
+/// back-edge to it is reached without passing through it.
+pub fn loop_body(f: &Function, back_edges: &BackEdges, header: usize) -> HashSet<usize> {
+    let index: HashMap<&Name, usize> = f
+        .basic_blocks
+        .iter()
+        .enumerate()
+        .map(|(i, bb)| (&bb.name, i))
+        .collect();
+    let mut predecessors = vec![vec![]; f.basic_blocks.len()];
+    for (i, bb) in f.basic_blocks.iter().enumerate() {
+        for succ in successors(&bb.term) {
+            predecessors[index[succ]].push(i);
+        }
+    }
+    let mut r = HashSet::from([header]);
+    let mut stack: Vec<usize> = back_edges
+        .iter()
+        .filter(|&&(_, to)| to == header)
+        .map(|&(from, _)| from)
+        .collect();
+    while let Some(bb) = stack.pop() {
+        if r.insert(bb) {
+            stack.extend(&predecessors[bb]);
+        }
+    }
+    r
+}
+
+#[cfg(test)]
+mod tests {
+    use llvm_ir::{
+        terminator::IndirectBr, types::Types, BasicBlock, Constant, ConstantRef, Operand,
+    };
+
+    use super::*;
+
+    /// A function whose block `i` jumps to the blocks `successors[i]`.
+    fn function(successors: &[&[usize]]) -> Function {
+        let mut f = Function::new("f");
+        let null = Constant::Null(Types::blank_for_testing().pointer());
+        for (i, dests) in successors.iter().enumerate() {
+            let mut bb = BasicBlock::new(Name::Number(i));
+            if !dests.is_empty() {
+                bb.term = Terminator::IndirectBr(IndirectBr {
+                    operand: Operand::ConstantOperand(ConstantRef::new(null.clone())),
+                    possible_dests: dests.iter().map(|x| Name::Number(*x)).collect(),
+                    debugloc: None,
+                });
+            }
+            f.basic_blocks.push(bb);
+        }
+        f
+    }
+
+    #[test]
+    fn no_back_edges_without_loops() {
+        let f = function(&[&[1, 2], &[3], &[3], &[]]);
+        assert_eq!(back_edges(&f), HashSet::new());
+    }
+
+    #[test]
+    fn back_edges_of_nested_loops() {
+        let f = function(&[&[1], &[2, 4], &[2, 3], &[1], &[]]);
+        assert_eq!(back_edges(&f), HashSet::from([(2, 2), (3, 1)]));
+    }
+
+    #[test]
+    fn loop_body_of_nested_loops() {
+        let f = function(&[&[1], &[2, 4], &[2, 3], &[1], &[]]);
+        let back_edges = back_edges(&f);
+        assert_eq!(loop_body(&f, &back_edges, 1), HashSet::from([1, 2, 3]));
+        assert_eq!(loop_body(&f, &back_edges, 2), HashSet::from([2]));
+    }
+
+    #[test]
+    fn back_edges_ignore_unreachable_blocks() {
+        let f = function(&[&[], &[1]]);
+        assert_eq!(back_edges(&f), HashSet::new());
+    }
+}
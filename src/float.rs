@@ -0,0 +1,539 @@
+use std::{
+    collections::HashMap,
+    ffi::{CStr, CString},
+    ptr::null_mut,
+};
+
+use llvm_ir::{types::FPType, Constant, FPPredicate, Function, Name, Type, TypeRef};
+use llvm_sys::{
+    core::{
+        LLVMContextCreate, LLVMContextDispose, LLVMCreateMemoryBufferWithContentsOfFile,
+        LLVMDisposeMessage, LLVMDisposeModule, LLVMGetFirstBasicBlock, LLVMGetFirstFunction,
+        LLVMGetFirstInstruction, LLVMGetNextBasicBlock, LLVMGetNextFunction,
+        LLVMGetNextInstruction, LLVMGetValueName2, LLVMPrintValueToString,
+    },
+    ir_reader::LLVMParseIRInContext,
+};
+
+use crate::{
+    block_name,
+    poison::any,
+    sexp::{Sexp, ToSexp},
+    z3_decl::{bv_hex, extract, if_then_else},
+    VerifierState,
+};
+
+// Floating point values are kept as their IEEE bits, in registers as in memory, and are
+// converted to the `FloatingPoint` sort of SMT-LIB by each operation. An operation that
+// produces NaN produces the NaN that the solver converts back to bits, which is one of
+// the NaNs that LangRef allows. Operations that only move bits, like `fneg`, keep NaN
+// payloads as they are.
+
+/// Fast-math flags, as enabled by the user or as an instruction has them. `nnan` and
+/// `ninf` are modeled on the results of the instructions that have them. `nsz` is
+/// modeled where the functions are compared, so the user asserts with it that every
+/// floating point operation has it. Flags like `reassoc` allow results that are not
+/// modeled, so they are refused rather than assumed.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FastMath {
+    /// Operands and results are not NaN, or the result is poison.
+    pub nnan: bool,
+    /// Operands and results are not infinite, or the result is poison.
+    pub ninf: bool,
+    /// The sign of zeros is insignificant, so zeros of either sign are the same value
+    /// where the functions are compared.
+    pub nsz: bool,
+}
+
+impl FastMath {
+    /// Parses a comma separated list of flags, like `nnan,nsz`.
+    pub fn parse(flags: &str) -> Self {
+        let mut r = FastMath::default();
+        for flag in flags.split(',') {
+            match flag {
+                "nnan" => r.nnan = true,
+                "ninf" => r.ninf = true,
+                "nsz" => r.nsz = true,
+                "reassoc" | "arcp" | "contract" | "afn" | "fast" => {
+                    panic!("fast-math flag {flag} is not supported, only nnan, ninf and nsz are")
+                }
+                _ => panic!("unknown fast-math flag {flag}"),
+            }
+        }
+        r
+    }
+}
+
+/// Fast-math flags of the instructions of the module at `path` that have any, by their
+/// function and result. The C API of LLVM 17 has no getter for them, so they are read
+/// from the printed instructions. Flags that are not `enabled`, `nnan` and `ninf` where
+/// they are not modeled, and operations without `nsz` when it is enabled are refused.
+pub fn instruction_fast_math(path: &str, enabled: FastMath) -> HashMap<(String, Name), FastMath> {
+    let path = CString::new(path).unwrap();
+    let mut r = HashMap::new();
+    unsafe {
+        let context = LLVMContextCreate();
+        let mut buffer = null_mut();
+        let mut module = null_mut();
+        let mut message = null_mut();
+        // Parsing IR takes bitcode as well, and takes ownership of the buffer.
+        if LLVMCreateMemoryBufferWithContentsOfFile(path.as_ptr(), &mut buffer, &mut message) != 0
+            || LLVMParseIRInContext(context, buffer, &mut module, &mut message) != 0
+        {
+            panic!("{}", CStr::from_ptr(message).to_string_lossy());
+        }
+        let mut f = LLVMGetFirstFunction(module);
+        while !f.is_null() {
+            let mut len = 0;
+            let function = CStr::from_ptr(LLVMGetValueName2(f, &mut len)).to_string_lossy();
+            let mut bb = LLVMGetFirstBasicBlock(f);
+            while !bb.is_null() {
+                let mut instr = LLVMGetFirstInstruction(bb);
+                while !instr.is_null() {
+                    let text = LLVMPrintValueToString(instr);
+                    let printed = CStr::from_ptr(text).to_string_lossy();
+                    if let Some((dest, flags)) = checked_flags(printed.trim(), enabled) {
+                        r.insert((function.to_string(), dest), flags);
+                    }
+                    LLVMDisposeMessage(text);
+                    instr = LLVMGetNextInstruction(instr);
+                }
+                bb = LLVMGetNextBasicBlock(bb);
+            }
+            f = LLVMGetNextFunction(f);
+        }
+        LLVMDisposeModule(module);
+        LLVMContextDispose(context);
+    }
+    r
+}
+
+/// Result and fast-math flags of a printed instruction, if it has any flags, refusing
+/// the flags that are not modeled as the instruction has them.
+fn checked_flags(text: &str, enabled: FastMath) -> Option<(Name, FastMath)> {
+    let (opcode, flags) = instruction_flags(text);
+    let arithmetic = matches!(opcode, "fadd" | "fsub" | "fmul" | "fdiv" | "frem");
+    if enabled.nsz && arithmetic && !flags.contains(&"nsz") {
+        panic!(
+            "`{text}` has no nsz, which --fast-math=nsz assumes of every floating point operation"
+        );
+    }
+    let mut r = FastMath::default();
+    for flag in &flags {
+        match *flag {
+            "nnan" if enabled.nnan => r.nnan = true,
+            "ninf" if enabled.ninf => r.ninf = true,
+            "nsz" if enabled.nsz => r.nsz = true,
+            _ => panic!("fast-math flag {flag} of `{text}` is not enabled, see --fast-math"),
+        }
+    }
+    if (r.nnan || r.ninf) && !arithmetic && opcode != "fcmp" {
+        panic!(
+            "fast-math flags of `{text}` are not supported, only those of arithmetic and fcmp are"
+        );
+    }
+    let dest = text.split_once(" = ").map(|x| x.0)?;
+    let dest = dest.trim_start_matches('%').trim_matches('"');
+    (!flags.is_empty()).then(|| (block_name(dest), r))
+}
+
+/// Opcode and fast-math flags of a printed instruction, whose flags follow its opcode.
+fn instruction_flags(text: &str) -> (&str, Vec<&str>) {
+    const FLAGS: [&str; 8] = [
+        "nnan", "ninf", "nsz", "arcp", "contract", "afn", "reassoc", "fast",
+    ];
+    let mut tokens = text
+        .split_whitespace()
+        .skip_while(|x| x.starts_with('%') || matches!(*x, "=" | "tail" | "musttail" | "notail"));
+    let opcode = tokens.next().unwrap_or_default();
+    let mut r = vec![];
+    while let Some(token) = tokens.next().filter(|x| FLAGS.contains(x)) {
+        r.push(token);
+    }
+    (opcode, r)
+}
+
+impl VerifierState {
+    /// Exponent and significand bits of a floating point type.
+    fn float_format(&self, ty: &TypeRef) -> (usize, usize) {
+        match &**ty {
+            Type::FPType(FPType::Half) => (5, 11),
+            Type::FPType(FPType::BFloat) => (8, 8),
+            Type::FPType(FPType::Single) => (8, 24),
+            Type::FPType(FPType::Double) => (11, 53),
+            Type::FPType(FPType::FP128) => (15, 113),
+            _ => panic!("floating point type {ty} is not supported"),
+        }
+    }
+
+    fn to_float(&self, bits: Sexp, ty: &TypeRef) -> Sexp {
+        let (exponent, significand) = self.float_format(ty);
+        Sexp::s2(
+            Sexp::s4(
+                "_",
+                "to_fp",
+                &*exponent.to_string(),
+                &*significand.to_string(),
+            ),
+            bits,
+        )
+    }
+
+    pub fn float_constant(&self, c: &Constant) -> Sexp {
+        match c {
+            Constant::Float(llvm_ir::constant::Float::Single(x)) => bv_hex(x.to_bits() as usize, 4),
+            Constant::Float(llvm_ir::constant::Float::Double(x)) => bv_hex(x.to_bits() as usize, 8),
+            // The IR reader does not give the values of the other formats.
+            _ => panic!("floating point constant {c} is not supported, only float and double are"),
+        }
+    }
+
+    /// Fast-math flags of the instruction of `f` with the result `dest`.
+    pub fn fast_math(&self, f: &Function, dest: &Name) -> FastMath {
+        let key = (f.name.clone(), dest.clone());
+        let flags = self.config.instruction_fast_math.get(&key);
+        flags.copied().unwrap_or_default()
+    }
+
+    /// Applies `op` to the `FloatingPoint` values of each lane of the operands, which are
+    /// of type `ty`. `op` returns bits, and the values that the fast-math flags of the
+    /// instruction are about. Returns the result, and the mask of the lanes that the
+    /// flags make poison.
+    fn float_lanes(
+        &self,
+        ty: &TypeRef,
+        result_ty: &TypeRef,
+        operands: Vec<Sexp>,
+        fast_math: FastMath,
+        op: impl Fn(Vec<Sexp>) -> (Sexp, Vec<Sexp>),
+    ) -> (Sexp, Sexp) {
+        let result_lane_ty = self.lane_type(result_ty);
+        let operands: Vec<_> = operands.into_iter().map(|x| (x, ty.clone())).collect();
+        let lane = |lane_ty: &TypeRef, args: Vec<Sexp>| {
+            op(args
+                .into_iter()
+                .map(|x| self.to_float(x, lane_ty))
+                .collect())
+        };
+        let r = self.lane_wise(operands.clone(), |lane_ty, args| lane(lane_ty, args).0);
+        let poison = self.lane_wise(operands, |lane_ty, args| {
+            let mut poison = vec![];
+            for x in lane(lane_ty, args).1 {
+                if fast_math.nnan {
+                    poison.push(Sexp::s2("fp.isNaN", x.clone()));
+                }
+                if fast_math.ninf {
+                    poison.push(Sexp::s2("fp.isInfinite", x));
+                }
+            }
+            self.poison_if(&result_lane_ty, any(poison))
+        });
+        (r, poison)
+    }
+
+    /// Result of `fadd`, `fsub`, `fmul`, `fdiv` or `frem`, named by `op`, and the mask
+    /// of its lanes that are poison whatever the operands.
+    pub fn float_arithmetic(
+        &self,
+        op: &str,
+        ty: &TypeRef,
+        operands: Vec<Sexp>,
+        fast_math: FastMath,
+    ) -> (Sexp, Sexp) {
+        self.float_lanes(ty, ty, operands, fast_math, |args| {
+            let (a, b) = (args[0].clone(), args[1].clone());
+            let r = match op {
+                "fadd" => Sexp::s4("fp.add", "RNE", a.clone(), b.clone()),
+                "fsub" => Sexp::s4("fp.sub", "RNE", a.clone(), b.clone()),
+                "fmul" => Sexp::s4("fp.mul", "RNE", a.clone(), b.clone()),
+                "fdiv" => Sexp::s4("fp.div", "RNE", a.clone(), b.clone()),
+                "frem" => remainder(a.clone(), b.clone()),
+                _ => unreachable!("unknown floating point operation {op}"),
+            };
+            (Sexp::s2("fp.to_ieee_bv", r.clone()), vec![a, b, r])
+        })
+    }
+
+    /// The value with its negative zeros made positive if `nsz` makes their sign
+    /// insignificant, so that values that differ only there are the same. Scalars that
+    /// are not floating point are kept as they are.
+    pub fn without_zero_sign(&self, ty: &TypeRef, value: Sexp) -> Sexp {
+        if !self.config.fast_math.nsz {
+            return value;
+        }
+        self.lane_wise(vec![(value, ty.clone())], |lane_ty, x| {
+            let x = x[0].clone();
+            let Type::FPType(_) = &**lane_ty else {
+                return x;
+            };
+            let bits = self.value_bits(lane_ty);
+            let zero = Sexp::s3("_", "bv0", &*bits.to_string());
+            let negative_zero = Sexp::s3(
+                "concat",
+                "#b1",
+                Sexp::s3("_", "bv0", &*(bits - 1).to_string()),
+            );
+            if_then_else(Sexp::s3("=", x.clone(), negative_zero), zero, x)
+        })
+    }
+
+    pub fn float_compare(
+        &self,
+        predicate: FPPredicate,
+        ty: &TypeRef,
+        result_ty: &TypeRef,
+        operands: Vec<Sexp>,
+        fast_math: FastMath,
+    ) -> (Sexp, Sexp) {
+        self.float_lanes(ty, result_ty, operands, fast_math, |args| {
+            let (a, b) = (args[0].clone(), args[1].clone());
+            let unordered = Sexp::s3(
+                "or",
+                Sexp::s2("fp.isNaN", a.clone()),
+                Sexp::s2("fp.isNaN", b.clone()),
+            );
+            let compare = |op| Sexp::s3(op, a.clone(), b.clone());
+            let r = match predicate {
+                FPPredicate::False => "false".to_sexp(),
+                FPPredicate::OEQ => compare("fp.eq"),
+                FPPredicate::OGT => compare("fp.gt"),
+                FPPredicate::OGE => compare("fp.geq"),
+                FPPredicate::OLT => compare("fp.lt"),
+                FPPredicate::OLE => compare("fp.leq"),
+                FPPredicate::ONE => Sexp::s3(
+                    "and",
+                    Sexp::s2("not", unordered.clone()),
+                    Sexp::s2("not", compare("fp.eq")),
+                ),
+                FPPredicate::ORD => Sexp::s2("not", unordered),
+                FPPredicate::UNO => unordered,
+                FPPredicate::UEQ => Sexp::s3("or", unordered, compare("fp.eq")),
+                FPPredicate::UGT => Sexp::s3("or", unordered, compare("fp.gt")),
+                FPPredicate::UGE => Sexp::s3("or", unordered, compare("fp.geq")),
+                FPPredicate::ULT => Sexp::s3("or", unordered, compare("fp.lt")),
+                FPPredicate::ULE => Sexp::s3("or", unordered, compare("fp.leq")),
+                FPPredicate::UNE => Sexp::s2("not", compare("fp.eq")),
+                FPPredicate::True => "true".to_sexp(),
+            };
+            (if_then_else(r, "#x01", "#x00"), vec![a, b])
+        })
+    }
+
+    /// Result of `fneg`, which only flips the sign bit of each lane.
+    pub fn float_negate(&self, ty: &TypeRef, value: Sexp) -> Sexp {
+        self.lane_wise(vec![(value, ty.clone())], |lane_ty, x| {
+            let high = self.value_bits(lane_ty) - 1;
+            Sexp::s3(
+                "concat",
+                Sexp::s2("bvnot", extract(x[0].clone(), high, high)),
+                extract(x[0].clone(), high - 1, 0),
+            )
+        })
+    }
+
+    /// Result of `fptrunc`, `fpext`, `fptosi`, `fptoui`, `sitofp` or `uitofp`, named by
+    /// `op`, converting from `ty` to `to_ty`, and the mask of its poison lanes. Converting
+    /// a float that does not fit into an integer gives poison.
+    pub fn float_cast(&self, op: &str, ty: &TypeRef, to_ty: &TypeRef, value: Sexp) -> (Sexp, Sexp) {
+        let to_lane_ty = self.lane_type(to_ty);
+        if let "sitofp" | "uitofp" = op {
+            let (exponent, significand) = self.float_format(&to_lane_ty);
+            let conversion = match op {
+                "sitofp" => "to_fp",
+                _ => "to_fp_unsigned",
+            };
+            // Narrow integers are converted from their declared width, so that the sign
+            // of `sitofp` is the top bit of that.
+            let r = self.lane_wise(vec![(value, ty.clone())], |lane_ty, x| {
+                let r = Sexp::s3(
+                    Sexp::s4(
+                        "_",
+                        conversion,
+                        &*exponent.to_string(),
+                        &*significand.to_string(),
+                    ),
+                    "RNE",
+                    self.narrowed(lane_ty, x[0].clone()),
+                );
+                Sexp::s2("fp.to_ieee_bv", r)
+            });
+            return (r, self.no_poison(to_ty));
+        }
+        let fits = |lane_ty: &TypeRef, x: Sexp| {
+            let to_bits = self.int_bits(&to_lane_ty);
+            let (low, high) = self.integer_range(lane_ty, to_bits, op == "fptosi");
+            let truncated = Sexp::s3("fp.roundToIntegral", "RTZ", x);
+            Sexp::s3(
+                "and",
+                Sexp::s3("fp.geq", truncated.clone(), low),
+                Sexp::s3("fp.lt", truncated, high),
+            )
+        };
+        let r = self.lane_wise(vec![(value.clone(), ty.clone())], |lane_ty, x| {
+            let x = self.to_float(x[0].clone(), lane_ty);
+            match op {
+                "fptrunc" | "fpext" => {
+                    let (exponent, significand) = self.float_format(&to_lane_ty);
+                    let to_fp = Sexp::s4(
+                        "_",
+                        "to_fp",
+                        &*exponent.to_string(),
+                        &*significand.to_string(),
+                    );
+                    Sexp::s2("fp.to_ieee_bv", Sexp::s3(to_fp, "RNE", x))
+                }
+                _ => {
+                    let to_bv = match op == "fptosi" {
+                        true => "fp.to_sbv",
+                        false => "fp.to_ubv",
+                    };
+                    let to_bits = self.int_bits(&to_lane_ty);
+                    let to_bv = Sexp::s3("_", to_bv, &*to_bits.to_string());
+                    self.widened(&to_lane_ty, Sexp::s3(to_bv, "RTZ", x))
+                }
+            }
+        });
+        let poison = match op {
+            "fptrunc" | "fpext" => self.no_poison(to_ty),
+            _ => self.lane_wise(vec![(value, ty.clone())], |lane_ty, x| {
+                let x = self.to_float(x[0].clone(), lane_ty);
+                self.poison_if(&to_lane_ty, Sexp::s2("not", fits(lane_ty, x)))
+            }),
+        };
+        (r, poison)
+    }
+
+    /// The smallest integer of `bits` bits and the one after the largest, as floats of
+    /// type `ty`. Both are powers of two, so they are exact unless out of range.
+    fn integer_range(&self, ty: &TypeRef, bits: usize, signed: bool) -> (Sexp, Sexp) {
+        let (exponent, significand) = self.float_format(ty);
+        let to_fp = |conversion| {
+            Sexp::s4(
+                "_",
+                conversion,
+                &*exponent.to_string(),
+                &*significand.to_string(),
+            )
+        };
+        // `bits + 1` bits with only the top one set, which is 2^bits when unsigned.
+        let top_bit = Sexp::s3("concat", "#b1", Sexp::s3("_", "bv0", &*bits.to_string()));
+        let above = Sexp::s3(to_fp("to_fp_unsigned"), "RNE", top_bit);
+        match signed {
+            true => {
+                let half = Sexp::s4(
+                    "fp.div",
+                    "RNE",
+                    above,
+                    Sexp::s3(to_fp("to_fp_unsigned"), "RNE", "#b10"),
+                );
+                (Sexp::s2("fp.neg", half.clone()), half)
+            }
+            false => (Sexp::s3(to_fp("to_fp_unsigned"), "RNE", "#b0"), above),
+        }
+    }
+}
+
+/// The remainder of `frem`, which has the sign of the dividend, unlike the IEEE
+/// remainder which is computed from the nearest quotient instead of the truncated one.
+fn remainder(a: Sexp, b: Sexp) -> Sexp {
+    let r = Sexp::s3("fp.rem", a.clone(), b.clone());
+    let same_sign = Sexp::List(vec![
+        "or".to_sexp(),
+        Sexp::s2("fp.isZero", r.clone()),
+        Sexp::s2("fp.isNaN", r.clone()),
+        Sexp::s3(
+            "=",
+            Sexp::s2("fp.isNegative", r.clone()),
+            Sexp::s2("fp.isNegative", a.clone()),
+        ),
+    ]);
+    let magnitude = Sexp::s2("fp.abs", b);
+    let step = if_then_else(
+        Sexp::s2("fp.isNegative", a),
+        Sexp::s2("fp.neg", magnitude.clone()),
+        magnitude,
+    );
+    if_then_else(same_sign, r.clone(), Sexp::s4("fp.add", "RNE", r, step))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::simplify;
+
+    fn single(bits: &str) -> Sexp {
+        Sexp::s2(Sexp::s4("_", "to_fp", "8", "24"), bits)
+    }
+
+    #[test]
+    fn flags_follow_the_opcode() {
+        assert_eq!(
+            instruction_flags("%r = fadd nnan nsz float %a, %b"),
+            ("fadd", vec!["nnan", "nsz"])
+        );
+        assert_eq!(
+            instruction_flags("%r = tail call fast float @llvm.sqrt.f32(float %a)"),
+            ("call", vec!["fast"])
+        );
+        assert!(instruction_flags("%r = fmul float %a, %b").1.is_empty());
+        assert!(instruction_flags("store float %a, ptr %nnan").1.is_empty());
+    }
+
+    #[test]
+    fn flags_by_result() {
+        let enabled = FastMath::parse("nnan,ninf");
+        let (dest, r) = checked_flags("%3 = fcmp ninf oeq float %a, %b", enabled).unwrap();
+        assert_eq!(dest, Name::Number(3));
+        assert!(!r.nnan && r.ninf);
+        assert!(checked_flags("%r = fadd float %a, %b", enabled).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "is not enabled")]
+    fn refuse_flags_that_are_not_enabled() {
+        checked_flags("%r = fadd nnan float %a, %b", FastMath::parse("ninf"));
+    }
+
+    #[test]
+    #[should_panic(expected = "assumes of every floating point operation")]
+    fn refuse_operations_without_nsz() {
+        checked_flags("%r = fadd nnan float %a, %b", FastMath::parse("nnan,nsz"));
+    }
+
+    /// Whether `frem` of the floats with the given bits gives the expected bits.
+    fn remainder_is(a: &str, b: &str, expected: &str) -> bool {
+        let r = remainder(single(a), single(b));
+        simplify(Sexp::s3("=", r, single(expected))) == "true"
+    }
+
+    #[test]
+    fn remainder_has_the_sign_of_the_dividend() {
+        // 5 % 3 is 2, where the IEEE remainder is -1.
+        assert!(remainder_is("#x40a00000", "#x40400000", "#x40000000"));
+        // -5 % 3 is -2.
+        assert!(remainder_is("#xc0a00000", "#x40400000", "#xc0000000"));
+        // 5 % -3 is 2.
+        assert!(remainder_is("#x40a00000", "#xc0400000", "#x40000000"));
+        // 5.5 % 2 is 1.5.
+        assert!(remainder_is("#x40b00000", "#x40000000", "#x3fc00000"));
+    }
+
+    #[test]
+    fn remainder_keeps_the_sign_of_zero() {
+        // -4 % 2 is -0.
+        assert!(remainder_is("#xc0800000", "#x40000000", "#x80000000"));
+        assert!(!remainder_is("#xc0800000", "#x40000000", "#x00000000"));
+    }
+
+    #[test]
+    fn parse_fast_math_flags() {
+        let r = FastMath::parse("nnan,nsz");
+        assert!(r.nnan && !r.ninf && r.nsz);
+    }
+
+    #[test]
+    #[should_panic(expected = "not supported")]
+    fn refuse_unmodeled_fast_math_flags() {
+        FastMath::parse("nnan,reassoc");
+    }
+}
@@ -1,59 +1,420 @@
-use llvm_ir::{instruction::Call, terminator::CondBr, Function, Operand};
+use std::{collections::HashMap, rc::Rc};
 
-use crate::{sexp::Sexp, z3_decl::if_then_else, MemorySnapshot, VerifierState};
+use llvm_ir::{
+    instruction::{Call, GetElementPtr},
+    terminator::CondBr,
+    Constant, Function, IntPredicate, Name, Operand, Type, TypeRef,
+};
 
-#[derive(Debug, Clone, Copy)]
+use crate::{
+    cfg, intrinsics,
+    memory::NULL_BLOCK,
+    poison,
+    sexp::{Sexp, ToSexp},
+    z3_decl::{bv_hex, if_then_else, resize, BLOCK_BITS},
+    MemorySnapshot, Side, VerifierState,
+};
+
+#[derive(Debug, Clone)]
 pub struct Position {
     pub bb: usize,
     pub instr: usize,
+    /// Calls that are being executed inline, innermost last.
+    pub stack: Vec<InlinedCall>,
+    /// How many times each loop header has been entered through a back-edge, by frame.
+    pub iterations: HashMap<(usize, usize), usize>,
+    /// Set when the position was reached by entering a loop header of the top function,
+    /// so that running from it first reports `Effect::LoopHeader`.
+    pub loop_header: Option<LoopEntry>,
+    /// Set when an exception passed through a call that does not catch it, so that
+    /// running from the position reports `Effect::Resume` with the exception in flight.
+    pub unwinding: bool,
+}
+
+impl Position {
+    pub fn start() -> Self {
+        Position {
+            bb: 0,
+            instr: 0,
+            stack: vec![],
+            iterations: HashMap::new(),
+            loop_header: None,
+            unwinding: false,
+        }
+    }
+
+    /// The start of a basic block, after its phis.
+    pub fn at(f: &Function, bb: usize) -> Self {
+        let phis = f.basic_blocks[bb]
+            .instrs
+            .iter()
+            .take_while(|x| matches!(x, llvm_ir::Instruction::Phi(_)))
+            .count();
+        Position {
+            bb,
+            instr: phis,
+            ..Position::start()
+        }
+    }
+}
+
+/// How a loop header was entered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoopEntry {
+    /// From outside the loop, like from its preheader.
+    Fresh,
+    /// Through a back-edge, coming around the loop.
+    BackEdge,
+}
+
+#[derive(Debug, Clone)]
+pub struct InlinedCall {
+    pub function: Rc<Function>,
+    pub dest: Option<Name>,
+    pub caller_frame: usize,
+    pub return_bb: usize,
+    pub return_instr: usize,
 }
 
 #[derive(Debug, Clone)]
 pub enum Effect {
-    Call { return_pos: Position, call: Call },
+    Call {
+        return_pos: Position,
+        call: Call,
+        /// For `invoke`, the blocks to continue at when the callee returns and when it
+        /// unwinds. `return_pos` is then at the `invoke` itself.
+        labels: Option<(Name, Name)>,
+    },
     Return(Option<Operand>),
-    CondBr(CondBr),
+    /// A function that never returns normally, like a panic, was called.
+    Diverge {
+        function: String,
+        arguments: Vec<Operand>,
+    },
+    /// The function unwinds with the given exception, of the given sort.
+    Resume {
+        value: Sexp,
+        sort: Sexp,
+    },
+    CondBr {
+        br: CondBr,
+        pos: Position,
+    },
+    /// A loop would run more iterations than the unroll bound allows.
+    UnrollBoundReached,
+    /// A loop header of the top function was entered. Running from `pos` continues
+    /// into the header.
+    LoopHeader {
+        pos: Position,
+        entry: LoopEntry,
+    },
 }
 
 impl VerifierState {
     pub fn run_until_effect(
         &mut self,
-        f: Function,
+        side: Side,
         p: Position,
         mut memory: MemorySnapshot,
     ) -> (MemorySnapshot, Effect) {
+        if let Some(entry) = p.loop_header {
+            let pos = Position {
+                loop_header: None,
+                ..p
+            };
+            return (memory, Effect::LoopHeader { pos, entry });
+        }
+        if p.unwinding {
+            let (value, sort) = self
+                .exception
+                .clone()
+                .expect("unwinding without an exception");
+            return (memory, Effect::Resume { value, sort });
+        }
+        let top = self.function(side);
+        let inlined = p.stack.last().map(|x| x.function.clone());
+        let f = inlined.as_deref().unwrap_or(&top);
         let bb = &f.basic_blocks[p.bb];
         if p.instr == bb.instrs.len() {
             match &bb.term {
                 llvm_ir::Terminator::Ret(ret) => {
-                    return (memory, Effect::Return(ret.return_operand.clone()))
+                    let mut stack = p.stack;
+                    let Some(frame) = stack.pop() else {
+                        return (memory, Effect::Return(ret.return_operand.clone()));
+                    };
+                    let value = ret.return_operand.as_ref().map(|x| {
+                        let poison = self.operand_poison(x, memory);
+                        (
+                            self.operand_to_sexp(side, x, memory),
+                            poison,
+                            self.type_of(x),
+                        )
+                    });
+                    memory.frame = frame.caller_frame;
+                    if let Some((value, _, ty)) = &value {
+                        // Returned pointers escape the callee, like captured ones.
+                        self.capture(self.pointer_blocks(ty, value.clone()));
+                    }
+                    if let (Some(dest), Some((value, poison, ty))) = (&frame.dest, value) {
+                        memory = self.store_register_with_poison(dest, &ty, value, poison, memory);
+                    }
+                    let next_pos = Position {
+                        bb: frame.return_bb,
+                        instr: frame.return_instr,
+                        stack,
+                        iterations: p.iterations,
+                        loop_header: None,
+                        unwinding: false,
+                    };
+                    return self.run_until_effect(side, next_pos, memory);
+                }
+                llvm_ir::Terminator::Br(br) => {
+                    let Some((next_pos, memory)) = self.jump(side, &p, &br.dest, memory) else {
+                        return (memory, Effect::UnrollBoundReached);
+                    };
+                    return self.run_until_effect(side, next_pos, memory);
                 }
                 llvm_ir::Terminator::CondBr(cond_br) => {
-                    return (memory, Effect::CondBr(cond_br.clone()));
+                    let message = "Branch on poison in new";
+                    self.assume_not_poison(side, &cond_br.condition, memory, message);
+                    let br = cond_br.clone();
+                    return (memory, Effect::CondBr { br, pos: p });
+                }
+                llvm_ir::Terminator::Invoke(invoke)
+                    if invoke
+                        .function
+                        .as_ref()
+                        .right()
+                        .is_some_and(|x| self.is_diverging(x)) =>
+                {
+                    let function = invoke.function.as_ref().right().unwrap();
+                    let function = self.function_name(function).unwrap();
+                    let arguments = invoke.arguments.iter().map(|(x, _)| x.clone()).collect();
+                    return (
+                        memory,
+                        Effect::Diverge {
+                            function,
+                            arguments,
+                        },
+                    );
+                }
+                llvm_ir::Terminator::Invoke(invoke) => {
+                    let Type::FuncType { result_type, .. } = &*invoke.function_ty else {
+                        panic!("invoke of non function type {}", invoke.function_ty);
+                    };
+                    let call = Call {
+                        function: invoke.function.clone(),
+                        function_ty: invoke.function_ty.clone(),
+                        arguments: invoke.arguments.clone(),
+                        return_attributes: invoke.return_attributes.clone(),
+                        dest: match &**result_type {
+                            Type::VoidType => None,
+                            _ => Some(invoke.result.clone()),
+                        },
+                        function_attributes: invoke.function_attributes.clone(),
+                        is_tail_call: false,
+                        calling_convention: invoke.calling_convention,
+                        debugloc: invoke.debugloc.clone(),
+                    };
+                    let labels =
+                        Some((invoke.return_label.clone(), invoke.exception_label.clone()));
+                    return (
+                        memory,
+                        Effect::Call {
+                            return_pos: p,
+                            call,
+                            labels,
+                        },
+                    );
+                }
+                llvm_ir::Terminator::Resume(resume) => {
+                    // Calls are only inlined when they are not invoked, so the exception
+                    // leaves the top function as well.
+                    let value = self.operand_to_sexp(side, &resume.operand, memory);
+                    let sort = self.sort_of_ty(&self.type_of(&resume.operand));
+                    return (memory, Effect::Resume { value, sort });
                 }
                 _ => unimplemented!("{:?}", bb.term),
             }
         }
         for (instr_id, instr) in bb.instrs.iter().enumerate().skip(p.instr) {
             macro_rules! binop_instr {
-                ($x:ident, $z3fn:expr) => {{
-                    let o0 = self.operand_to_sexp(&$x.operand0, memory);
-                    let o1 = self.operand_to_sexp(&$x.operand1, memory);
-                    let o = Sexp::s3($z3fn, o0, o1);
-                    let size = self.size_of_operand(&$x.operand0);
-                    let addr = self.address_of_name(&$x.dest);
-                    let next_memory = self.store_in_addr(addr, size, o, memory);
-                    memory = next_memory;
+                ($x:ident, $z3fn:expr) => {
+                    binop_instr!($x, $z3fn, vec![])
+                };
+                // `overflowing` names the operations, like `sadd`, whose overflow makes
+                // the result poison.
+                ($x:ident, $z3fn:expr, $overflowing:expr) => {{
+                    let ty = self.type_of(instr);
+                    let o0 = self.operand_to_sexp(side, &$x.operand0, memory);
+                    let o1 = self.operand_to_sexp(side, &$x.operand1, memory);
+                    let operands = vec![(o0, ty.clone()), (o1, ty.clone())];
+                    let o = self.lane_wise(operands.clone(), |_, x| {
+                        Sexp::s3($z3fn, x[0].clone(), x[1].clone())
+                    });
+                    let overflowing: Vec<String> = $overflowing;
+                    let overflow = self.lane_wise(operands, |lane_ty, x| {
+                        let bits = self.int_bits(lane_ty);
+                        let a = self.narrowed(lane_ty, x[0].clone());
+                        let b = self.narrowed(lane_ty, x[1].clone());
+                        let overflows = overflowing
+                            .iter()
+                            .map(|op| intrinsics::overflowing(op, a.clone(), b.clone(), bits).1)
+                            .collect();
+                        self.poison_if(lane_ty, poison::any(overflows))
+                    });
+                    let poison = self.lane_poison(
+                        &ty,
+                        vec![
+                            (self.operand_poison(&$x.operand0, memory), ty.clone()),
+                            (self.operand_poison(&$x.operand1, memory), ty.clone()),
+                            (overflow, ty.clone()),
+                        ],
+                    );
+                    memory = self.store_register_with_poison(&$x.dest, &ty, o, poison, memory);
+                }};
+            }
+            // Shifts by at least the width give poison. `undo` names the shifts that have
+            // to give back the first operand from the result, or else it is poison.
+            macro_rules! shift_instr {
+                ($x:ident, $z3fn:expr, $undo:expr) => {{
+                    let ty = self.type_of(instr);
+                    let o0 = self.operand_to_sexp(side, &$x.operand0, memory);
+                    let o1 = self.operand_to_sexp(side, &$x.operand1, memory);
+                    let operands = vec![(o0, ty.clone()), (o1, ty.clone())];
+                    let o = self.lane_wise(operands.clone(), |lane_ty, x| {
+                        let a = self.narrowed(lane_ty, x[0].clone());
+                        let b = self.narrowed(lane_ty, x[1].clone());
+                        self.widened(lane_ty, Sexp::s3($z3fn, a, b))
+                    });
+                    let undo: Vec<&str> = $undo;
+                    let invalid = self.lane_wise(operands, |lane_ty, x| {
+                        let bits = self.int_bits(lane_ty);
+                        let a = self.narrowed(lane_ty, x[0].clone());
+                        let b = self.narrowed(lane_ty, x[1].clone());
+                        let r = Sexp::s3($z3fn, a.clone(), b.clone());
+                        let width = Sexp::s3("_", &*format!("bv{bits}"), &*bits.to_string());
+                        let mut invalid = vec![Sexp::s3("bvuge", b.clone(), width)];
+                        invalid.extend(undo.iter().map(|op| {
+                            let undone = Sexp::s3(*op, r.clone(), b.clone());
+                            Sexp::s3("distinct", undone, a.clone())
+                        }));
+                        self.poison_if(lane_ty, poison::any(invalid))
+                    });
+                    let poison = self.lane_poison(
+                        &ty,
+                        vec![
+                            (self.operand_poison(&$x.operand0, memory), ty.clone()),
+                            (self.operand_poison(&$x.operand1, memory), ty.clone()),
+                            (invalid, ty.clone()),
+                        ],
+                    );
+                    memory = self.store_register_with_poison(&$x.dest, &ty, o, poison, memory);
                 }};
             }
+            // `op` gets the lane types of the operand and the result, and the operand.
+            macro_rules! int_cast_instr {
+                ($x:ident, $op:expr) => {{
+                    let value = self.operand_to_sexp(side, &$x.operand, memory);
+                    let ty = self.type_of(&$x.operand);
+                    let to_lane_ty = self.lane_type(&$x.to_type);
+                    let r = self.lane_wise(vec![(value, ty)], |lane_ty, x| {
+                        $op(lane_ty, &to_lane_ty, x[0].clone())
+                    });
+                    let poison = cast_poison!($x);
+                    memory =
+                        self.store_register_with_poison(&$x.dest, &$x.to_type, r, poison, memory);
+                }};
+            }
+            macro_rules! float_instr {
+                ($x:ident, $op:expr) => {{
+                    let ty = self.type_of(instr);
+                    let o0 = self.operand_to_sexp(side, &$x.operand0, memory);
+                    let o1 = self.operand_to_sexp(side, &$x.operand1, memory);
+                    let fast_math = self.fast_math(f, &$x.dest);
+                    let (o, flags) = self.float_arithmetic($op, &ty, vec![o0, o1], fast_math);
+                    let poison = self.lane_poison(
+                        &ty,
+                        vec![
+                            (self.operand_poison(&$x.operand0, memory), ty.clone()),
+                            (self.operand_poison(&$x.operand1, memory), ty.clone()),
+                            (flags, ty.clone()),
+                        ],
+                    );
+                    memory = self.store_register_with_poison(&$x.dest, &ty, o, poison, memory);
+                }};
+            }
+            macro_rules! float_cast_instr {
+                ($x:ident, $op:expr) => {{
+                    let value = self.operand_to_sexp(side, &$x.operand, memory);
+                    let ty = self.type_of(&$x.operand);
+                    let (r, range) = self.float_cast($op, &ty, &$x.to_type, value);
+                    let poison = self.lane_poison(
+                        &$x.to_type,
+                        vec![
+                            (self.operand_poison(&$x.operand, memory), ty),
+                            (range, $x.to_type.clone()),
+                        ],
+                    );
+                    memory =
+                        self.store_register_with_poison(&$x.dest, &$x.to_type, r, poison, memory);
+                }};
+            }
+            macro_rules! cast_poison {
+                ($x:ident) => {
+                    self.lane_poison(
+                        &$x.to_type,
+                        vec![(
+                            self.operand_poison(&$x.operand, memory),
+                            self.type_of(&$x.operand),
+                        )],
+                    )
+                };
+            }
             match instr {
-                llvm_ir::Instruction::Add(add) => binop_instr!(add, "bvadd"),
+                llvm_ir::Instruction::Add(add) => {
+                    binop_instr!(add, "bvadd", wrap_flags("add", add.nsw, add.nuw))
+                }
                 llvm_ir::Instruction::And(and) => binop_instr!(and, "bvand"),
-                llvm_ir::Instruction::Sub(sub) => binop_instr!(sub, "bvsub"),
+                llvm_ir::Instruction::Or(or) => binop_instr!(or, "bvor"),
+                llvm_ir::Instruction::Xor(xor) => binop_instr!(xor, "bvxor"),
+                llvm_ir::Instruction::Sub(sub) => {
+                    binop_instr!(sub, "bvsub", wrap_flags("sub", sub.nsw, sub.nuw))
+                }
+                llvm_ir::Instruction::Mul(mul) => {
+                    binop_instr!(mul, "bvmul", wrap_flags("mul", mul.nsw, mul.nuw))
+                }
+                llvm_ir::Instruction::Shl(shl) => {
+                    let undo = [(shl.nsw, "bvashr"), (shl.nuw, "bvlshr")];
+                    shift_instr!(shl, "bvshl", flagged(&undo))
+                }
+                llvm_ir::Instruction::LShr(lshr) => {
+                    shift_instr!(lshr, "bvlshr", flagged(&[(lshr.exact, "bvshl")]))
+                }
+                llvm_ir::Instruction::AShr(ashr) => {
+                    shift_instr!(ashr, "bvashr", flagged(&[(ashr.exact, "bvshl")]))
+                }
+                llvm_ir::Instruction::Trunc(cast) => {
+                    int_cast_instr!(cast, |from: &TypeRef, to: &TypeRef, x| {
+                        resize(x, self.value_bits(from), self.value_bits(to), false)
+                    })
+                }
+                llvm_ir::Instruction::ZExt(cast) => {
+                    int_cast_instr!(cast, |from: &TypeRef, to: &TypeRef, x| {
+                        resize(x, self.value_bits(from), self.value_bits(to), false)
+                    })
+                }
+                llvm_ir::Instruction::SExt(cast) => {
+                    int_cast_instr!(cast, |from: &TypeRef, to: &TypeRef, x| {
+                        let x = self.narrowed(from, x);
+                        let bits = self.int_bits(to);
+                        self.widened(to, resize(x, self.int_bits(from), bits, true))
+                    })
+                }
                 llvm_ir::Instruction::ICmp(icmp) => {
                     let operation = match icmp.predicate {
                         llvm_ir::IntPredicate::EQ => "=",
-                        llvm_ir::IntPredicate::NE => todo!(),
+                        llvm_ir::IntPredicate::NE => "distinct",
                         llvm_ir::IntPredicate::UGT => "bvugt",
                         llvm_ir::IntPredicate::UGE => "bvuge",
                         llvm_ir::IntPredicate::ULT => "bvult",
@@ -63,40 +424,740 @@ impl VerifierState {
                         llvm_ir::IntPredicate::SLT => "bvslt",
                         llvm_ir::IntPredicate::SLE => "bvsle",
                     };
-                    let o0 = self.operand_to_sexp(&icmp.operand0, memory);
-                    let o1 = self.operand_to_sexp(&icmp.operand1, memory);
-                    let r = if_then_else(Sexp::s3(operation, o0, o1), "#x01", "#x00");
-                    let addr = self.address_of_name(&icmp.dest);
-                    memory = self.store_in_addr(addr, 1, r, memory);
+                    let o0 = self.operand_to_sexp(side, &icmp.operand0, memory);
+                    let o1 = self.operand_to_sexp(side, &icmp.operand1, memory);
+                    let ty = self.type_of(&icmp.operand0);
+                    let operands = vec![(o0, ty.clone()), (o1, ty.clone())];
+                    let r = self.lane_wise(operands, |ty, mut x| {
+                        if let Type::PointerType { .. } = &**ty {
+                            let bits = self.pointer_bits(ty);
+                            let address = |x: &Sexp| self.ptr_to_int(x.clone(), ty, bits);
+                            if !matches!(icmp.predicate, IntPredicate::EQ | IntPredicate::NE) {
+                                // Only the addresses of pointers are ordered, not their provenance.
+                                x = x.iter().map(address).collect();
+                            } else {
+                                // Pointers into different blocks are never equal, but one
+                                // made up from an integer equals any with the same address.
+                                let null = bv_hex(NULL_BLOCK, BLOCK_BITS / 8);
+                                let by_address = poison::any(
+                                    x.iter()
+                                        .map(|x| {
+                                            let block = self.pointer_block(x.clone(), ty);
+                                            Sexp::s3("=", block, null.clone())
+                                        })
+                                        .collect(),
+                                );
+                                x = x
+                                    .iter()
+                                    .map(|x| {
+                                        let made_up = self.int_to_ptr(address(x), bits, ty);
+                                        if_then_else(by_address.clone(), made_up, x.clone())
+                                    })
+                                    .collect();
+                            }
+                        } else {
+                            // The sign of a narrow integer is the top bit of its declared width.
+                            x = x.into_iter().map(|x| self.narrowed(ty, x)).collect();
+                        }
+                        if_then_else(
+                            Sexp::s3(operation, x[0].clone(), x[1].clone()),
+                            "#x01",
+                            "#x00",
+                        )
+                    });
+                    let result_ty = self.type_of(instr);
+                    let poison = self.lane_poison(
+                        &result_ty,
+                        vec![
+                            (self.operand_poison(&icmp.operand0, memory), ty.clone()),
+                            (self.operand_poison(&icmp.operand1, memory), ty),
+                        ],
+                    );
+                    memory =
+                        self.store_register_with_poison(&icmp.dest, &result_ty, r, poison, memory);
                 }
+                llvm_ir::Instruction::FAdd(fadd) => float_instr!(fadd, "fadd"),
+                llvm_ir::Instruction::FSub(fsub) => float_instr!(fsub, "fsub"),
+                llvm_ir::Instruction::FMul(fmul) => float_instr!(fmul, "fmul"),
+                llvm_ir::Instruction::FDiv(fdiv) => float_instr!(fdiv, "fdiv"),
+                llvm_ir::Instruction::FRem(frem) => float_instr!(frem, "frem"),
+                llvm_ir::Instruction::FNeg(fneg) => {
+                    let value = self.operand_to_sexp(side, &fneg.operand, memory);
+                    let ty = self.type_of(instr);
+                    let r = self.float_negate(&ty, value);
+                    let poison = self.operand_poison(&fneg.operand, memory);
+                    memory = self.store_register_with_poison(&fneg.dest, &ty, r, poison, memory);
+                }
+                llvm_ir::Instruction::FCmp(fcmp) => {
+                    let o0 = self.operand_to_sexp(side, &fcmp.operand0, memory);
+                    let o1 = self.operand_to_sexp(side, &fcmp.operand1, memory);
+                    let ty = self.type_of(&fcmp.operand0);
+                    let result_ty = self.type_of(instr);
+                    let fast_math = self.fast_math(f, &fcmp.dest);
+                    let (r, flags) = self.float_compare(
+                        fcmp.predicate,
+                        &ty,
+                        &result_ty,
+                        vec![o0, o1],
+                        fast_math,
+                    );
+                    let poison = self.lane_poison(
+                        &result_ty,
+                        vec![
+                            (self.operand_poison(&fcmp.operand0, memory), ty.clone()),
+                            (self.operand_poison(&fcmp.operand1, memory), ty),
+                            (flags, result_ty.clone()),
+                        ],
+                    );
+                    memory =
+                        self.store_register_with_poison(&fcmp.dest, &result_ty, r, poison, memory);
+                }
+                llvm_ir::Instruction::FPTrunc(cast) => float_cast_instr!(cast, "fptrunc"),
+                llvm_ir::Instruction::FPExt(cast) => float_cast_instr!(cast, "fpext"),
+                llvm_ir::Instruction::FPToSI(cast) => float_cast_instr!(cast, "fptosi"),
+                llvm_ir::Instruction::FPToUI(cast) => float_cast_instr!(cast, "fptoui"),
+                llvm_ir::Instruction::SIToFP(cast) => float_cast_instr!(cast, "sitofp"),
+                llvm_ir::Instruction::UIToFP(cast) => float_cast_instr!(cast, "uitofp"),
                 llvm_ir::Instruction::Select(select) => {
-                    let condition = self.operand_to_sexp(&select.condition, memory);
-                    let otrue = self.operand_to_sexp(&select.true_value, memory);
-                    let ofalse = self.operand_to_sexp(&select.false_value, memory);
-                    let r = if_then_else(Sexp::s3("=", condition, "#x00"), ofalse, otrue);
-                    let addr = self.address_of_name(&select.dest);
-                    let size = self.size_of_operand(&select.true_value);
-                    memory = self.store_in_addr(addr, size, r, memory);
+                    let condition = self.operand_to_sexp(side, &select.condition, memory);
+                    let otrue = self.operand_to_sexp(side, &select.true_value, memory);
+                    let ofalse = self.operand_to_sexp(side, &select.false_value, memory);
+                    let ty = self.type_of(instr);
+                    let condition_ty = self.type_of(&select.condition);
+                    let operands = vec![
+                        (condition.clone(), condition_ty.clone()),
+                        (otrue, ty.clone()),
+                        (ofalse, ty.clone()),
+                    ];
+                    let r = self.lane_wise(operands, |_, x| {
+                        if_then_else(
+                            Sexp::s3("=", x[0].clone(), "#x00"),
+                            x[2].clone(),
+                            x[1].clone(),
+                        )
+                    });
+                    // Poison in the arm that is not chosen does not matter, but a poison
+                    // condition makes the lane poison.
+                    let lane_ty = self.lane_type(&ty);
+                    let masks = vec![
+                        (condition, condition_ty.clone()),
+                        (self.operand_poison(&select.condition, memory), condition_ty),
+                        (self.operand_poison(&select.true_value, memory), ty.clone()),
+                        (self.operand_poison(&select.false_value, memory), ty.clone()),
+                    ];
+                    let poison = self.lane_wise(masks, |_, x| {
+                        if_then_else(
+                            poison::is_poison(x[1].clone()),
+                            Sexp::s2("bvnot", self.no_poison(&lane_ty)),
+                            if_then_else(
+                                Sexp::s3("=", x[0].clone(), "#x00"),
+                                x[3].clone(),
+                                x[2].clone(),
+                            ),
+                        )
+                    });
+                    memory = self.store_register_with_poison(&select.dest, &ty, r, poison, memory);
+                }
+                llvm_ir::Instruction::Freeze(freeze) => {
+                    let ty = self.type_of(instr);
+                    let value = self.operand_to_sexp(side, &freeze.operand, memory);
+                    let poison = self.operand_poison(&freeze.operand, memory);
+                    let r = self.freeze(side, &ty, value, poison);
+                    memory = self.store_register(&freeze.dest, &ty, r, memory);
+                }
+                llvm_ir::Instruction::ExtractElement(extract) => {
+                    let vector = self.operand_to_sexp(side, &extract.vector, memory);
+                    let index = self.operand_to_sexp(side, &extract.index, memory);
+                    let index_bits = self.value_bits(&self.type_of(&extract.index));
+                    let ty = self.type_of(&extract.vector);
+                    let r = self.lane_at(vector, &ty, index.clone(), index_bits);
+                    let result_ty = self.type_of(instr);
+                    let mask = self.operand_poison(&extract.vector, memory);
+                    let poison = if_then_else(
+                        self.lane_index_poison(side, &ty, &extract.index, memory),
+                        Sexp::s2("bvnot", self.no_poison(&result_ty)),
+                        self.lane_at(mask, &ty, index, index_bits),
+                    );
+                    memory = self.store_register_with_poison(
+                        &extract.dest,
+                        &result_ty,
+                        r,
+                        poison,
+                        memory,
+                    );
+                }
+                llvm_ir::Instruction::InsertElement(insert) => {
+                    let vector = self.operand_to_sexp(side, &insert.vector, memory);
+                    let element = self.operand_to_sexp(side, &insert.element, memory);
+                    let index = self.operand_to_sexp(side, &insert.index, memory);
+                    let index_bits = self.value_bits(&self.type_of(&insert.index));
+                    let ty = self.type_of(&insert.vector);
+                    let r = self.insert_lane_at(vector, &ty, index.clone(), index_bits, element);
+                    let mask = self.operand_poison(&insert.vector, memory);
+                    let element_mask = self.operand_poison(&insert.element, memory);
+                    let poison = if_then_else(
+                        self.lane_index_poison(side, &ty, &insert.index, memory),
+                        Sexp::s2("bvnot", self.no_poison(&ty)),
+                        self.insert_lane_at(mask, &ty, index, index_bits, element_mask),
+                    );
+                    memory = self.store_register_with_poison(&insert.dest, &ty, r, poison, memory);
+                }
+                llvm_ir::Instruction::ShuffleVector(shuffle) => {
+                    let first = self.operand_to_sexp(side, &shuffle.operand0, memory);
+                    let second = self.operand_to_sexp(side, &shuffle.operand1, memory);
+                    let ty = self.type_of(&shuffle.operand0);
+                    let result_ty = self.type_of(instr);
+                    let undefined = self.arbitrary_value(&result_ty);
+                    let r = self.shuffle(first, second, &ty, &shuffle.mask, &result_ty, undefined);
+                    // Lanes with an undefined mask element are poison.
+                    let poison = self.shuffle(
+                        self.operand_poison(&shuffle.operand0, memory),
+                        self.operand_poison(&shuffle.operand1, memory),
+                        &ty,
+                        &shuffle.mask,
+                        &result_ty,
+                        Sexp::s2("bvnot", self.no_poison(&result_ty)),
+                    );
+                    memory = self.store_register_with_poison(
+                        &shuffle.dest,
+                        &result_ty,
+                        r,
+                        poison,
+                        memory,
+                    );
+                }
+                llvm_ir::Instruction::ExtractValue(extract) => {
+                    let aggregate = self.operand_to_sexp(side, &extract.aggregate, memory);
+                    let ty = self.type_of(&extract.aggregate);
+                    let r = self.extract_path(aggregate, &ty, &extract.indices);
+                    let mask = self.operand_poison(&extract.aggregate, memory);
+                    let poison = self.extract_path(mask, &ty, &extract.indices);
+                    memory = self.store_register_with_poison(
+                        &extract.dest,
+                        &self.type_of(instr),
+                        r,
+                        poison,
+                        memory,
+                    );
+                }
+                llvm_ir::Instruction::InsertValue(insert) => {
+                    let aggregate = self.operand_to_sexp(side, &insert.aggregate, memory);
+                    let element = self.operand_to_sexp(side, &insert.element, memory);
+                    let ty = self.type_of(&insert.aggregate);
+                    let r = self.insert_path(aggregate, &ty, &insert.indices, element);
+                    let mask = self.operand_poison(&insert.aggregate, memory);
+                    let element_mask = self.operand_poison(&insert.element, memory);
+                    let poison = self.insert_path(mask, &ty, &insert.indices, element_mask);
+                    memory = self.store_register_with_poison(&insert.dest, &ty, r, poison, memory);
+                }
+                llvm_ir::Instruction::Alloca(alloca) => {
+                    let ty = self.type_of(instr);
+                    let block = self.alloca_block(f, &alloca.dest, memory.frame);
+                    let r = self.block_pointer(block, &ty);
+                    memory = self.store_register(&alloca.dest, &ty, r, memory);
+                }
+                llvm_ir::Instruction::Load(load) => {
+                    let message = "Load from a poison address in new";
+                    self.assume_not_poison(side, &load.address, memory, message);
+                    let ptr = self.operand_to_sexp(side, &load.address, memory);
+                    let ptr_ty = self.type_of(&load.address);
+                    let len = bv_hex(self.size_of_ty(&load.loaded_ty), 4);
+                    let frames = live_frames(&top, &p, memory.frame);
+                    let in_bounds = self.access_in_bounds(ptr.clone(), &ptr_ty, len, &frames);
+                    self.assume(side, in_bounds, "Load out of bounds in new");
+                    let addr = self.pointer_to_addr(ptr, &ptr_ty);
+                    let r = self.load_from_memory(addr.clone(), &load.loaded_ty, memory);
+                    let poison = self.memory_poison(addr, &load.loaded_ty, memory);
+                    memory = self.store_register_with_poison(
+                        &load.dest,
+                        &load.loaded_ty,
+                        r,
+                        poison,
+                        memory,
+                    );
+                }
+                llvm_ir::Instruction::Store(store) => {
+                    let message = "Store to a poison address in new";
+                    self.assume_not_poison(side, &store.address, memory, message);
+                    let ptr = self.operand_to_sexp(side, &store.address, memory);
+                    let ptr_ty = self.type_of(&store.address);
+                    let ty = self.type_of(&store.value);
+                    let len = bv_hex(self.size_of_ty(&ty), 4);
+                    let frames = live_frames(&top, &p, memory.frame);
+                    let in_bounds = self.access_in_bounds(ptr.clone(), &ptr_ty, len, &frames);
+                    self.assume(side, in_bounds, "Store out of bounds in new");
+                    let addr = self.pointer_to_addr(ptr, &ptr_ty);
+                    let value = self.operand_to_sexp(side, &store.value, memory);
+                    let poison = self.operand_poison(&store.value, memory);
+                    self.record_pointer_store(&addr, &ty, value.clone());
+                    memory = self.store_in_memory(side, addr, &ty, value, poison, memory);
+                }
+                llvm_ir::Instruction::GetElementPtr(gep) => {
+                    let (r, wraps) = self.gep_to_sexp(side, gep, memory);
+                    let ty = self.type_of(instr);
+                    let mut poison: Vec<_> = [&gep.address]
+                        .into_iter()
+                        .chain(&gep.indices)
+                        .map(|x| poison::is_poison(self.operand_poison(x, memory)))
+                        .collect();
+                    if gep.in_bounds {
+                        // Both the base and the result must be inside a live block or just
+                        // past its end, with no wrapping in between.
+                        let base = self.operand_to_sexp(side, &gep.address, memory);
+                        let frames = live_frames(&top, &p, memory.frame);
+                        let in_bounds =
+                            |ptr| self.access_in_bounds(ptr, &ty, bv_hex(0, 4), &frames);
+                        poison.push(wraps);
+                        poison.push(Sexp::s2("not", in_bounds(base)));
+                        poison.push(Sexp::s2("not", in_bounds(r.clone())));
+                    }
+                    let poison = self.poison_if(&ty, poison::any(poison));
+                    memory = self.store_register_with_poison(&gep.dest, &ty, r, poison, memory);
+                }
+                llvm_ir::Instruction::PtrToInt(cast) => {
+                    let ptr = self.operand_to_sexp(side, &cast.operand, memory);
+                    let bits = self.value_bits(&cast.to_type);
+                    let r = self.ptr_to_int(ptr, &self.type_of(&cast.operand), bits);
+                    let poison = cast_poison!(cast);
+                    memory = self.store_register_with_poison(
+                        &cast.dest,
+                        &cast.to_type,
+                        r,
+                        poison,
+                        memory,
+                    );
+                }
+                llvm_ir::Instruction::IntToPtr(cast) => {
+                    let int = self.operand_to_sexp(side, &cast.operand, memory);
+                    let bits = self.value_bits(&self.type_of(&cast.operand));
+                    let r = self.int_to_ptr(int, bits, &cast.to_type);
+                    let poison = cast_poison!(cast);
+                    memory = self.store_register_with_poison(
+                        &cast.dest,
+                        &cast.to_type,
+                        r,
+                        poison,
+                        memory,
+                    );
+                }
+                llvm_ir::Instruction::AddrSpaceCast(cast) => {
+                    let ptr = self.operand_to_sexp(side, &cast.operand, memory);
+                    let r = self.cast_pointer(ptr, &self.type_of(&cast.operand), &cast.to_type);
+                    let poison = cast_poison!(cast);
+                    memory = self.store_register_with_poison(
+                        &cast.dest,
+                        &cast.to_type,
+                        r,
+                        poison,
+                        memory,
+                    );
+                }
+                llvm_ir::Instruction::BitCast(cast) => {
+                    let from = self.type_of(&cast.operand);
+                    let r = self.operand_to_sexp(side, &cast.operand, memory);
+                    let r = self.bitcast(r, &from, &cast.to_type);
+                    // The mask is cast like the value, then a lane of the result is
+                    // poison if any of its bits were.
+                    let mask = self.operand_poison(&cast.operand, memory);
+                    let mask = self.bitcast(mask, &from, &cast.to_type);
+                    let poison =
+                        self.lane_poison(&cast.to_type, vec![(mask, cast.to_type.clone())]);
+                    memory = self.store_register_with_poison(
+                        &cast.dest,
+                        &cast.to_type,
+                        r,
+                        poison,
+                        memory,
+                    );
+                }
+                llvm_ir::Instruction::Call(call) if self.is_modeled_intrinsic(call) => {
+                    let frames = live_frames(&top, &p, memory.frame);
+                    memory = self.run_intrinsic(side, call, &frames, memory);
+                }
+                llvm_ir::Instruction::Call(call)
+                    if call
+                        .function
+                        .as_ref()
+                        .right()
+                        .is_some_and(|x| self.is_diverging(x)) =>
+                {
+                    let function = self.callee_name(call).unwrap();
+                    let arguments = call.arguments.iter().map(|(x, _)| x.clone()).collect();
+                    return (
+                        memory,
+                        Effect::Diverge {
+                            function,
+                            arguments,
+                        },
+                    );
+                }
+                // Indirect calls have no name to model the function by, so they stay effects.
+                llvm_ir::Instruction::Call(call)
+                    if self.config.pure_calls_as_functions
+                        && self.callee_name(call).is_some()
+                        && self.call_effects(call).is_pure() =>
+                {
+                    if let Some(dest) = &call.dest {
+                        let r = self.pure_call_to_sexp(side, call, memory);
+                        memory = self.store_register(dest, &self.type_of(instr), r, memory);
+                    }
+                }
+                llvm_ir::Instruction::Call(call) if self.should_inline(call, &p) => {
+                    let function = Rc::new(self.callee(call).unwrap().clone());
+                    let args: Vec<_> = call
+                        .arguments
+                        .iter()
+                        .map(|(arg, _)| {
+                            (
+                                self.operand_to_sexp(side, arg, memory),
+                                self.operand_poison(arg, memory),
+                            )
+                        })
+                        .collect();
+                    let caller_frame = memory.frame;
+                    memory.frame = self.new_frame();
+                    for (param, (arg, poison)) in function.parameters.iter().zip(args) {
+                        memory = self.store_register_with_poison(
+                            &param.name,
+                            &param.ty,
+                            arg,
+                            poison,
+                            memory,
+                        );
+                    }
+                    let mut stack = p.stack;
+                    stack.push(InlinedCall {
+                        function,
+                        dest: call.dest.clone(),
+                        caller_frame,
+                        return_bb: p.bb,
+                        return_instr: instr_id + 1,
+                    });
+                    let next_pos = Position {
+                        bb: 0,
+                        instr: 0,
+                        stack,
+                        iterations: p.iterations,
+                        loop_header: None,
+                        unwinding: false,
+                    };
+                    return self.run_until_effect(side, next_pos, memory);
                 }
                 llvm_ir::Instruction::Call(call) => {
                     return (
                         memory,
                         Effect::Call {
                             call: call.clone(),
+                            labels: None,
                             return_pos: Position {
                                 bb: p.bb,
                                 instr: instr_id + 1,
+                                stack: p.stack,
+                                iterations: p.iterations,
+                                loop_header: None,
+                                unwinding: false,
                             },
                         },
                     )
                 }
+                llvm_ir::Instruction::LandingPad(landing_pad) => {
+                    let (value, _) = self
+                        .exception
+                        .clone()
+                        .expect("landing pad without unwinding");
+                    let ty = &landing_pad.result_type;
+                    memory = self.store_register(&landing_pad.dest, ty, value, memory);
+                }
+                llvm_ir::Instruction::Phi(_) => unreachable!("phis are evaluated by `jump`"),
                 _ => unimplemented!("{instr:?}"),
             }
         }
         let next_pos = Position {
             bb: p.bb,
             instr: bb.instrs.len(),
+            stack: p.stack,
+            iterations: p.iterations,
+            loop_header: None,
+            unwinding: false,
         };
-        self.run_until_effect(f, next_pos, memory)
+        self.run_until_effect(side, next_pos, memory)
     }
+
+    /// Moves to the start of the block `to`, evaluating its phis for an edge coming
+    /// from the block of `p`. Returns `None` if that would exceed the unroll bound.
+    pub fn jump(
+        &mut self,
+        side: Side,
+        p: &Position,
+        to: &Name,
+        mut memory: MemorySnapshot,
+    ) -> Option<(Position, MemorySnapshot)> {
+        let top = self.function(side);
+        let inlined = p.stack.last().map(|x| x.function.clone());
+        let f = inlined.as_deref().unwrap_or(&top);
+        let from = &f.basic_blocks[p.bb].name;
+        let bb = f.basic_blocks.iter().position(|x| x.name == *to).unwrap();
+        let mut iterations = p.iterations.clone();
+        let back_edge = self.back_edges(f).contains(&(p.bb, bb));
+        if back_edge {
+            let count = iterations.entry((memory.frame, bb)).or_default();
+            *count += 1;
+            if *count > self.config.unroll_bound {
+                return None;
+            }
+        }
+        let phis: Vec<_> = f.basic_blocks[bb]
+            .instrs
+            .iter()
+            .map_while(|x| match x {
+                llvm_ir::Instruction::Phi(phi) => Some(phi),
+                _ => None,
+            })
+            .collect();
+        let values: Vec<_> = phis
+            .iter()
+            .map(|phi| {
+                let (value, _) = phi
+                    .incoming_values
+                    .iter()
+                    .find(|(_, pred)| pred == from)
+                    .unwrap();
+                (
+                    self.operand_to_sexp(side, value, memory),
+                    self.operand_poison(value, memory),
+                )
+            })
+            .collect();
+        for (phi, (value, poison)) in phis.iter().zip(values) {
+            memory =
+                self.store_register_with_poison(&phi.dest, &phi.to_type, value, poison, memory);
+        }
+        let next_pos = Position {
+            bb,
+            instr: phis.len(),
+            stack: p.stack.clone(),
+            iterations,
+            loop_header: (p.stack.is_empty() && self.is_loop_header(f, bb)).then_some(
+                match back_edge {
+                    true => LoopEntry::BackEdge,
+                    false => LoopEntry::Fresh,
+                },
+            ),
+            unwinding: false,
+        };
+        Some((next_pos, memory))
+    }
+
+    pub fn back_edges(&self, f: &Function) -> Rc<cfg::BackEdges> {
+        self.back_edges
+            .borrow_mut()
+            .entry(f.name.clone())
+            .or_insert_with(|| Rc::new(cfg::back_edges(f)))
+            .clone()
+    }
+
+    /// Where a side continues after a call that returned normally.
+    pub fn return_continuation(
+        &mut self,
+        side: Side,
+        return_pos: &Position,
+        labels: &Option<(Name, Name)>,
+        memory: MemorySnapshot,
+    ) -> Option<(Position, MemorySnapshot)> {
+        match labels {
+            Some((return_label, _)) => self.jump(side, return_pos, return_label, memory),
+            None => Some((return_pos.clone(), memory)),
+        }
+    }
+
+    /// Where a side continues after a call unwound: the landing pad of an `invoke`, or
+    /// out of the function.
+    pub fn unwind_continuation(
+        &mut self,
+        side: Side,
+        return_pos: &Position,
+        labels: &Option<(Name, Name)>,
+        memory: MemorySnapshot,
+    ) -> Option<(Position, MemorySnapshot)> {
+        match labels {
+            Some((_, exception_label)) => self.jump(side, return_pos, exception_label, memory),
+            None => {
+                let pos = Position {
+                    stack: vec![],
+                    unwinding: true,
+                    ..return_pos.clone()
+                };
+                Some((pos, memory))
+            }
+        }
+    }
+
+    /// Type of the value produced by the landing pad of an `invoke`.
+    pub fn landing_pad_type(
+        &self,
+        side: Side,
+        return_pos: &Position,
+        labels: &Option<(Name, Name)>,
+    ) -> Option<TypeRef> {
+        let (_, exception_label) = labels.as_ref()?;
+        let top = self.function(side);
+        let inlined = return_pos.stack.last().map(|x| x.function.clone());
+        let f = inlined.as_deref().unwrap_or(&top);
+        let bb = f.basic_blocks.iter().find(|x| x.name == *exception_label)?;
+        bb.instrs.iter().find_map(|x| match x {
+            llvm_ir::Instruction::LandingPad(landing_pad) => Some(landing_pad.result_type.clone()),
+            _ => None,
+        })
+    }
+
+    fn is_loop_header(&self, f: &Function, bb: usize) -> bool {
+        self.back_edges(f).iter().any(|&(_, to)| to == bb)
+    }
+
+    fn should_inline(&self, call: &Call, p: &Position) -> bool {
+        p.stack.len() < self.config.inline_depth && self.callee(call).is_some_and(|x| !x.is_var_arg)
+    }
+}
+
+impl VerifierState {
+    /// Stores a value that is not poison in a register.
+    pub fn store_register(
+        &mut self,
+        name: &Name,
+        ty: &TypeRef,
+        value: Sexp,
+        memory: MemorySnapshot,
+    ) -> MemorySnapshot {
+        let poison = self.no_poison(ty);
+        self.store_register_with_poison(name, ty, value, poison, memory)
+    }
+
+    /// Stores a value in a register, along with the mask of its poison scalars.
+    pub fn store_register_with_poison(
+        &mut self,
+        name: &Name,
+        ty: &TypeRef,
+        value: Sexp,
+        poison: Sexp,
+        memory: MemorySnapshot,
+    ) -> MemorySnapshot {
+        let addr = self.address_of_name(name, memory.frame);
+        let memory = self.store_in_addr(bv_hex(addr, 8), ty, value, memory);
+        self.store_poison(name, ty, poison, memory)
+    }
+
+    /// Result of a call to a pure function, as an uninterpreted function of its arguments.
+    fn pure_call_to_sexp(&mut self, side: Side, call: &Call, memory: MemorySnapshot) -> Sexp {
+        let Type::FuncType {
+            result_type,
+            param_types,
+            ..
+        } = &*call.function_ty
+        else {
+            panic!("call of non function type {}", call.function_ty);
+        };
+        let name = format!("|pure_{}|", self.callee_name(call).unwrap());
+        if self.declared_functions.insert(name.clone()) {
+            let params = param_types.iter().map(|x| self.sort_of_ty(x)).collect();
+            self.add_z3_line(Sexp::s4(
+                "declare-fun",
+                &*name,
+                Sexp::List(params),
+                self.sort_of_ty(result_type),
+            ));
+        }
+        let mut r = vec![name.to_sexp()];
+        for (arg, _) in &call.arguments {
+            r.push(self.operand_to_sexp(side, arg, memory));
+        }
+        if r.len() == 1 {
+            return name.to_sexp();
+        }
+        Sexp::List(r)
+    }
+
+    /// The pointer that `gep` computes, and whether its offset wraps around, which
+    /// infinitely precise arithmetic on wider offsets tells.
+    fn gep_to_sexp(&self, side: Side, gep: &GetElementPtr, memory: MemorySnapshot) -> (Sexp, Sexp) {
+        let ptr_ty = self.type_of(&gep.address);
+        let bits = self.pointer_bits(&ptr_ty);
+        // Wide enough for the sum of the base offset and of the products of each index.
+        let wide = 2 * bits + 8;
+        let ptr = self.operand_to_sexp(side, &gep.address, memory);
+        let mut offset = self.pointer_offset(ptr.clone(), &ptr_ty);
+        let mut exact = resize(offset.clone(), bits, wide, false);
+        let mut ty = gep.source_element_type.clone();
+        for (i, index) in gep.indices.iter().enumerate() {
+            let index_value = self.operand_to_sexp(side, index, memory);
+            let index_bits = self.value_bits(&self.type_of(index));
+            let stride = if i == 0 {
+                self.alloc_size_of_ty(&ty)
+            } else {
+                match &*ty.clone() {
+                    Type::ArrayType { element_type, .. }
+                    | Type::VectorType { element_type, .. } => {
+                        ty = element_type.clone();
+                        self.alloc_size_of_ty(&ty)
+                    }
+                    Type::StructType { .. } | Type::NamedStructType { .. } => {
+                        let field = constant_index(index);
+                        let (offsets, _) = self.struct_layout(&ty);
+                        ty = self.struct_fields(&ty)[field].clone();
+                        offset = Sexp::s3("bvadd", offset, bv_hex(offsets[field], bits / 8));
+                        let field_offset =
+                            Sexp::s3("_", &*format!("bv{}", offsets[field]), &*wide.to_string());
+                        exact = Sexp::s3("bvadd", exact, field_offset);
+                        continue;
+                    }
+                    _ => panic!("gep into non aggregate type {ty}"),
+                }
+            };
+            let exact_index = resize(index_value.clone(), index_bits, wide, true);
+            let exact_stride = Sexp::s3("_", &*format!("bv{stride}"), &*wide.to_string());
+            exact = Sexp::s3("bvadd", exact, Sexp::s3("bvmul", exact_index, exact_stride));
+            let index_value = resize(index_value, index_bits, bits, true);
+            let scaled = Sexp::s3("bvmul", index_value, bv_hex(stride, bits / 8));
+            offset = Sexp::s3("bvadd", offset, scaled);
+        }
+        let wraps = Sexp::s3("distinct", exact, resize(offset.clone(), bits, wide, false));
+        (
+            Sexp::s3("concat", self.pointer_block(ptr, &ptr_ty), offset),
+            wraps,
+        )
+    }
+}
+
+/// Operations of `intrinsics::overflowing`, like `sadd`, whose overflow makes the
+/// result of `op` poison under the `nsw` and `nuw` flags.
+fn wrap_flags(op: &str, nsw: bool, nuw: bool) -> Vec<String> {
+    [(nsw, "s"), (nuw, "u")]
+        .into_iter()
+        .filter(|(flag, _)| *flag)
+        .map(|(_, sign)| format!("{sign}{op}"))
+        .collect()
+}
+
+fn flagged(ops: &[(bool, &'static str)]) -> Vec<&'static str> {
+    ops.iter()
+        .filter(|(flag, _)| *flag)
+        .map(|(_, op)| *op)
+        .collect()
+}
+
+fn constant_index(index: &Operand) -> usize {
+    match index {
+        Operand::ConstantOperand(c) => match &**c {
+            Constant::Int { value, .. } => *value as usize,
+            _ => panic!("struct index {c} is not an integer"),
+        },
+        _ => panic!("struct index {index} is not a constant"),
+    }
+}
+
+/// The functions being run at `p`, outermost first, each with its frame. `frame` is the
+/// frame of the innermost one.
+fn live_frames<'a>(top: &'a Function, p: &'a Position, frame: usize) -> Vec<(&'a Function, usize)> {
+    let functions = std::iter::once(top).chain(p.stack.iter().map(|x| &*x.function));
+    let frames = p.stack.iter().map(|x| x.caller_frame).chain([frame]);
+    functions.zip(frames).collect()
 }
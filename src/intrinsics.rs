@@ -0,0 +1,530 @@
+use llvm_ir::{instruction::Call, Function, Operand, Type};
+
+use crate::{
+    memory::{NULL_BLOCK, POISON_BLOCK},
+    poison::is_poison,
+    sexp::{Sexp, ToSexp},
+    z3_decl::{bit_to_byte, bv_hex, extract, if_then_else, resize, BLOCK_BITS},
+    MemorySnapshot, Side, VerifierState,
+};
+
+enum Intrinsic {
+    /// Only carries information for the optimizer and has no effect.
+    Ignored,
+    /// Returns its first argument.
+    Identity,
+    /// Undefined behavior if its argument is false.
+    Assume,
+    /// An operation on integers or on each lane of integer vectors, named without the
+    /// `llvm.` prefix and the type suffix.
+    Integer(String),
+    /// Combines the lanes of an integer vector with an operation like `add` or `smax`.
+    Reduce(String),
+    /// Copies bytes between ranges that may overlap only if `overlap` is set.
+    Copy { overlap: bool },
+    /// Fills a range with a byte.
+    Set,
+}
+
+fn intrinsic_kind(name: &str) -> Option<Intrinsic> {
+    const IGNORED: &[&str] = &[
+        "llvm.lifetime.",
+        "llvm.dbg.",
+        "llvm.sideeffect",
+        "llvm.donothing",
+        "llvm.experimental.noalias.scope.decl",
+        "llvm.pseudoprobe",
+        "llvm.var.annotation",
+    ];
+    const IDENTITY: &[&str] = &["llvm.expect.", "llvm.ptr.annotation."];
+    if IGNORED.iter().any(|x| name.starts_with(x)) {
+        return Some(Intrinsic::Ignored);
+    }
+    if IDENTITY.iter().any(|x| name.starts_with(x)) {
+        return Some(Intrinsic::Identity);
+    }
+    const INTEGER: &[&str] = &[
+        "smax",
+        "smin",
+        "umax",
+        "umin",
+        "abs",
+        "ctpop",
+        "ctlz",
+        "cttz",
+        "bswap",
+        "bitreverse",
+        "fshl",
+        "fshr",
+        "sadd.with.overflow",
+        "uadd.with.overflow",
+        "ssub.with.overflow",
+        "usub.with.overflow",
+        "smul.with.overflow",
+        "umul.with.overflow",
+        "sadd.sat",
+        "uadd.sat",
+        "ssub.sat",
+        "usub.sat",
+    ];
+    if name == "llvm.assume" {
+        return Some(Intrinsic::Assume);
+    }
+    if name.starts_with("llvm.memcpy.") {
+        return Some(Intrinsic::Copy { overlap: false });
+    }
+    if name.starts_with("llvm.memmove.") {
+        return Some(Intrinsic::Copy { overlap: true });
+    }
+    if name.starts_with("llvm.memset.") {
+        return Some(Intrinsic::Set);
+    }
+    const REDUCE: &[&str] = &[
+        "add", "mul", "and", "or", "xor", "smax", "smin", "umax", "umin",
+    ];
+    let (op, suffix) = name.strip_prefix("llvm.")?.rsplit_once('.')?;
+    let integer = |x: &str| {
+        x.strip_prefix('i')
+            .is_some_and(|x| x.parse::<u32>().is_ok())
+    };
+    // Like `v4i32`.
+    let vector = suffix
+        .strip_prefix('v')
+        .and_then(|x| x.find('i').map(|i| &x[i..]))
+        .is_some_and(integer);
+    if let Some(op) = op.strip_prefix("vector.reduce.") {
+        if vector && REDUCE.contains(&op) {
+            return Some(Intrinsic::Reduce(op.to_owned()));
+        }
+        return None;
+    }
+    // The overflow bits of vectors are a vector of their own.
+    let lane_wise = vector && !op.ends_with(".with.overflow");
+    if (integer(suffix) || lane_wise) && INTEGER.contains(&op) {
+        return Some(Intrinsic::Integer(op.to_owned()));
+    }
+    None
+}
+
+impl VerifierState {
+    /// Whether the call is to an intrinsic that is executed by `run_intrinsic` instead of
+    /// being an effect.
+    pub fn is_modeled_intrinsic(&self, call: &Call) -> bool {
+        self.callee_name(call)
+            .is_some_and(|name| intrinsic_kind(&name).is_some())
+    }
+
+    /// Whether the call is to an intrinsic that does nothing, like `llvm.lifetime.start`.
+    pub fn is_ignored_intrinsic(&self, call: &Call) -> bool {
+        self.callee_name(call)
+            .is_some_and(|name| matches!(intrinsic_kind(&name), Some(Intrinsic::Ignored)))
+    }
+
+    pub fn run_intrinsic(
+        &mut self,
+        side: Side,
+        call: &Call,
+        frames: &[(&Function, usize)],
+        memory: MemorySnapshot,
+    ) -> MemorySnapshot {
+        let name = self.callee_name(call).unwrap();
+        match intrinsic_kind(&name).unwrap() {
+            Intrinsic::Ignored => memory,
+            Intrinsic::Identity => {
+                let Some(dest) = &call.dest else {
+                    return memory;
+                };
+                let (arg, _) = &call.arguments[0];
+                let value = self.operand_to_sexp(side, arg, memory);
+                let poison = self.operand_poison(arg, memory);
+                self.store_register_with_poison(dest, &self.type_of(arg), value, poison, memory)
+            }
+            Intrinsic::Assume => {
+                let (arg, _) = &call.arguments[0];
+                self.assume_not_poison(side, arg, memory, "Assumption in new may be poison");
+                let condition = is_set(self.operand_to_sexp(side, arg, memory));
+                self.assume(side, condition, "Assumption in new may not hold");
+                memory
+            }
+            Intrinsic::Integer(op) => {
+                let Type::FuncType { result_type, .. } = &*call.function_ty else {
+                    panic!("call of non function type {}", call.function_ty);
+                };
+                let args: Vec<_> = call
+                    .arguments
+                    .iter()
+                    .map(|(arg, _)| (self.operand_to_sexp(side, arg, memory), self.type_of(arg)))
+                    .collect();
+                let r = self.lane_wise(args.clone(), |ty, args| {
+                    self.integer_intrinsic(&op, &args, self.int_bits(ty)).0
+                });
+                let result_lane_ty = self.lane_type(result_type);
+                let flags = self.lane_wise(args, |ty, args| {
+                    let (_, poison) = self.integer_intrinsic(&op, &args, self.int_bits(ty));
+                    self.poison_if(&result_lane_ty, poison.unwrap_or("false".to_sexp()))
+                });
+                let mut masks: Vec<_> = call
+                    .arguments
+                    .iter()
+                    .map(|(arg, _)| (self.operand_poison(arg, memory), self.type_of(arg)))
+                    .collect();
+                masks.push((flags, result_type.clone()));
+                let poison = self.lane_poison(result_type, masks);
+                match &call.dest {
+                    Some(dest) => {
+                        self.store_register_with_poison(dest, result_type, r, poison, memory)
+                    }
+                    None => memory,
+                }
+            }
+            Intrinsic::Reduce(op) => {
+                let (arg, _) = &call.arguments[0];
+                let vector = self.operand_to_sexp(side, arg, memory);
+                let ty = self.type_of(arg);
+                let (lanes, lane_ty) = self.vector_lanes(&ty).unwrap();
+                let lane = |i| self.narrowed(&lane_ty, self.extract_field(vector.clone(), &ty, i));
+                let r = (1..lanes).fold(lane(0), |r, i| {
+                    let lane = lane(i);
+                    match &*op {
+                        "add" => Sexp::s3("bvadd", r, lane),
+                        "mul" => Sexp::s3("bvmul", r, lane),
+                        "and" => Sexp::s3("bvand", r, lane),
+                        "or" => Sexp::s3("bvor", r, lane),
+                        "xor" => Sexp::s3("bvxor", r, lane),
+                        _ => min_max(&op, r, lane),
+                    }
+                });
+                let r = self.widened(&lane_ty, r);
+                let poison = is_poison(self.operand_poison(arg, memory));
+                let poison = self.poison_if(&lane_ty, poison);
+                match &call.dest {
+                    Some(dest) => {
+                        self.store_register_with_poison(dest, &lane_ty, r, poison, memory)
+                    }
+                    None => memory,
+                }
+            }
+            Intrinsic::Copy { overlap } => {
+                self.assume_arguments_not_poison(side, call, &[0, 1, 2], memory);
+                let dest = self.range_start(side, &call.arguments[0].0, memory);
+                let source = self.range_start(side, &call.arguments[1].0, memory);
+                let len = self.range_len(side, call, memory);
+                self.assume_range_in_bounds(side, call, 0, &len, frames, memory);
+                self.assume_range_in_bounds(side, call, 1, &len, frames, memory);
+                if !overlap {
+                    let message = format!("Call to {name} in new may copy overlapping ranges");
+                    let disjoint = disjoint_ranges(&dest, &source, &len);
+                    self.assume(side, disjoint, &message);
+                }
+                self.record_pointer_copy(&dest, &source);
+                self.store_range(memory, dest, len, |offset| {
+                    Sexp::s3("select", memory, Sexp::s3("bvadd", source, offset))
+                })
+            }
+            Intrinsic::Set => {
+                self.assume_arguments_not_poison(side, call, &[0, 2], memory);
+                let dest = self.range_start(side, &call.arguments[0].0, memory);
+                let (value, _) = &call.arguments[1];
+                let poison = self.operand_poison(value, memory);
+                let value = self.operand_to_sexp(side, value, memory);
+                let len = self.range_len(side, call, memory);
+                self.assume_range_in_bounds(side, call, 0, &len, frames, memory);
+                let block = if_then_else(
+                    is_poison(poison),
+                    bv_hex(POISON_BLOCK, BLOCK_BITS / 8),
+                    bv_hex(NULL_BLOCK, BLOCK_BITS / 8),
+                );
+                let byte = Sexp::s3("concat", block, value);
+                self.store_range(memory, dest, len, |_| byte)
+            }
+        }
+    }
+
+    /// Makes it undefined behavior for the arguments of `call` at `indices` to be poison.
+    fn assume_arguments_not_poison(
+        &mut self,
+        side: Side,
+        call: &Call,
+        indices: &[usize],
+        memory: MemorySnapshot,
+    ) {
+        let message = format!(
+            "Call to {} in new may get poison",
+            self.callee_name(call).unwrap()
+        );
+        for &i in indices {
+            self.assume_not_poison(side, &call.arguments[i].0, memory, &message);
+        }
+    }
+
+    fn range_start(&self, side: Side, ptr: &Operand, memory: MemorySnapshot) -> Sexp {
+        let value = self.operand_to_sexp(side, ptr, memory);
+        self.pointer_to_addr(value, &self.type_of(ptr))
+    }
+
+    /// Length of the range that `call` accesses, as its third argument. It is undefined
+    /// behavior for it to exceed the offsets of a block, which have 32 bits.
+    fn range_len(&mut self, side: Side, call: &Call, memory: MemorySnapshot) -> Sexp {
+        let (len, _) = &call.arguments[2];
+        let bits = self.value_bits(&self.type_of(len));
+        let len = self.operand_to_sexp(side, len, memory);
+        if bits > 32 {
+            let message = format!(
+                "Call to {} in new may access more than a block",
+                self.callee_name(call).unwrap()
+            );
+            let high = extract(len.clone(), bits - 1, 32);
+            let fits = Sexp::s3("=", high, Sexp::s3("_", "bv0", &*(bits - 32).to_string()));
+            self.assume(side, fits, &message);
+        }
+        resize(len, bits, 32, false)
+    }
+
+    /// Makes it undefined behavior for the `len` bytes that argument `i` of `call` points
+    /// to not to be inside a live block, unless there are none.
+    fn assume_range_in_bounds(
+        &mut self,
+        side: Side,
+        call: &Call,
+        i: usize,
+        len: &Sexp,
+        frames: &[(&Function, usize)],
+        memory: MemorySnapshot,
+    ) {
+        let message = format!(
+            "Call to {} in new may access memory out of bounds",
+            self.callee_name(call).unwrap()
+        );
+        let (ptr, _) = &call.arguments[i];
+        let value = self.operand_to_sexp(side, ptr, memory);
+        let in_bounds = self.access_in_bounds(value, &self.type_of(ptr), len.clone(), frames);
+        let empty = Sexp::s3("=", len.clone(), bv_hex(0, 4));
+        self.assume(side, Sexp::s3("or", empty, in_bounds), &message);
+    }
+
+    /// Makes `condition` hold from now on, in a function where it is undefined behavior
+    /// otherwise. The old function may assume anything, since it is undefined behavior
+    /// if that is wrong. The new one may only assume what is known to hold.
+    pub fn assume(&mut self, side: Side, condition: Sexp, message: &str) {
+        if side == Side::Right {
+            let mut this = self.clone();
+            this.goal.push(("assumption".to_owned(), condition.clone()));
+            this.check_sat(message);
+        }
+        self.add_z3_line(Sexp::s2("assert", condition));
+    }
+
+    /// Result of an integer intrinsic on integers of type `iN`, where N is `bits`, and the
+    /// condition under which the result is poison if it can be. The integers are held in
+    /// whole bytes like any value, and are computed on at their declared width.
+    fn integer_intrinsic(&self, op: &str, args: &[Sexp], bits: usize) -> (Sexp, Option<Sexp>) {
+        let value_bits = bit_to_byte(bits) * 8;
+        // The flags of `abs`, `ctlz` and `cttz` are `i1`s, the other arguments are of
+        // the same type as the result.
+        let has_flag = matches!(op, "abs" | "ctlz" | "cttz");
+        let args: Vec<_> = args
+            .iter()
+            .enumerate()
+            .map(|(i, x)| match (has_flag, i) {
+                (true, 1) => x.clone(),
+                _ => resize(x.clone(), value_bits, bits, false),
+            })
+            .collect();
+        let widened = |x| resize(x, bits, value_bits, false);
+        let a = args[0].clone();
+        let number = |x: usize| Sexp::s3("_", &*format!("bv{x}"), &*bits.to_string());
+        let bit = |i: usize| Sexp::s3("=", extract(a.clone(), i, i), "#b1");
+        if let Some(op) = op.strip_suffix(".with.overflow") {
+            let (r, overflow) = overflowing(op, a, args[1].clone(), bits);
+            let overflow = if_then_else(overflow, "#x01", "#x00");
+            return (self.aggregate_value(vec![widened(r), overflow]), None);
+        }
+        if let Some(op) = op.strip_suffix(".sat") {
+            let (r, overflow) = overflowing(op, a.clone(), args[1].clone(), bits);
+            let bound = match op {
+                "uadd" => Sexp::s2("bvnot", number(0)),
+                "usub" => number(0),
+                _ => if_then_else(
+                    Sexp::s3("bvslt", a, number(0)),
+                    signed_min(bits),
+                    Sexp::s2("bvnot", signed_min(bits)),
+                ),
+            };
+            return (widened(if_then_else(overflow, bound, r)), None);
+        }
+        let (r, poison) = match op {
+            "smax" | "smin" | "umax" | "umin" => (min_max(op, a, args[1].clone()), None),
+            "abs" => {
+                let poison = Sexp::s3(
+                    "and",
+                    is_set(args[1].clone()),
+                    Sexp::s3("=", a.clone(), signed_min(bits)),
+                );
+                let r = if_then_else(
+                    Sexp::s3("bvslt", a.clone(), number(0)),
+                    Sexp::s2("bvneg", a.clone()),
+                    a,
+                );
+                (r, Some(poison))
+            }
+            "ctpop" => {
+                let mut r = vec!["bvadd".to_sexp()];
+                r.extend((0..bits).map(|i| resize(extract(a.clone(), i, i), 1, bits, false)));
+                (Sexp::List(r), None)
+            }
+            "ctlz" | "cttz" => {
+                let poison = Sexp::s3(
+                    "and",
+                    is_set(args[1].clone()),
+                    Sexp::s3("=", a.clone(), number(0)),
+                );
+                // The bit that is looked at last decides, so it must be the first one
+                // set in counting order.
+                let mut r = number(bits);
+                for i in 0..bits {
+                    r = match op {
+                        "ctlz" => if_then_else(bit(i), number(bits - 1 - i), r),
+                        _ => if_then_else(bit(bits - 1 - i), number(bits - 1 - i), r),
+                    };
+                }
+                (r, Some(poison))
+            }
+            "bswap" => {
+                let mut r = vec!["concat".to_sexp()];
+                r.extend((0..bits / 8).map(|i| extract(a.clone(), i * 8 + 7, i * 8)));
+                (Sexp::List(r), None)
+            }
+            "bitreverse" => {
+                let mut r = vec!["concat".to_sexp()];
+                r.extend((0..bits).map(|i| extract(a.clone(), i, i)));
+                (Sexp::List(r), None)
+            }
+            "fshl" | "fshr" => {
+                let both = Sexp::s3("concat", a, args[1].clone());
+                let amount = Sexp::s3("bvurem", args[2].clone(), number(bits));
+                let amount = resize(amount, bits, bits * 2, false);
+                let r = match op {
+                    "fshl" => extract(Sexp::s3("bvshl", both, amount), bits * 2 - 1, bits),
+                    _ => extract(Sexp::s3("bvlshr", both, amount), bits - 1, 0),
+                };
+                (r, None)
+            }
+            _ => unreachable!("unknown integer intrinsic {op}"),
+        };
+        (widened(r), poison)
+    }
+}
+
+/// Whether two ranges of `len` bytes, starting at the given memory array indices,
+/// have no byte in common.
+fn disjoint_ranges(a: &Sexp, b: &Sexp, len: &Sexp) -> Sexp {
+    let len = resize(len.clone(), 32, 64, false);
+    Sexp::s3(
+        "and",
+        Sexp::s3(
+            "bvuge",
+            Sexp::s3("bvsub", a.clone(), b.clone()),
+            len.clone(),
+        ),
+        Sexp::s3("bvuge", Sexp::s3("bvsub", b.clone(), a.clone()), len),
+    )
+}
+
+fn min_max(op: &str, a: Sexp, b: Sexp) -> Sexp {
+    let compare = match op {
+        "smax" => "bvsgt",
+        "smin" => "bvslt",
+        "umax" => "bvugt",
+        _ => "bvult",
+    };
+    if_then_else(Sexp::s3(compare, a.clone(), b.clone()), a, b)
+}
+
+fn is_set(flag: Sexp) -> Sexp {
+    Sexp::s3("=", flag, "#x01")
+}
+
+fn signed_min(bits: usize) -> Sexp {
+    if bits == 1 {
+        return "#b1".to_sexp();
+    }
+    Sexp::s3(
+        "concat",
+        "#b1",
+        Sexp::s3("_", "bv0", &*(bits - 1).to_string()),
+    )
+}
+
+/// Wrapped result of `op`, like `sadd` or `umul`, and whether it overflowed, which is
+/// when it differs from the result computed with twice the bits.
+pub fn overflowing(op: &str, a: Sexp, b: Sexp, bits: usize) -> (Sexp, Sexp) {
+    let signed = op.starts_with('s');
+    let z3_op = match &op[1..] {
+        "add" => "bvadd",
+        "sub" => "bvsub",
+        "mul" => "bvmul",
+        _ => unreachable!("unknown overflowing operation {op}"),
+    };
+    let wide = Sexp::s3(
+        z3_op,
+        resize(a.clone(), bits, bits * 2, signed),
+        resize(b.clone(), bits, bits * 2, signed),
+    );
+    let r = Sexp::s3(z3_op, a, b);
+    let overflow = Sexp::s3("distinct", resize(r.clone(), bits, bits * 2, signed), wide);
+    (r, overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use llvm_ir::module::Endianness;
+
+    use super::*;
+    use crate::{solver::simplify, tests::state};
+
+    fn run(op: &str, args: &[&str], bits: usize) -> (String, Option<String>) {
+        let args: Vec<Sexp> = args.iter().map(|x| x.to_sexp()).collect();
+        let (r, poison) = state(Endianness::LittleEndian).integer_intrinsic(op, &args, bits);
+        (simplify(r), poison.map(simplify))
+    }
+
+    #[test]
+    fn count_leading_and_trailing_zeros() {
+        assert_eq!(run("ctlz", &["#x10", "#x00"], 8).0, "#x03");
+        assert_eq!(run("cttz", &["#x10", "#x00"], 8).0, "#x04");
+        assert_eq!(run("ctlz", &["#x0001", "#x00"], 16).0, "#x000f");
+        assert_eq!(run("cttz", &["#x8000", "#x00"], 16).0, "#x000f");
+    }
+
+    #[test]
+    fn count_zeros_of_zero() {
+        assert_eq!(
+            run("ctlz", &["#x00", "#x00"], 8),
+            ("#x08".into(), Some("false".into()))
+        );
+        assert_eq!(run("cttz", &["#x00", "#x01"], 8).1, Some("true".into()));
+        assert_eq!(run("cttz", &["#x01", "#x01"], 8).1, Some("false".into()));
+    }
+
+    #[test]
+    fn narrow_integers_use_their_declared_width() {
+        assert_eq!(run("ctlz", &["#x01", "#x00"], 7).0, "#x06");
+        assert_eq!(run("ctpop", &["#x7f"], 7).0, "#x07");
+        assert_eq!(run("bitreverse", &["#x01"], 7).0, "#x40");
+        // The sign bit of an i7 is its seventh bit.
+        assert_eq!(run("smax", &["#x40", "#x01"], 7).0, "#x01");
+        assert_eq!(run("abs", &["#x7f", "#x00"], 7).0, "#x01");
+        assert_eq!(run("sadd.sat", &["#x3f", "#x01"], 7).0, "#x3f");
+    }
+
+    #[test]
+    fn funnel_shifts() {
+        assert_eq!(run("fshl", &["#x12", "#x34", "#x04"], 8).0, "#x23");
+        assert_eq!(run("fshl", &["#x12", "#x34", "#x01"], 8).0, "#x24");
+        assert_eq!(run("fshr", &["#x12", "#x34", "#x01"], 8).0, "#x1a");
+        // The shift amount is taken modulo the width.
+        assert_eq!(run("fshr", &["#x12", "#x34", "#x0c"], 8).0, "#x23");
+        assert_eq!(run("fshl", &["#x12", "#x34", "#x08"], 8).0, "#x12");
+        assert_eq!(run("fshr", &["#x12", "#x34", "#x00"], 8).0, "#x34");
+    }
+}
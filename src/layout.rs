@@ -0,0 +1,194 @@
+use llvm_ir::{
+    module::{AddrSpace, Endianness},
+    types::{FPType, NamedStructDef},
+    Type, TypeRef,
+};
+
+use crate::{z3_decl::bit_to_byte, VerifierState};
+
+impl VerifierState {
+    pub fn is_big_endian(&self) -> bool {
+        self.module.data_layout.endianness == Endianness::BigEndian
+    }
+
+    pub fn pointer_size(&self, addr_space: AddrSpace) -> usize {
+        let layout = self.module.data_layout.alignments.ptr_alignment(addr_space);
+        bit_to_byte(layout.size as usize)
+    }
+
+    /// Number of bytes that a store of `ty` may overwrite. This is the store size of LLVM,
+    /// which includes the tail padding of structs, see `struct_layout`, but not the padding
+    /// that aligns scalars like `x86_fp80` in arrays.
+    pub fn size_of_ty(&self, ty: &TypeRef) -> usize {
+        match &**ty {
+            Type::VoidType => 0,
+            Type::IntegerType { bits } => bit_to_byte(*bits as usize),
+            Type::PointerType { addr_space } => self.pointer_size(*addr_space),
+            Type::FuncType { .. } => {
+                self.pointer_size(self.module.data_layout.program_address_space)
+            }
+            Type::ArrayType {
+                element_type,
+                num_elements,
+            } => self.alloc_size_of_ty(element_type) * num_elements,
+            Type::StructType { .. } | Type::NamedStructType { .. } => self.struct_layout(ty).1,
+            Type::VectorType { .. } => match self.packed_vector_size(ty) {
+                Some(size) => size,
+                None => {
+                    let (lanes, lane_ty) = self.vector_lanes(ty).unwrap();
+                    self.size_of_ty(&lane_ty) * lanes
+                }
+            },
+            Type::FPType(fp) => match fp {
+                FPType::Half | FPType::BFloat => 2,
+                FPType::Single => 4,
+                FPType::Double => 8,
+                FPType::X86_FP80 => 10,
+                FPType::FP128 | FPType::PPC_FP128 => 16,
+            },
+            _ => panic!("{ty} has no size in memory"),
+        }
+    }
+
+    /// Distance in bytes between two consecutive elements of type `ty` in an array.
+    pub fn alloc_size_of_ty(&self, ty: &TypeRef) -> usize {
+        let size = self.size_of_ty(ty);
+        let align = self.abi_align_of_ty(ty);
+        size.next_multiple_of(align)
+    }
+
+    pub fn abi_align_of_ty(&self, ty: &TypeRef) -> usize {
+        let alignments = &self.module.data_layout.alignments;
+        let bits = match &**ty {
+            Type::VoidType => 8,
+            Type::FuncType { .. } => alignments.fptr_alignment().abi,
+            Type::ArrayType { element_type, .. } => {
+                return self.abi_align_of_ty(element_type);
+            }
+            Type::StructType {
+                element_types,
+                is_packed,
+            } => {
+                let agg = bit_to_byte(alignments.agg_alignment().abi as usize).max(1);
+                if *is_packed {
+                    return agg;
+                }
+                return element_types
+                    .iter()
+                    .map(|x| self.abi_align_of_ty(x))
+                    .fold(agg, usize::max);
+            }
+            Type::NamedStructType { name } => {
+                return self.abi_align_of_ty(&self.named_struct_body(name));
+            }
+            // The data layout only knows vectors of integers and floats.
+            Type::VectorType { element_type, .. }
+                if matches!(**element_type, Type::PointerType { .. }) =>
+            {
+                return self.size_of_ty(ty).next_power_of_two();
+            }
+            ty => alignments.type_alignment(ty).abi,
+        };
+        bit_to_byte(bits as usize).max(1)
+    }
+
+    /// Byte offset of each field of a struct type, and the size of the whole struct
+    /// including its tail padding.
+    pub fn struct_layout(&self, ty: &TypeRef) -> (Vec<usize>, usize) {
+        let (element_types, is_packed) = match &**ty {
+            Type::StructType {
+                element_types,
+                is_packed,
+            } => (element_types, *is_packed),
+            Type::NamedStructType { name } => {
+                return self.struct_layout(&self.named_struct_body(name))
+            }
+            _ => panic!("{ty} is not a struct"),
+        };
+        let mut offsets = vec![];
+        let mut offset = 0usize;
+        for field in element_types {
+            if !is_packed {
+                offset = offset.next_multiple_of(self.abi_align_of_ty(field));
+            }
+            offsets.push(offset);
+            offset += self.alloc_size_of_ty(field);
+        }
+        if !is_packed {
+            offset = offset.next_multiple_of(self.abi_align_of_ty(ty));
+        }
+        (offsets, offset)
+    }
+
+    pub fn struct_fields(&self, ty: &TypeRef) -> Vec<TypeRef> {
+        match &**ty {
+            Type::StructType { element_types, .. } => element_types.clone(),
+            Type::NamedStructType { name } => self.struct_fields(&self.named_struct_body(name)),
+            _ => panic!("{ty} is not a struct"),
+        }
+    }
+
+    fn named_struct_body(&self, name: &str) -> TypeRef {
+        match self.module.types.named_struct_def(name) {
+            Some(NamedStructDef::Defined(ty)) => ty.clone(),
+            Some(NamedStructDef::Opaque) | None => {
+                panic!("layout of opaque struct {name} is unknown")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use llvm_ir::types::Types;
+
+    use super::*;
+    use crate::tests::state;
+
+    #[test]
+    fn struct_fields_are_aligned() {
+        let state = state(Endianness::LittleEndian);
+        let types = Types::blank_for_testing();
+        let ty = types.struct_of(vec![types.i8(), types.i32(), types.i16()], false);
+        assert_eq!(state.struct_layout(&ty), (vec![0, 4, 8], 12));
+        // The default data layout aligns `i64` to 4 bytes only.
+        let ty = types.struct_of(vec![types.i8(), types.i64()], false);
+        assert_eq!(state.struct_layout(&ty), (vec![0, 4], 12));
+    }
+
+    #[test]
+    fn packed_structs_have_no_padding() {
+        let state = state(Endianness::LittleEndian);
+        let types = Types::blank_for_testing();
+        let ty = types.struct_of(vec![types.i8(), types.i32()], true);
+        assert_eq!(state.struct_layout(&ty), (vec![0, 1], 5));
+        assert_eq!(state.abi_align_of_ty(&ty), 1);
+    }
+
+    #[test]
+    fn arrays_and_vectors() {
+        let state = state(Endianness::LittleEndian);
+        let types = Types::blank_for_testing();
+        let element = types.struct_of(vec![types.i16(), types.i8()], false);
+        assert_eq!(state.alloc_size_of_ty(&element), 4);
+        assert_eq!(state.size_of_ty(&types.array_of(element, 3)), 12);
+        let vector = types.vector_of(types.i16(), 4, false);
+        assert_eq!(state.size_of_ty(&vector), 8);
+        let offsets: Vec<usize> = state
+            .aggregate_fields(&vector)
+            .unwrap()
+            .into_iter()
+            .map(|(_, offset)| offset)
+            .collect();
+        assert_eq!(offsets, vec![0, 2, 4, 6]);
+    }
+
+    #[test]
+    fn stores_of_structs_include_their_tail_padding() {
+        let state = state(Endianness::LittleEndian);
+        let types = Types::blank_for_testing();
+        let ty = types.struct_of(vec![types.i32(), types.i8()], false);
+        assert_eq!(state.size_of_ty(&ty), 8);
+        assert_eq!(state.size_of_ty(&types.int(24)), 3);
+    }
+}
@@ -0,0 +1,339 @@
+use std::{cell::Cell, collections::HashSet, rc::Rc};
+
+use llvm_ir::{Function, Instruction, Name, Operand, Terminator, TypeRef};
+
+use crate::{
+    cfg,
+    interpret::{Effect, Position},
+    memory::Footprint,
+    sexp::Sexp,
+    z3_decl::{bv_hex, bv_ty, declare_const, memory_ty, BLOCK_BITS},
+    MemorySnapshot, Path, Side, VerifierState,
+};
+
+// Loops are verified by treating a pair of loop headers that both functions reach at
+// the same time as synchronized, and looking for an invariant that couples the states
+// of the two functions at those headers. Candidate relations that hold when the loops
+// are entered are kept as long as one iteration of both loops preserves them. Once that
+// is the case the loops continue from an arbitrary state satisfying the invariant, and
+// coming back to the headers ends the path.
+
+/// A relation between the states of the two functions at a pair of loop headers that
+/// may be part of the loop invariant.
+#[derive(Debug, Clone, PartialEq)]
+enum Candidate {
+    /// A phi of the left header equals a phi of the right header, poison included.
+    Registers {
+        left: Name,
+        right: Name,
+        ty: TypeRef,
+    },
+    /// Both sides agree on the memory that outlives the functions.
+    Memory,
+    /// The first conditional branch reached from the header of one side goes the given way.
+    Branch { right: bool, taken: bool },
+}
+
+/// A pair of synchronized loop headers, each given by the frame it runs in and its block.
+pub type LoopHeaders = ((usize, usize), (usize, usize));
+
+pub fn loop_headers(
+    left_memory: MemorySnapshot,
+    right_memory: MemorySnapshot,
+    left_pos: &Position,
+    right_pos: &Position,
+) -> LoopHeaders {
+    (
+        (left_memory.frame, left_pos.bb),
+        (right_memory.frame, right_pos.bb),
+    )
+}
+
+impl VerifierState {
+    /// Tries to find an inductive invariant for the loops whose headers are at `left_pos`
+    /// and `right_pos`. On success, returns the state to continue from: both loops at their
+    /// headers in an arbitrary state that satisfies the invariant.
+    pub fn prove_loop(
+        &self,
+        left_memory: MemorySnapshot,
+        right_memory: MemorySnapshot,
+        left_pos: &Position,
+        right_pos: &Position,
+    ) -> Option<Path> {
+        let headers = loop_headers(left_memory, right_memory, left_pos, right_pos);
+        let entry = (
+            self.clone(),
+            left_memory,
+            right_memory,
+            left_pos.clone(),
+            right_pos.clone(),
+        );
+        let mut candidates = self.loop_candidates(&entry);
+        candidates.retain(|c| self.candidate_holds(c, &entry));
+        // An invariant too weak for the checks after the headers is no proof, the loops
+        // are unrolled instead.
+        let failed_checks = Rc::new(Cell::new(0));
+        loop {
+            if candidates.is_empty() {
+                return None;
+            }
+            let mut start = self.abstract_loop_state(&candidates, &entry);
+            start.0.failed_checks = Some(failed_checks.clone());
+            let exploration = start.0.explore(start.clone(), Some(headers));
+            if exploration.incomplete_paths > 0 || failed_checks.get() > 0 {
+                return None;
+            }
+            let before = candidates.len();
+            candidates.retain(|c| {
+                exploration
+                    .arrivals
+                    .iter()
+                    .all(|path| path.0.candidate_holds(c, path))
+            });
+            if candidates.len() == before {
+                let (mut this, left_memory, right_memory, left_pos, right_pos) = start;
+                this.proven_loops.insert(headers);
+                this.failed_checks = self.failed_checks.clone();
+                return Some((this, left_memory, right_memory, left_pos, right_pos));
+            }
+        }
+    }
+
+    fn loop_candidates(&self, entry: &Path) -> Vec<Candidate> {
+        let (this, left_memory, right_memory, left_pos, right_pos) = entry;
+        let left_phis = header_phis(&self.left, left_pos.bb);
+        let right_phis = header_phis(&self.right, right_pos.bb);
+        let mut r = vec![Candidate::Memory];
+        for (left, left_ty) in &left_phis {
+            for (right, right_ty) in &right_phis {
+                if left_ty == right_ty {
+                    r.push(Candidate::Registers {
+                        left: left.clone(),
+                        right: right.clone(),
+                        ty: left_ty.clone(),
+                    });
+                }
+            }
+        }
+        for (right, side, memory, pos) in [
+            (false, Side::Left, *left_memory, left_pos),
+            (true, Side::Right, *right_memory, right_pos),
+        ] {
+            let (_, effect) = this.clone().run_alone(side, pos.clone(), memory);
+            if let Effect::CondBr { .. } = effect {
+                r.push(Candidate::Branch { right, taken: true });
+                r.push(Candidate::Branch {
+                    right,
+                    taken: false,
+                });
+            }
+        }
+        r
+    }
+
+    /// Runs one side up to its next effect, to find the branch that it ends with. Checks
+    /// of the new function need the old one to have run alongside, so they are left out.
+    fn run_alone(
+        &mut self,
+        side: Side,
+        pos: Position,
+        memory: MemorySnapshot,
+    ) -> (MemorySnapshot, Effect) {
+        let failed_checks = self.failed_checks.replace(Rc::new(Cell::new(0)));
+        let r = self.run_until_effect(side, pos, memory);
+        self.failed_checks = failed_checks;
+        r
+    }
+
+    fn candidate_holds(&self, candidate: &Candidate, path: &Path) -> bool {
+        let mut this = self.clone();
+        let (_, left_memory, right_memory, _, _) = path;
+        if let Candidate::Memory = candidate {
+            let addr = format!("{left_memory}_{right_memory}_loop_address");
+            this.add_z3_line(declare_const(&*addr, bv_ty(64)));
+            this.add_z3_line(Sexp::s2("assert", Footprint::outliving().contains(&*addr)));
+            return this.is_valid(Sexp::s3(
+                "=",
+                Sexp::s3("select", *left_memory, &*addr),
+                Sexp::s3("select", *right_memory, &*addr),
+            ));
+        }
+        let Some(fact) = this.candidate_to_sexp(candidate, path) else {
+            return false;
+        };
+        this.is_valid(fact)
+    }
+
+    /// The relation as a formula over the state of `path`, if it can be stated there.
+    /// Memory is related by construction instead, see `abstract_loop_state`.
+    fn candidate_to_sexp(&mut self, candidate: &Candidate, path: &Path) -> Option<Sexp> {
+        let (_, left_memory, right_memory, left_pos, right_pos) = path;
+        match candidate {
+            Candidate::Registers { left, right, ty } => {
+                let left_addr = self.address_of_name(left, left_memory.frame);
+                let right_addr = self.address_of_name(right, right_memory.frame);
+                Some(Sexp::s3(
+                    "and",
+                    Sexp::s3(
+                        "=",
+                        self.load_from_addr(bv_hex(left_addr, 8), ty, *left_memory),
+                        self.load_from_addr(bv_hex(right_addr, 8), ty, *right_memory),
+                    ),
+                    Sexp::s3(
+                        "=",
+                        self.register_poison(left, ty, *left_memory),
+                        self.register_poison(right, ty, *right_memory),
+                    ),
+                ))
+            }
+            Candidate::Memory => None,
+            Candidate::Branch { right, taken } => {
+                let (side, memory, pos) = match right {
+                    false => (Side::Left, *left_memory, left_pos),
+                    true => (Side::Right, *right_memory, right_pos),
+                };
+                let (memory, effect) = self.run_alone(side, pos.clone(), memory);
+                let Effect::CondBr { br, .. } = effect else {
+                    return None;
+                };
+                let condition = self.operand_to_sexp(side, &br.condition, memory);
+                Some(Sexp::s3(
+                    "=",
+                    condition,
+                    if *taken { "#x01" } else { "#x00" },
+                ))
+            }
+        }
+    }
+
+    /// Forgets everything the loops may change about the state at `entry`, keeping only
+    /// the `candidates`.
+    fn abstract_loop_state(&self, candidates: &[Candidate], entry: &Path) -> Path {
+        let (_, left_memory, right_memory, left_pos, right_pos) = entry;
+        let mut this = self.clone();
+        let mut left_memory = this.havoc_loop_state(&self.left, left_pos, *left_memory);
+        let mut right_memory = this.havoc_loop_state(&self.right, right_pos, *right_memory);
+        if candidates.contains(&Candidate::Memory) {
+            let havoc = this.new_memory();
+            this.add_z3_line(declare_const(havoc, memory_ty()));
+            left_memory = this.havoc_memory(left_memory, havoc, &Footprint::outliving());
+            right_memory = this.havoc_memory(right_memory, havoc, &Footprint::outliving());
+        }
+        let mut left_pos = left_pos.clone();
+        left_pos
+            .iterations
+            .remove(&(left_memory.frame, left_pos.bb));
+        let mut right_pos = right_pos.clone();
+        right_pos
+            .iterations
+            .remove(&(right_memory.frame, right_pos.bb));
+        let path = (this.clone(), left_memory, right_memory, left_pos, right_pos);
+        for c in candidates {
+            if let Some(fact) = this.candidate_to_sexp(c, &path) {
+                this.add_z3_line(Sexp::s2("assert", fact));
+            }
+        }
+        let (_, left_memory, right_memory, left_pos, right_pos) = path;
+        (this, left_memory, right_memory, left_pos, right_pos)
+    }
+
+    /// Havocs the memory and allocas of one side and the phis of its loop header.
+    fn havoc_loop_state(
+        &mut self,
+        f: &Function,
+        pos: &Position,
+        memory: MemorySnapshot,
+    ) -> MemorySnapshot {
+        let mut blocks = vec![];
+        for instr in f.basic_blocks.iter().flat_map(|bb| &bb.instrs) {
+            if let Instruction::Alloca(alloca) = instr {
+                let block = self.alloca_block(f, &alloca.dest, memory.frame);
+                blocks.push(bv_hex(block, BLOCK_BITS / 8));
+            }
+        }
+        for (name, _) in header_phis(f, pos.bb) {
+            let block = self.address_of_name(&name, memory.frame) >> 32;
+            blocks.push(bv_hex(block, BLOCK_BITS / 8));
+        }
+        // An alloca may escape in any iteration, not only in the ones run so far.
+        self.capture(self.escaping_allocas(f, pos.bb, memory.frame));
+        let havoc = self.new_memory();
+        self.add_z3_line(declare_const(havoc, memory_ty()));
+        let footprint = Footprint {
+            other_memory: true,
+            blocks,
+        };
+        self.havoc_memory(memory, havoc, &footprint)
+    }
+
+    /// Blocks of the allocas of `f` whose address, or a pointer computed from it, is
+    /// stored or passed to a call in the loop with the header `header`.
+    fn escaping_allocas(&self, f: &Function, header: usize, frame: usize) -> Vec<Sexp> {
+        let body = cfg::loop_body(f, &self.back_edges(f), header);
+        let instrs = || f.basic_blocks.iter().flat_map(|bb| &bb.instrs);
+        let mut r = vec![];
+        for instr in instrs() {
+            let Instruction::Alloca(alloca) = instr else {
+                continue;
+            };
+            let mut derived = HashSet::from([&alloca.dest]);
+            loop {
+                let before = derived.len();
+                for instr in instrs() {
+                    let operands: Vec<&Operand> = match instr {
+                        Instruction::GetElementPtr(x) => vec![&x.address],
+                        Instruction::BitCast(x) => vec![&x.operand],
+                        Instruction::AddrSpaceCast(x) => vec![&x.operand],
+                        Instruction::PtrToInt(x) => vec![&x.operand],
+                        Instruction::IntToPtr(x) => vec![&x.operand],
+                        Instruction::Phi(x) => x.incoming_values.iter().map(|(x, _)| x).collect(),
+                        Instruction::Select(x) => vec![&x.true_value, &x.false_value],
+                        Instruction::InsertValue(x) => vec![&x.aggregate, &x.element],
+                        _ => continue,
+                    };
+                    if operands.into_iter().any(|x| is_local(x, &derived)) {
+                        derived.insert(instr.try_get_result().unwrap());
+                    }
+                }
+                if derived.len() == before {
+                    break;
+                }
+            }
+            let escapes = body.iter().any(|&bb| {
+                let block = &f.basic_blocks[bb];
+                let arguments = match &block.term {
+                    Terminator::Invoke(invoke) => invoke.arguments.iter().collect(),
+                    _ => vec![],
+                };
+                block.instrs.iter().any(|instr| match instr {
+                    Instruction::Store(store) => is_local(&store.value, &derived),
+                    Instruction::Call(call) if !self.is_modeled_intrinsic(call) => {
+                        call.arguments.iter().any(|(x, _)| is_local(x, &derived))
+                    }
+                    _ => false,
+                }) || arguments.into_iter().any(|(x, _)| is_local(x, &derived))
+            });
+            if escapes {
+                let block = self.alloca_block(f, &alloca.dest, frame);
+                r.push(bv_hex(block, BLOCK_BITS / 8));
+            }
+        }
+        r
+    }
+}
+
+/// Whether `operand` is a register among `names`.
+fn is_local(operand: &Operand, names: &HashSet<&Name>) -> bool {
+    matches!(operand, Operand::LocalOperand { name, .. } if names.contains(name))
+}
+
+fn header_phis(f: &Function, bb: usize) -> Vec<(Name, TypeRef)> {
+    f.basic_blocks[bb]
+        .instrs
+        .iter()
+        .map_while(|x| match x {
+            Instruction::Phi(phi) => Some((phi.dest.clone(), phi.to_type.clone())),
+            _ => None,
+        })
+        .collect()
+}
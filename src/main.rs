@@ -1,39 +1,237 @@
 use std::{
-    borrow::BorrowMut,
-    cell::RefCell,
-    collections::{HashMap, VecDeque},
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet, VecDeque},
     fmt::Display,
-    io::Write,
     os::unix::process::CommandExt,
+    rc::Rc,
 };
 
-use interpret::{Effect, Position};
+use interpret::{Effect, LoopEntry, Position};
 use llvm_ir::{
+    function::ParameterAttribute,
     instruction::{BinaryOp, Call},
+    terminator::CondBr,
     types::Typed,
-    Function, Module, Operand, Terminator,
+    Function, Module, Name, Operand, Terminator,
 };
+use loops::{loop_headers, LoopHeaders};
+use memory::{Footprint, FIRST_ALLOCA_BLOCK, FIRST_REGISTER_BLOCK, NULL_BLOCK, POISON_BLOCK};
+use merge::enqueue;
+use poison::TakenWords;
 use sexp::{Sexp, ToSexp};
-use z3_decl::{bit_to_byte, bv_hex, bv_ty, declare_const, define_const, if_then_else, memory_ty};
+use solver::Solver;
+use z3_decl::{
+    add_offset, bit_to_byte, bv_hex, bv_ty, declare_const, define_const, extract, if_then_else,
+    memory_ty, string_literal, BLOCK_BITS,
+};
 
+mod aggregate;
+mod attributes;
+mod cfg;
+mod float;
 mod interpret;
+mod intrinsics;
+mod layout;
+mod loops;
+mod memory;
+mod merge;
+mod poison;
 mod sexp;
+mod solver;
+mod vector;
 mod z3_decl;
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
+struct Config {
+    /// Model calls to functions that neither touch memory nor diverge as uninterpreted
+    /// functions of their arguments, so they can be removed, hoisted or deduplicated.
+    pure_calls_as_functions: bool,
+    /// How deep calls to functions defined in the module are executed inline instead of
+    /// being treated as external calls.
+    inline_depth: usize,
+    /// Join paths that reach the same positions into one path whose state is chosen by
+    /// the path conditions, instead of exploring them separately.
+    merge_paths: bool,
+    /// Patterns of functions that never return normally, like panics and aborts. Calls
+    /// to them end the path. A pattern is a demangled path, optionally ending in `*`.
+    diverging_functions: Vec<String>,
+    /// How many times each loop may go around its back-edge on a path before the path is
+    /// given up on.
+    unroll_bound: usize,
+    /// Fast-math flags that the instructions may have.
+    fast_math: float::FastMath,
+    /// Fast-math flags of the instructions that have any, by function and result.
+    instruction_fast_math: HashMap<(String, Name), float::FastMath>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            pure_calls_as_functions: false,
+            inline_depth: 0,
+            merge_paths: false,
+            diverging_functions: [
+                "core::panicking::*",
+                "core::option::unwrap_failed",
+                "core::option::expect_failed",
+                "core::result::unwrap_failed",
+                "core::slice::index::*_fail",
+                "std::panicking::begin_panic*",
+                "alloc::alloc::handle_alloc_error",
+                "__rust_alloc_error_handler",
+                "std::process::abort",
+                "abort",
+            ]
+            .map(str::to_owned)
+            .to_vec(),
+            unroll_bound: 4,
+            fast_math: float::FastMath::default(),
+            instruction_fast_math: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Verdict {
+    Equivalent,
+    /// Every path was equivalent, but some paths ran into the unroll bound and were not
+    /// explored to the end.
+    EquivalentUpTo {
+        unroll_bound: usize,
+        incomplete_paths: usize,
+    },
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::Equivalent => write!(f, "equivalent"),
+            Verdict::EquivalentUpTo {
+                unroll_bound,
+                incomplete_paths,
+            } => write!(
+                f,
+                "equivalent up to {} ({} exceeding the bound {} incomplete)",
+                plural(*unroll_bound, "iteration", "iterations"),
+                plural(*incomplete_paths, "path", "paths"),
+                if *incomplete_paths == 1 { "is" } else { "are" },
+            ),
+        }
+    }
+}
+
+/// `count` followed by the noun in the form for that many.
+fn plural(count: usize, one: &str, many: &str) -> String {
+    match count {
+        1 => format!("{count} {one}"),
+        _ => format!("{count} {many}"),
+    }
+}
+
+#[derive(Debug, Default)]
+struct Stats {
+    /// Pairs of paths that were run up to an effect.
+    explored_paths: usize,
+    /// Combinations of branch outcomes that were dropped because their path condition
+    /// is unsatisfiable.
+    pruned_paths: usize,
+    /// Pairs of branches whose conditions were proven equivalent or complementary.
+    corresponding_branches: usize,
+    /// Paths that were joined into another path at the same positions.
+    merged_paths: usize,
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "explored {}, pruned {}, matched {}, merged {}",
+            plural(self.explored_paths, "path", "paths"),
+            plural(
+                self.pruned_paths,
+                "infeasible branch combination",
+                "infeasible branch combinations"
+            ),
+            plural(
+                self.corresponding_branches,
+                "corresponding branch",
+                "corresponding branches"
+            ),
+            plural(self.merged_paths, "path", "paths"),
+        )
+    }
+}
+
+#[derive(Clone)]
 struct VerifierState {
-    local_addresses: RefCell<HashMap<llvm_ir::Name, usize>>,
-    left: Function,
-    right: Function,
-    z3_state: String,
-    memory_generator_counter: usize,
+    /// Shared by all paths, so that a register is at the same address in all of them and
+    /// paths can be merged.
+    local_addresses: Rc<RefCell<HashMap<(usize, llvm_ir::Name), usize>>>,
+    module: Rc<Module>,
+    config: Rc<Config>,
+    left: Rc<Function>,
+    right: Rc<Function>,
+    /// Commands that define the state of this path for the solver.
+    z3_state: Vec<Rc<Sexp>>,
+    solver: Rc<RefCell<Solver>>,
+    /// Shared by all paths, so that snapshots of different paths never share a name.
+    memory_generator_counter: Rc<Cell<usize>>,
+    /// Arbitrary words that each side took on this path, see `poison`.
+    taken_words: Cell<TakenWords>,
     intersting_consts: Vec<String>,
-    goal: Vec<Sexp>,
+    /// Named facts that must all hold, checked together by `check_sat`.
+    goal: Vec<(String, Sexp)>,
+    /// Blocks of allocas that code outside the function may access, apart from those
+    /// captured by pointer stores.
+    captured_blocks: Vec<Sexp>,
+    /// The block a pointer was stored into, the block of that pointer, and the latter if
+    /// it is captured by the store or `NULL_BLOCK` if not.
+    pointer_stores: Vec<(Sexp, Sexp, Sexp)>,
+    /// The exception being propagated and its sort, once a call unwound.
+    exception: Option<(Sexp, Sexp)>,
+    declared_functions: HashSet<String>,
+    frame_counter: usize,
+    back_edges: RefCell<HashMap<String, Rc<cfg::BackEdges>>>,
+    /// Index of each alloca of a function among those of its frame, see `alloca_block`.
+    alloca_indices: RefCell<HashMap<String, Rc<HashMap<Name, usize>>>>,
+    proven_loops: HashSet<LoopHeaders>,
+    unsynchronized_loops: HashSet<LoopHeaders>,
+    /// While a loop invariant is tried out, counts the checks that failed instead of
+    /// reporting them.
+    failed_checks: Option<Rc<Cell<usize>>>,
+}
+
+/// One of the compared functions: the original on the left, or the transformed one on
+/// the right, which must refine it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Left,
+    Right,
+}
+
+/// A state of both functions: the verifier state, the memory of each side and the
+/// position of each side.
+type Path = (
+    VerifierState,
+    MemorySnapshot,
+    MemorySnapshot,
+    Position,
+    Position,
+);
+
+#[derive(Default)]
+struct Exploration {
+    stats: Stats,
+    incomplete_paths: usize,
+    /// Paths that came back to the loop headers being iterated.
+    arrivals: Vec<Path>,
 }
 
+/// A state of memory, together with the frame whose registers are currently in use.
 #[derive(Debug, Clone, Copy)]
 struct MemorySnapshot {
     index: usize,
+    frame: usize,
 }
 
 impl Display for MemorySnapshot {
@@ -50,43 +248,130 @@ impl ToSexp for MemorySnapshot {
 }
 
 impl VerifierState {
-    fn new(left: Function, right: Function) -> Self {
+    fn new(module: Rc<Module>, config: Config, left: Function, right: Function) -> Self {
         Self {
-            local_addresses: RefCell::new(HashMap::new()),
-            left,
-            right,
-            z3_state: "".to_owned(),
-            memory_generator_counter: 0,
+            local_addresses: Rc::new(RefCell::new(HashMap::new())),
+            module,
+            config: Rc::new(config),
+            left: Rc::new(left),
+            right: Rc::new(right),
+            z3_state: vec![],
+            solver: Rc::new(RefCell::new(Solver::default())),
+            memory_generator_counter: Rc::new(Cell::new(0)),
+            taken_words: Cell::new(TakenWords::default()),
             intersting_consts: vec![],
             goal: vec![],
+            captured_blocks: vec![],
+            pointer_stores: vec![],
+            exception: None,
+            declared_functions: HashSet::new(),
+            frame_counter: 0,
+            back_edges: RefCell::new(HashMap::new()),
+            alloca_indices: RefCell::new(HashMap::new()),
+            proven_loops: HashSet::new(),
+            unsynchronized_loops: HashSet::new(),
+            failed_checks: None,
         }
     }
 
-    fn compare_functions(mut self) -> bool {
+    fn compare_functions(mut self) -> (Verdict, Stats) {
         let memory = self.new_memory();
         self.add_z3_line(declare_const(memory, memory_ty()));
+        self.declare_block_address();
+        self.declare_arbitrary_words();
         for p in self.left.parameters.clone() {
             let name = format!("param_{}", p.name);
-            let size = self.size_of_ty(&p.ty);
-            let addr = self.address_of_name(&p.name);
-            let value = self.load_from_addr(addr, size, memory);
-            self.add_z3_line(define_const(&*name, bv_ty(size * 8), value));
+            let addr = self.address_of_name(&p.name, memory.frame);
+            let value = self.load_from_addr(bv_hex(addr, 8), &p.ty, memory);
+            self.add_z3_line(define_const(&*name, self.sort_of_ty(&p.ty), value));
+            // Each scalar of an argument is poison or not as a whole, and not at all if
+            // the argument is `noundef`.
+            let poison = self.register_poison(&p.name, &p.ty, memory);
+            let allowed = match p.attributes.contains(&ParameterAttribute::NoUndef) {
+                true => self.no_poison(&p.ty),
+                false => self.lane_poison(&p.ty, vec![(poison.clone(), p.ty.clone())]),
+            };
+            self.add_z3_line(Sexp::s2("assert", Sexp::s3("=", poison, allowed)));
+            if let llvm_ir::Type::PointerType { .. } = &*p.ty {
+                // Pointer arguments can only point to memory that existed before the call.
+                let block = self.pointer_block(&*name, &p.ty);
+                self.add_z3_line(Sexp::s2(
+                    "assert",
+                    Sexp::s3("bvult", block, bv_hex(FIRST_ALLOCA_BLOCK, BLOCK_BITS / 8)),
+                ));
+            }
             self.intersting_consts.push(name);
         }
         self.compare_bb_start(0, 0, memory, memory)
     }
 
+    /// Checks that both functions behave the same when started at the given basic blocks
+    /// in any pair of states that satisfies `precondition`. The precondition is a
+    /// formula over the memories, which also hold the registers, of the two sides. Phis
+    /// of the starting blocks are part of that state.
+    pub fn compare_from(
+        mut self,
+        left_bb: &Name,
+        right_bb: &Name,
+        precondition: impl FnOnce(&mut Self, MemorySnapshot, MemorySnapshot) -> Sexp,
+    ) -> (Verdict, Stats) {
+        let left_memory = self.new_memory();
+        self.add_z3_line(declare_const(left_memory, memory_ty()));
+        let right_memory = self.new_memory();
+        self.add_z3_line(declare_const(right_memory, memory_ty()));
+        self.declare_block_address();
+        self.declare_arbitrary_words();
+        let assumption = precondition(&mut self, left_memory, right_memory);
+        self.add_z3_line(Sexp::s2("assert", assumption));
+        let block_index = |f: &Function, name: &Name| {
+            f.basic_blocks
+                .iter()
+                .position(|x| x.name == *name)
+                .unwrap_or_else(|| panic!("no block {name} in {}", f.name))
+        };
+        let left_bb = block_index(&self.left, left_bb);
+        let right_bb = block_index(&self.right, right_bb);
+        self.compare_bb_start(left_bb, right_bb, left_memory, right_memory)
+    }
+
+    fn declare_block_address(&mut self) {
+        self.add_z3_line(Sexp::s4(
+            "declare-fun",
+            "block_address",
+            Sexp::s1(bv_ty(BLOCK_BITS)),
+            bv_ty(64),
+        ));
+        // Only pointers without provenance have the null address, no allocated block does.
+        let is_null = |x: Sexp| Sexp::s3("=", x, bv_hex(NULL_BLOCK, BLOCK_BITS / 8));
+        self.add_z3_line(Sexp::s2(
+            "assert",
+            Sexp::s3(
+                "forall",
+                Sexp::s1(Sexp::s2("block", bv_ty(BLOCK_BITS))),
+                Sexp::s3(
+                    "=",
+                    Sexp::s3("=", Sexp::s2("block_address", "block"), bv_hex(0, 8)),
+                    is_null("block".to_sexp()),
+                ),
+            ),
+        ));
+    }
+
+    fn function(&self, side: Side) -> Rc<Function> {
+        match side {
+            Side::Left => self.left.clone(),
+            Side::Right => self.right.clone(),
+        }
+    }
+
     fn add_z3_line(&mut self, arg: Sexp) {
-        self.z3_state += &arg.to_pretty(100);
-        self.z3_state.push('\n');
+        self.z3_state.push(Rc::new(arg));
     }
 
     fn new_memory(&mut self) -> MemorySnapshot {
-        let r = MemorySnapshot {
-            index: self.memory_generator_counter,
-        };
-        self.memory_generator_counter += 1;
-        r
+        let index = self.memory_generator_counter.get();
+        self.memory_generator_counter.set(index + 1);
+        MemorySnapshot { index, frame: 0 }
     }
 
     fn compare_bb_start(
@@ -95,125 +380,410 @@ impl VerifierState {
         right_bb: usize,
         left_memory: MemorySnapshot,
         right_memory: MemorySnapshot,
-    ) -> bool {
-        let mut queue = VecDeque::new();
-        queue.push_back((
+    ) -> (Verdict, Stats) {
+        let start = (
             self.clone(),
             left_memory,
             right_memory,
-            Position { bb: 0, instr: 0 },
-            Position { bb: 0, instr: 0 },
-        ));
+            Position::at(&self.left, left_bb),
+            Position::at(&self.right, right_bb),
+        );
+        let Exploration {
+            stats,
+            incomplete_paths,
+            ..
+        } = self.explore(start, None);
+        let verdict = if incomplete_paths == 0 {
+            Verdict::Equivalent
+        } else {
+            Verdict::EquivalentUpTo {
+                unroll_bound: self.config.unroll_bound,
+                incomplete_paths,
+            }
+        };
+        (verdict, stats)
+    }
+
+    /// Runs both sides from `starts` until they reach effects to compare. A loop header is
+    /// only such an effect if both sides are at one. When `quiet`, the failed checks of the
+    /// new function are only counted.
+    fn run_both(
+        &mut self,
+        starts: ((Position, MemorySnapshot), (Position, MemorySnapshot)),
+        quiet: bool,
+    ) -> ((MemorySnapshot, Effect), (MemorySnapshot, Effect), usize) {
+        let ((left_pos, left_memory), (right_pos, right_memory)) = starts;
+        let failed_checks = Rc::new(Cell::new(0));
+        let outer_checks = self.failed_checks.clone();
+        if quiet {
+            self.failed_checks = Some(failed_checks.clone());
+        }
+        let (mut left_memory, mut left_effect) =
+            self.run_until_effect(Side::Left, left_pos, left_memory);
+        let (mut right_memory, mut right_effect) =
+            self.run_until_effect(Side::Right, right_pos, right_memory);
+        loop {
+            match (&left_effect, &right_effect) {
+                (Effect::LoopHeader { .. }, Effect::LoopHeader { .. }) => break,
+                (Effect::LoopHeader { pos, .. }, _) => {
+                    (left_memory, left_effect) =
+                        self.run_until_effect(Side::Left, pos.clone(), left_memory);
+                }
+                (_, Effect::LoopHeader { pos, .. }) => {
+                    (right_memory, right_effect) =
+                        self.run_until_effect(Side::Right, pos.clone(), right_memory);
+                }
+                _ => break,
+            }
+        }
+        self.failed_checks = outer_checks;
+        (
+            (left_memory, left_effect),
+            (right_memory, right_effect),
+            failed_checks.get(),
+        )
+    }
+
+    /// Runs both functions side by side from `start`, checking that they have the same
+    /// effects. When `iterating` is a pair of loop headers, the paths that come back to
+    /// those headers are collected instead of going around the loops again.
+    fn explore(&self, start: Path, iterating: Option<LoopHeaders>) -> Exploration {
+        let mut exploration = Exploration::default();
+        let mut queue = VecDeque::from([start]);
         while let Some((mut this, left_memory, right_memory, left_pos, right_pos)) =
             queue.pop_front()
         {
-            let (left_memory, left_effect) =
-                this.run_until_effect(self.left.clone(), left_pos, left_memory);
-            let (right_memory, right_effect) =
-                this.run_until_effect(self.right.clone(), right_pos, right_memory);
+            exploration.stats.explored_paths += 1;
+            let starts = ((left_pos, left_memory), (right_pos, right_memory));
+            // When only the left branches, the right starts over on each of its branches,
+            // so its checks only count there, where they know which way the left went.
+            let unchecked = this.clone();
+            let taken_words = this.taken_words.get();
+            let ((mut left_memory, mut left_effect), (mut right_memory, mut right_effect), failed) =
+                this.run_both(starts.clone(), true);
+            let left_branches = matches!(left_effect, Effect::CondBr { .. })
+                && !matches!(right_effect, Effect::CondBr { .. });
+            if failed > 0 && !left_branches {
+                this = unchecked;
+                ((left_memory, left_effect), (right_memory, right_effect), _) =
+                    this.run_both(starts.clone(), false);
+            }
             match (left_effect.clone(), right_effect.clone()) {
+                (Effect::UnrollBoundReached, _) | (_, Effect::UnrollBoundReached) => {
+                    exploration.incomplete_paths += 1;
+                }
+                (
+                    Effect::LoopHeader {
+                        pos: left_pos,
+                        entry: left_entry,
+                    },
+                    Effect::LoopHeader {
+                        pos: right_pos,
+                        entry: right_entry,
+                    },
+                ) => {
+                    let headers = loop_headers(left_memory, right_memory, &left_pos, &right_pos);
+                    // Only coming around the loops leads back to the state the invariant
+                    // was proven for. Entering them anew, like on the next iteration of an
+                    // outer loop, starts over from another state.
+                    let around =
+                        left_entry == LoopEntry::BackEdge && right_entry == LoopEntry::BackEdge;
+                    if !around {
+                        this.proven_loops.remove(&headers);
+                    }
+                    if around && iterating == Some(headers) {
+                        exploration.arrivals.push((
+                            this,
+                            left_memory,
+                            right_memory,
+                            left_pos,
+                            right_pos,
+                        ));
+                        continue;
+                    }
+                    if this.proven_loops.contains(&headers) {
+                        // Going around a loop with an inductive invariant again adds nothing.
+                        continue;
+                    }
+                    if !this.unsynchronized_loops.contains(&headers) {
+                        if let Some(path) =
+                            this.prove_loop(left_memory, right_memory, &left_pos, &right_pos)
+                        {
+                            enqueue(&mut queue, &mut exploration.stats, path);
+                            continue;
+                        }
+                        this.unsynchronized_loops.insert(headers);
+                    }
+                    enqueue(
+                        &mut queue,
+                        &mut exploration.stats,
+                        (this, left_memory, right_memory, left_pos, right_pos),
+                    );
+                }
+                (
+                    Effect::Diverge {
+                        function: left_function,
+                        arguments: left_arguments,
+                    },
+                    Effect::Diverge {
+                        function: right_function,
+                        arguments: right_arguments,
+                    },
+                ) => {
+                    // A panic unwinds and reports its message and location, an abort does
+                    // neither, so the optimizer may only merge and reorder calls to the
+                    // same function with the same arguments.
+                    if left_function != right_function
+                        || left_arguments.len() != right_arguments.len()
+                    {
+                        this.check_sat(&format!(
+                            "Diverging call to {left_function} replaced by {right_function}"
+                        ));
+                        continue;
+                    }
+                    for (i, (left_arg, right_arg)) in
+                        left_arguments.iter().zip(&right_arguments).enumerate()
+                    {
+                        let ty = this.type_of(left_arg);
+                        let left = (
+                            this.operand_to_sexp(Side::Left, left_arg, left_memory),
+                            this.operand_poison(left_arg, left_memory),
+                        );
+                        let right = (
+                            this.operand_to_sexp(Side::Right, right_arg, right_memory),
+                            this.operand_poison(right_arg, right_memory),
+                        );
+                        let sort = this.sort_of_ty(&ty);
+                        this.add_refinement_compare(&format!("argument_{i}"), sort, left, right);
+                    }
+                    // Memory that outlives the function is still observable, by whoever
+                    // catches the panic or by the panic hook.
+                    this.add_memory_compare(
+                        "final_memory",
+                        left_memory,
+                        right_memory,
+                        Footprint::outliving(),
+                    );
+                    this.check_sat("Diverging with different memory");
+                }
                 (Effect::Return(left_op), Effect::Return(right_op)) => {
+                    this.add_memory_compare(
+                        "final_memory",
+                        left_memory,
+                        right_memory,
+                        Footprint::outliving(),
+                    );
                     this.compare_returns(left_op, right_op, left_memory, right_memory);
-                    return true;
                 }
                 (
                     Effect::Call {
                         call: left_call,
                         return_pos: left_pos,
+                        labels: left_labels,
                     },
                     Effect::Call {
                         call: right_call,
                         return_pos: right_pos,
+                        labels: right_labels,
                     },
                 ) => {
-                    this.clone()
-                        .compare_calls(left_call, right_call, left_memory, right_memory);
-                    queue.push_back((this, left_memory, right_memory, left_pos, right_pos));
+                    this.clone().compare_calls(
+                        left_call.clone(),
+                        right_call.clone(),
+                        left_memory,
+                        right_memory,
+                    );
+                    let (left_memory, right_memory) =
+                        this.return_from_call(&left_call, &right_call, left_memory, right_memory);
+                    // Both sides call the same function in the same state, so either both
+                    // calls unwind or neither does.
+                    if this.may_unwind(&left_call) || this.may_unwind(&right_call) {
+                        let mut t = this.clone();
+                        let ty = t
+                            .landing_pad_type(Side::Left, &left_pos, &left_labels)
+                            .or_else(|| t.landing_pad_type(Side::Right, &right_pos, &right_labels));
+                        t.start_unwinding(ty);
+                        let left =
+                            t.unwind_continuation(Side::Left, &left_pos, &left_labels, left_memory);
+                        let right = t.unwind_continuation(
+                            Side::Right,
+                            &right_pos,
+                            &right_labels,
+                            right_memory,
+                        );
+                        match (left, right) {
+                            (Some(left), Some(right)) => enqueue(
+                                &mut queue,
+                                &mut exploration.stats,
+                                (t, left.1, right.1, left.0, right.0),
+                            ),
+                            _ => exploration.incomplete_paths += 1,
+                        }
+                    }
+                    let left =
+                        this.return_continuation(Side::Left, &left_pos, &left_labels, left_memory);
+                    let right = this.return_continuation(
+                        Side::Right,
+                        &right_pos,
+                        &right_labels,
+                        right_memory,
+                    );
+                    match (left, right) {
+                        (Some(left), Some(right)) => enqueue(
+                            &mut queue,
+                            &mut exploration.stats,
+                            (this, left.1, right.1, left.0, right.0),
+                        ),
+                        _ => exploration.incomplete_paths += 1,
+                    }
                 }
-                (Effect::CondBr(left_br), Effect::CondBr(right_br)) => {
-                    let left_cond_false = Sexp::s3(
-                        "=",
-                        self.operand_to_sexp(&left_br.condition, left_memory),
-                        "#x00",
+                (
+                    Effect::Resume {
+                        value: left_value,
+                        sort,
+                    },
+                    Effect::Resume {
+                        value: right_value, ..
+                    },
+                ) => {
+                    this.add_memory_compare(
+                        "final_memory",
+                        left_memory,
+                        right_memory,
+                        Footprint::outliving(),
                     );
-                    let left_cond_true = Sexp::s2("not", left_cond_false.clone());
-                    let right_cond_false = Sexp::s3(
-                        "=",
-                        self.operand_to_sexp(&right_br.condition, right_memory),
-                        "#x00",
+                    this.add_interesting_compare("exception", sort, left_value, right_value);
+                    this.check_sat("Unwinding with different exception or memory");
+                }
+                (
+                    Effect::CondBr {
+                        br: left_br,
+                        pos: left_pos,
+                    },
+                    Effect::CondBr {
+                        br: right_br,
+                        pos: right_pos,
+                    },
+                ) => {
+                    let [(left_cond_true, left_true), (left_cond_false, left_false)] =
+                        this.branch_outcomes(Side::Left, &left_br, &left_pos, left_memory);
+                    let [(right_cond_true, right_true), (right_cond_false, right_false)] =
+                        this.branch_outcomes(Side::Right, &right_br, &right_pos, right_memory);
+                    let same = (&left_cond_true, &left_true, &right_cond_true, &right_true);
+                    let left_only = (&left_cond_true, &left_true, &right_cond_false, &right_false);
+                    let right_only = (&left_cond_false, &left_false, &right_cond_true, &right_true);
+                    let neither = (
+                        &left_cond_false,
+                        &left_false,
+                        &right_cond_false,
+                        &right_false,
                     );
-                    let right_cond_true = Sexp::s2("not", right_cond_false.clone());
-                    let left_true_pos = pos_of_bb_name(&left_br.true_dest, &self.left);
-                    let left_false_pos = pos_of_bb_name(&left_br.false_dest, &self.left);
-                    let right_true_pos = pos_of_bb_name(&right_br.true_dest, &self.right);
-                    let right_false_pos = pos_of_bb_name(&right_br.false_dest, &self.right);
-                    let this_true_true = {
-                        let mut t = this.clone();
-                        t.add_z3_line(Sexp::s2("assert", left_cond_true.clone()));
-                        t.add_z3_line(Sexp::s2("assert", right_cond_true.clone()));
-                        t
-                    };
-                    let this_true_false = {
-                        let mut t = this.clone();
-                        t.add_z3_line(Sexp::s2("assert", left_cond_true.clone()));
-                        t.add_z3_line(Sexp::s2("assert", right_cond_false.clone()));
-                        t
+                    // Usually both sides branch on the same condition, possibly negated, and
+                    // only two of the combinations can happen.
+                    let combinations = if this.is_valid(Sexp::s3(
+                        "=",
+                        left_cond_true.clone(),
+                        right_cond_true.clone(),
+                    )) {
+                        exploration.stats.corresponding_branches += 1;
+                        vec![same, neither]
+                    } else if this.is_valid(Sexp::s3(
+                        "distinct",
+                        left_cond_true.clone(),
+                        right_cond_true.clone(),
+                    )) {
+                        exploration.stats.corresponding_branches += 1;
+                        vec![left_only, right_only]
+                    } else {
+                        vec![same, left_only, right_only, neither]
                     };
-                    let this_false_true = {
+                    for (left_cond, left, right_cond, right) in combinations {
                         let mut t = this.clone();
-                        t.add_z3_line(Sexp::s2("assert", left_cond_false.clone()));
-                        t.add_z3_line(Sexp::s2("assert", right_cond_true.clone()));
-                        t
+                        t.add_z3_line(Sexp::s2("assert", left_cond.clone()));
+                        t.add_z3_line(Sexp::s2("assert", right_cond.clone()));
+                        if !t.is_satisfiable() {
+                            exploration.stats.pruned_paths += 1;
+                            continue;
+                        }
+                        let (Some(left), Some(right)) = (left, right) else {
+                            exploration.incomplete_paths += 1;
+                            continue;
+                        };
+                        enqueue(
+                            &mut queue,
+                            &mut exploration.stats,
+                            (t, left.1, right.1, left.0.clone(), right.0.clone()),
+                        );
+                    }
+                }
+                (Effect::CondBr { br, pos }, _) | (_, Effect::CondBr { br, pos }) => {
+                    // Only one side branches, the other one waits where it started until the
+                    // branching side reaches an effect too.
+                    let right = !matches!(left_effect, Effect::CondBr { .. });
+                    let (side, waiting, memory) = match right {
+                        false => (Side::Left, Side::Right, left_memory),
+                        true => (Side::Right, Side::Left, right_memory),
                     };
-                    let this_false_false = {
+                    for (cond, next) in this.branch_outcomes(side, &br, &pos, memory) {
                         let mut t = this.clone();
-                        t.add_z3_line(Sexp::s2("assert", left_cond_false.clone()));
-                        t.add_z3_line(Sexp::s2("assert", right_cond_false.clone()));
-                        t
-                    };
-                    queue.push_back((
-                        this_true_true,
-                        left_memory,
-                        right_memory,
-                        left_true_pos,
-                        right_true_pos,
-                    ));
-                    queue.push_back((
-                        this_true_false,
-                        left_memory,
-                        right_memory,
-                        left_true_pos,
-                        right_false_pos,
-                    ));
-                    queue.push_back((
-                        this_false_true,
-                        left_memory,
-                        right_memory,
-                        left_false_pos,
-                        right_true_pos,
-                    ));
-                    queue.push_back((
-                        this_false_false,
-                        left_memory,
-                        right_memory,
-                        left_false_pos,
-                        right_false_pos,
-                    ));
+                        t.add_z3_line(Sexp::s2("assert", cond));
+                        if !t.is_satisfiable() {
+                            exploration.stats.pruned_paths += 1;
+                            continue;
+                        }
+                        let Some((next_pos, next_memory)) = next else {
+                            exploration.incomplete_paths += 1;
+                            continue;
+                        };
+                        let (left_start, right_start) = starts.clone();
+                        // The waiting side takes the same words again when it starts over.
+                        t.taken_words
+                            .set(t.taken_words.get().restart(waiting, taken_words));
+                        enqueue(
+                            &mut queue,
+                            &mut exploration.stats,
+                            match right {
+                                false => (t, next_memory, right_start.1, next_pos, right_start.0),
+                                true => (t, left_start.1, next_memory, left_start.0, next_pos),
+                            },
+                        );
+                    }
                 }
                 _ => {
                     let reason = match (left_effect, right_effect) {
-                        (Effect::Call { .. }, Effect::Return(_)) => "Call missed in new",
-                        (Effect::Return(_), Effect::Call { .. }) => "Call happened in new",
-                        (Effect::CondBr(_), _)
-                        | (_, Effect::CondBr(_))
+                        (Effect::Diverge { function, .. }, _) => {
+                            format!("Diverging call to {function} missed in new")
+                        }
+                        (_, Effect::Diverge { function, .. }) => {
+                            format!("Diverging call to {function} happened in new")
+                        }
+                        (Effect::Call { .. }, Effect::Return(_) | Effect::Resume { .. }) => {
+                            "Call missed in new".to_owned()
+                        }
+                        (Effect::Return(_) | Effect::Resume { .. }, Effect::Call { .. }) => {
+                            "Call happened in new".to_owned()
+                        }
+                        (Effect::Resume { .. }, Effect::Return(_)) => {
+                            "Unwinding missed in new".to_owned()
+                        }
+                        (Effect::Return(_), Effect::Resume { .. }) => {
+                            "Unwinding happened in new".to_owned()
+                        }
+                        (Effect::CondBr { .. }, _)
+                        | (_, Effect::CondBr { .. })
+                        | (Effect::UnrollBoundReached, _)
+                        | (_, Effect::UnrollBoundReached)
+                        | (Effect::LoopHeader { .. }, _)
+                        | (_, Effect::LoopHeader { .. })
                         | (Effect::Call { .. }, Effect::Call { .. })
-                        | (Effect::Return(_), Effect::Return(_)) => unreachable!(),
+                        | (Effect::Return(_), Effect::Return(_))
+                        | (Effect::Resume { .. }, Effect::Resume { .. }) => unreachable!(),
                     };
-                    this.check_sat(reason);
+                    this.check_sat(&reason);
                 }
             }
         }
-        true
+        exploration
     }
 
     fn add_interesting_compare(
@@ -227,12 +797,38 @@ impl VerifierState {
         let name_right = format!("{name}_right");
         self.add_z3_line(define_const(name_left.as_str(), ty.clone(), left_value));
         self.add_z3_line(define_const(name_right.as_str(), ty, right_value));
-        self.goal
-            .push(Sexp::s3("=", name_left.as_str(), name_right.as_str()));
+        self.goal.push((
+            name.to_owned(),
+            Sexp::s3("=", name_left.as_str(), name_right.as_str()),
+        ));
         self.intersting_consts.push(name_left);
         self.intersting_consts.push(name_right);
     }
 
+    /// Like `add_interesting_compare`, for values given with their poison masks, where
+    /// the right value only has to refine the left one.
+    fn add_refinement_compare(
+        &mut self,
+        name: &str,
+        ty: Sexp,
+        (left_value, left_poison): (Sexp, Sexp),
+        (right_value, right_poison): (Sexp, Sexp),
+    ) {
+        let names = ["left", "right", "left_poison", "right_poison"].map(|x| format!("{name}_{x}"));
+        for (x, value) in names
+            .iter()
+            .zip([left_value, right_value, left_poison, right_poison])
+        {
+            self.add_z3_line(define_const(x.as_str(), ty.clone(), value));
+        }
+        let [left, right, left_poison, right_poison] = names.clone().map(|x| x.to_sexp());
+        self.goal.push((
+            name.to_owned(),
+            poison::refines(left, left_poison, right, right_poison),
+        ));
+        self.intersting_consts.extend(names);
+    }
+
     fn compare_returns(
         mut self,
         left_op: Option<Operand>,
@@ -240,66 +836,268 @@ impl VerifierState {
         left_memory: MemorySnapshot,
         right_memory: MemorySnapshot,
     ) {
-        let Some(left_op) = &left_op else {
-            return;
-        };
-        let Some(right_op) = &right_op else {
+        if let (Some(left_op), Some(right_op)) = (&left_op, &right_op) {
+            let left = (
+                self.operand_to_sexp(Side::Left, left_op, left_memory),
+                self.operand_poison(left_op, left_memory),
+            );
+            let right = (
+                self.operand_to_sexp(Side::Right, right_op, right_memory),
+                self.operand_poison(right_op, right_memory),
+            );
+            let ty = self.type_of(left_op);
+            match self.aggregate_fields(&ty) {
+                // Each field is a goal of its own, so that the ones that differ are named.
+                Some(fields) => {
+                    for (i, (field_ty, _)) in fields.iter().enumerate() {
+                        if self.value_bits(field_ty) == 0 {
+                            continue;
+                        }
+                        let field = |(value, poison): &(Sexp, Sexp)| {
+                            (
+                                self.extract_field(value.clone(), &ty, i),
+                                self.extract_field(poison.clone(), &ty, i),
+                            )
+                        };
+                        let (mut left_field, mut right_field) = (field(&left), field(&right));
+                        left_field.0 = self.without_zero_sign(field_ty, left_field.0);
+                        right_field.0 = self.without_zero_sign(field_ty, right_field.0);
+                        let sort = self.sort_of_ty(field_ty);
+                        let name = format!("return_{i}");
+                        self.add_refinement_compare(&name, sort, left_field, right_field);
+                    }
+                }
+                None => {
+                    let left = (self.without_zero_sign(&ty, left.0), left.1);
+                    let right = (self.without_zero_sign(&ty, right.0), right.1);
+                    let sort = self.sort_of_ty(&ty);
+                    self.add_refinement_compare("return", sort, left, right);
+                }
+            }
+        }
+        self.check_sat("Return with different values or memory");
+    }
+
+    /// Compares the memory that outlives the function or is visible to a callee.
+    fn add_memory_compare(
+        &mut self,
+        name: &str,
+        left_memory: MemorySnapshot,
+        right_memory: MemorySnapshot,
+        footprint: Footprint,
+    ) {
+        // Asserting that an address is in an empty footprint would make every goal hold,
+        // and there is nothing to compare in it anyway.
+        if footprint.is_empty() {
+            self.goal.push((name.to_owned(), "true".to_sexp()));
             return;
-        };
-        let left_value = self.operand_to_sexp(left_op, left_memory);
-        let right_value = self.operand_to_sexp(right_op, right_memory);
-        let size = self.size_of_operand(left_op);
-        self.add_interesting_compare("return", bv_ty(size * 8), left_value, right_value);
-        self.check_sat("Return with different values");
+        }
+        let addr = format!("{name}_address");
+        self.add_z3_line(declare_const(&*addr, bv_ty(64)));
+        self.add_z3_line(Sexp::s2("assert", footprint.contains(&*addr)));
+        self.intersting_consts.push(addr.clone());
+        let left = Sexp::s3("select", left_memory, &*addr);
+        self.add_interesting_compare(
+            name,
+            bv_ty(BLOCK_BITS + 8),
+            left.clone(),
+            Sexp::s3("select", right_memory, &*addr),
+        );
+        // Poison in the old memory may be replaced by anything.
+        let (_, same) = self.goal.pop().unwrap();
+        let poison = Sexp::s3(
+            "=",
+            extract(left, BLOCK_BITS + 7, 8),
+            bv_hex(POISON_BLOCK, BLOCK_BITS / 8),
+        );
+        self.goal
+            .push((name.to_owned(), Sexp::s3("or", poison, same)));
     }
 
-    fn operand_to_sexp(&self, operand: &llvm_ir::Operand, memory: MemorySnapshot) -> Sexp {
+    fn operand_to_sexp(
+        &self,
+        side: Side,
+        operand: &llvm_ir::Operand,
+        memory: MemorySnapshot,
+    ) -> Sexp {
         match operand {
             llvm_ir::Operand::LocalOperand { name, ty } => {
-                let size = self.size_of_ty(ty);
-                let addr = self.address_of_name(name);
-                self.load_from_addr(addr, size, memory)
+                let addr = self.address_of_name(name, memory.frame);
+                let value = self.load_from_addr(bv_hex(addr, 8), ty, memory);
+                self.without_unused_bits(ty, value)
             }
-            llvm_ir::Operand::ConstantOperand(c) => match &**c {
-                &llvm_ir::Constant::Int { bits, value } => {
-                    bv_hex(value as usize, bit_to_byte(bits as usize))
-                }
-                llvm_ir::Constant::GlobalReference { name, ty } => {
-                    let size = self.size_of_ty(ty);
-                    let addr = self.address_of_name(name);
-                    self.load_from_addr(addr, size, memory)
-                }
-                _ => todo!(),
-            },
+            llvm_ir::Operand::ConstantOperand(c) => self.constant_to_sexp(side, c),
             llvm_ir::Operand::MetadataOperand => todo!(),
         }
     }
 
+    fn constant_to_sexp(&self, side: Side, c: &llvm_ir::ConstantRef) -> Sexp {
+        match &**c {
+            &llvm_ir::Constant::Int { bits, value } => {
+                bv_hex(value as usize, bit_to_byte(bits as usize))
+            }
+            llvm_ir::Constant::Null(ty) => self.null_pointer(ty),
+            llvm_ir::Constant::GlobalReference { name, .. } => {
+                self.block_pointer(self.global_block(name), &self.type_of(c))
+            }
+            llvm_ir::Constant::Struct { values, .. } => self.aggregate_value(
+                values
+                    .iter()
+                    .map(|x| self.constant_to_sexp(side, x))
+                    .collect(),
+            ),
+            llvm_ir::Constant::Array { elements, .. } => self.aggregate_value(
+                elements
+                    .iter()
+                    .map(|x| self.constant_to_sexp(side, x))
+                    .collect(),
+            ),
+            llvm_ir::Constant::Float(_) => self.float_constant(c),
+            llvm_ir::Constant::Vector(elements) => self.aggregate_value(
+                elements
+                    .iter()
+                    .map(|x| self.constant_to_sexp(side, x))
+                    .collect(),
+            ),
+            llvm_ir::Constant::Undef(ty) => self.undef_value(side, ty),
+            // Only the poison mask tells poison apart, see `poison`.
+            llvm_ir::Constant::Poison(ty) | llvm_ir::Constant::AggregateZero(ty) => {
+                let bits = self.value_bits(ty).to_string();
+                Sexp::s3("_", "bv0", &*bits)
+            }
+            llvm_ir::Constant::BitCast(cast) => {
+                let operand = self.constant_to_sexp(side, &cast.operand);
+                self.bitcast(operand, &self.type_of(&cast.operand), &cast.to_type)
+            }
+            llvm_ir::Constant::PtrToInt(cast) => {
+                let ptr = self.constant_to_sexp(side, &cast.operand);
+                let bits = self.value_bits(&cast.to_type);
+                self.ptr_to_int(ptr, &self.type_of(&cast.operand), bits)
+            }
+            llvm_ir::Constant::IntToPtr(cast) => {
+                let int = self.constant_to_sexp(side, &cast.operand);
+                let bits = self.value_bits(&self.type_of(&cast.operand));
+                self.int_to_ptr(int, bits, &cast.to_type)
+            }
+            llvm_ir::Constant::AddrSpaceCast(cast) => {
+                let ptr = self.constant_to_sexp(side, &cast.operand);
+                self.cast_pointer(ptr, &self.type_of(&cast.operand), &cast.to_type)
+            }
+            // llvm-ir does not give the source element type of constant getelementptrs, so
+            // only those that do not move the address can be computed.
+            llvm_ir::Constant::GetElementPtr(gep) => {
+                let moves = gep
+                    .indices
+                    .iter()
+                    .any(|x| !matches!(&**x, llvm_ir::Constant::Int { value: 0, .. }));
+                if moves {
+                    panic!("constant {c} is not supported, its source element type is unknown");
+                }
+                self.constant_to_sexp(side, &gep.address)
+            }
+            _ => panic!("constant {c} is not supported"),
+        }
+    }
+
     fn store_in_addr(
         &mut self,
-        addr: usize,
-        size: usize,
+        addr: Sexp,
+        ty: &llvm_ir::TypeRef,
+        o: Sexp,
+        memory: MemorySnapshot,
+    ) -> MemorySnapshot {
+        self.store_value(addr, ty, o, None, memory)
+    }
+
+    /// Stores a value of `side` in memory, where the bytes of its scalars that are poison
+    /// in `poison` are marked as such and vectors are packed. Registers need neither,
+    /// since they keep their poison masks apart.
+    fn store_in_memory(
+        &mut self,
+        side: Side,
+        addr: Sexp,
+        ty: &llvm_ir::TypeRef,
         o: Sexp,
+        poison: Sexp,
         memory: MemorySnapshot,
     ) -> MemorySnapshot {
-        let nm = self.new_memory();
+        self.store_value(addr, ty, o, Some((side, poison)), memory)
+    }
+
+    fn store_value(
+        &mut self,
+        addr: Sexp,
+        ty: &llvm_ir::TypeRef,
+        o: Sexp,
+        poison: Option<(Side, Sexp)>,
+        mut memory: MemorySnapshot,
+    ) -> MemorySnapshot {
+        if let (Some((side, mask)), Some(_)) = (&poison, self.packed_vector_size(ty)) {
+            let bytes_ty = self.packed_bytes_type(ty);
+            let bytes = self.pack_vector(o, ty);
+            let mask = self.pack_vector_poison(*side, mask.clone(), ty);
+            return self.store_value(addr, &bytes_ty, bytes, Some((*side, mask)), memory);
+        }
+        if let Some(fields) = self.aggregate_fields(ty) {
+            for (i, (field_ty, offset)) in fields.iter().enumerate() {
+                let value = self.extract_field(o.clone(), ty, i);
+                let mask = poison
+                    .as_ref()
+                    .map(|(side, x)| (*side, self.extract_field(x.clone(), ty, i)));
+                let addr = add_offset(&addr, *offset);
+                memory = self.store_value(addr, field_ty, value, mask, memory);
+            }
+            // A store to memory makes the padding undef, which is modeled as poison.
+            let padding = self.padding_bytes(ty, &fields);
+            if poison.is_none() || padding.is_empty() {
+                return memory;
+            }
+            let byte = Sexp::s3("concat", bv_hex(POISON_BLOCK, BLOCK_BITS / 8), bv_hex(0, 1));
+            let stored = padding.into_iter().fold(memory.to_sexp(), |stored, i| {
+                Sexp::s4("store", stored, add_offset(&addr, i), byte.clone())
+            });
+            let nm = MemorySnapshot {
+                frame: memory.frame,
+                ..self.new_memory()
+            };
+            self.add_z3_line(define_const(nm, memory_ty(), stored));
+            return nm;
+        }
+        let size = self.size_of_ty(ty);
+        let (mut block, data) = match &**ty {
+            llvm_ir::Type::PointerType { .. } => (
+                self.pointer_block("val", ty),
+                self.pointer_offset("val", ty),
+            ),
+            _ => (bv_hex(NULL_BLOCK, BLOCK_BITS / 8), "val".to_sexp()),
+        };
+        if let Some((_, mask)) = poison {
+            let poison_block = bv_hex(POISON_BLOCK, BLOCK_BITS / 8);
+            block = if_then_else(poison::is_poison(mask), poison_block, block);
+        }
+        let nm = MemorySnapshot {
+            frame: memory.frame,
+            ..self.new_memory()
+        };
         let mut stored = memory.to_sexp();
         for i in 0..size {
+            let significance = if self.is_big_endian() {
+                size - 1 - i
+            } else {
+                i
+            };
             stored = Sexp::s4(
                 "store",
                 stored,
-                bv_hex(addr + i, 8),
-                Sexp::s2(
-                    Sexp::s4(
-                        "_",
-                        "extract",
-                        &*(i * 8 + 7).to_string(),
-                        &*(i * 8).to_string(),
-                    ),
-                    "val",
+                add_offset(&addr, i),
+                Sexp::s3(
+                    "concat",
+                    "block",
+                    extract(data.clone(), significance * 8 + 7, significance * 8),
                 ),
             );
         }
+        let stored = Sexp::s3("let", Sexp::s1(Sexp::s2("block", block)), stored);
         self.add_z3_line(define_const(
             nm,
             memory_ty(),
@@ -308,91 +1106,209 @@ impl VerifierState {
         nm
     }
 
-    fn size_of_ty(&self, ty: &llvm_ir::TypeRef) -> usize {
-        match &**ty {
-            llvm_ir::Type::VoidType => 0,
-            llvm_ir::Type::IntegerType { bits } => bit_to_byte(*bits as usize),
-            llvm_ir::Type::FuncType { .. } => 8,
-            _ => todo!(),
-        }
+    fn load_from_addr(&self, addr: Sexp, ty: &llvm_ir::TypeRef, memory: MemorySnapshot) -> Sexp {
+        self.load_value(addr, ty, memory, false)
     }
 
-    fn size_of_operand(&self, operand: &llvm_ir::Operand) -> usize {
-        match operand {
-            llvm_ir::Operand::LocalOperand { name, ty } => self.size_of_ty(ty),
-            llvm_ir::Operand::ConstantOperand(c) => match &**c {
-                &llvm_ir::Constant::Int { bits, value } => bit_to_byte(bits as usize),
-                _ => todo!(),
-            },
-            llvm_ir::Operand::MetadataOperand => todo!(),
-        }
+    /// Loads a value from memory, where vectors are packed, unlike in registers.
+    fn load_from_memory(&self, addr: Sexp, ty: &llvm_ir::TypeRef, memory: MemorySnapshot) -> Sexp {
+        self.load_value(addr, ty, memory, true)
     }
 
-    fn load_from_addr(&self, addr: usize, size: usize, memory: MemorySnapshot) -> Sexp {
-        if size == 1 {
-            return Sexp::s3("select", memory, bv_hex(addr, 8));
+    fn load_value(
+        &self,
+        addr: Sexp,
+        ty: &llvm_ir::TypeRef,
+        memory: MemorySnapshot,
+        packed: bool,
+    ) -> Sexp {
+        if packed && self.packed_vector_size(ty).is_some() {
+            let bytes = self.load_value(addr, &self.packed_bytes_type(ty), memory, false);
+            return self.unpack_vector(bytes, ty);
+        }
+        if let Some(fields) = self.aggregate_fields(ty) {
+            let values = fields
+                .iter()
+                .map(|(field_ty, offset)| {
+                    self.load_value(add_offset(&addr, *offset), field_ty, memory, packed)
+                })
+                .collect();
+            return self.aggregate_value(values);
         }
-        let mut r = vec!["concat".to_sexp()];
-        for i in (0..size).rev() {
-            r.push(Sexp::s3("select", memory, bv_hex(addr + i, 8)));
+        let size = self.size_of_ty(ty);
+        let byte = |i| Sexp::s3("select", memory, add_offset(&addr, i));
+        let data = if size == 1 {
+            extract(byte(0), 7, 0)
+        } else {
+            let mut r = vec!["concat".to_sexp()];
+            for i in 0..size {
+                r.push(extract(byte(i), 7, 0));
+            }
+            if !self.is_big_endian() {
+                r[1..].reverse();
+            }
+            Sexp::List(r)
+        };
+        match &**ty {
+            llvm_ir::Type::PointerType { .. } => {
+                Sexp::s3("concat", extract(byte(0), BLOCK_BITS + 7, 8), data)
+            }
+            _ => data,
         }
-        Sexp::List(r)
     }
 
     fn check_sat(mut self, sat_message: &str) {
-        match &*self.goal {
+        let mut goal_names = vec![];
+        for (name, g) in std::mem::take(&mut self.goal) {
+            let goal_name = format!("{name}_holds");
+            // A declared constant rather than a definition, since the solver only gives
+            // the values of terms without quantifiers, which goals on memory have.
+            self.add_z3_line(declare_const(&*goal_name, "Bool"));
+            self.add_z3_line(Sexp::s2("assert", Sexp::s3("=", &*goal_name, g)));
+            goal_names.push((name, goal_name));
+        }
+        match &*goal_names {
             [] => {}
-            [g] => self.add_z3_line(Sexp::s2("assert", Sexp::s2("not", g.clone()))),
-            _ => todo!(),
-        }
-        self.add_z3_line(Sexp::s1("check-sat"));
-        self.add_z3_line(Sexp::s1("get-model"));
-        let mut f = std::fs::File::create("z3-query").unwrap();
-        f.write_all(self.z3_state.as_bytes()).unwrap();
-        let mut child = std::process::Command::new("bash")
-            .arg("-c")
-            .arg("z3 z3-query > z3-result")
-            .spawn()
-            .unwrap();
-        child.wait().unwrap();
-        let r = std::fs::read_to_string("z3-result").unwrap();
-        if !r.starts_with("unsat") {
-            if let Some(r) = r.strip_prefix("sat") {
-                let r = r.trim();
-                if let Some(r) = r.strip_prefix("(") {
-                    if let Some(r) = r.strip_suffix(")") {
-                        let mut f = std::fs::File::create("z3-model").unwrap();
-                        f.write_all(r.as_bytes()).unwrap();
-                        writeln!(f, r#"(echo "{sat_message}")"#).unwrap();
-                        for x in &self.intersting_consts {
-                            writeln!(f, r#"(echo "{x} is:") (simplify {x})"#).unwrap();
-                        }
-                        let mut child = std::process::Command::new("bash")
-                            .arg("-c")
-                            .arg("z3 z3-model > z3-model-simplified")
-                            .spawn()
-                            .unwrap();
-                        child.wait().unwrap();
-                        let r = std::fs::read_to_string("z3-model-simplified").unwrap();
-                        panic!("{r}");
-                    }
+            [(_, g)] => self.add_z3_line(Sexp::s2("assert", Sexp::s2("not", &**g))),
+            _ => {
+                let mut all = vec!["and".to_sexp()];
+                all.extend(goal_names.iter().map(|(_, g)| g.as_str().to_sexp()));
+                self.add_z3_line(Sexp::s2("assert", Sexp::s2("not", Sexp::List(all))));
+            }
+        }
+        let mut query = vec![Sexp::s1("check-sat")];
+        if !goal_names.is_empty() {
+            let values = goal_names.iter().map(|(_, g)| g.as_str().to_sexp());
+            query.push(Sexp::s2("get-value", Sexp::List(values.collect())));
+        }
+        query.push(Sexp::s1("get-model"));
+        let mut r = self.run_z3(&query);
+        let answers = Sexp::parse(&r).unwrap_or_else(|| panic!("{r}"));
+        match answers.first() {
+            Some(Sexp::Atom(x)) if x == "unsat" => return,
+            Some(Sexp::Atom(x)) if x == "sat" => {}
+            _ => panic!("{r}"),
+        }
+        if let Some(failed_checks) = &self.failed_checks {
+            failed_checks.set(failed_checks.get() + 1);
+            return;
+        }
+        let failed_in = |answers: &[Sexp]| -> Vec<String> {
+            let Some(Sexp::List(values)) = answers.get(1) else {
+                panic!("{answers:?}");
+            };
+            goal_names
+                .iter()
+                .filter(|(_, g)| values.contains(&Sexp::s2(g.as_str(), "false")))
+                .map(|(_, g)| g.clone())
+                .collect()
+        };
+        if goal_names.len() > 1 {
+            // The model found first may break fewer goals than can fail together, so each
+            // goal that held is tried again along with those that failed.
+            let mut failed = failed_in(&answers);
+            for (_, g) in &goal_names {
+                if failed.contains(g) {
+                    continue;
+                }
+                let mut more = failed.clone();
+                more.push(g.clone());
+                let mut attempt: Vec<_> = more
+                    .iter()
+                    .map(|x| Sexp::s2("assert", Sexp::s2("not", x.as_str())))
+                    .collect();
+                attempt.extend(query.iter().cloned());
+                let answer = self.run_z3(&attempt);
+                if answer.starts_with("sat") {
+                    r = answer;
+                    failed = more;
                 }
             }
+        }
+        let answers = Sexp::parse(&r).unwrap_or_else(|| panic!("{r}"));
+        let mut rest = &answers[1..];
+        let mut sat_message = sat_message.to_owned();
+        if !goal_names.is_empty() {
+            let failed = failed_in(&answers);
+            let names: Vec<_> = goal_names
+                .iter()
+                .filter(|(_, g)| failed.contains(g))
+                .map(|(name, _)| name.as_str())
+                .collect();
+            sat_message += &format!("\nFailed: {}", names.join(", "));
+            rest = &rest[1..];
+        }
+        let [Sexp::List(model)] = rest else {
             panic!("{r}");
+        };
+        // Older versions of z3 start the model with `model`.
+        let model = model.iter().filter(|x| **x != "model".to_sexp()).cloned();
+        let mut query = definition_order(model.collect());
+        query.push(Sexp::s2("echo", string_literal(&sat_message)));
+        for x in &self.intersting_consts {
+            query.push(Sexp::s2("echo", string_literal(&format!("{x} is:"))));
+            query.push(Sexp::s2("simplify", x.as_str()));
         }
+        // The model defines the constants of the path, so it needs a solver of its own.
+        let r = Solver::default().run(&[], &query);
+        panic!("{r}");
+    }
+
+    /// Whether `fact` holds in every state that satisfies the current assertions.
+    fn is_valid(&self, fact: Sexp) -> bool {
+        let negation = Sexp::s2("assert", Sexp::s2("not", fact));
+        self.run_z3(&[negation, Sexp::s1("check-sat")])
+            .starts_with("unsat")
     }
 
-    fn address_of_name(&self, name: &llvm_ir::Name) -> usize {
+    /// The condition for taking each way of `br`, true first, and where it leads to, or
+    /// `None` if that would exceed the unroll bound.
+    fn branch_outcomes(
+        &mut self,
+        side: Side,
+        br: &CondBr,
+        pos: &Position,
+        memory: MemorySnapshot,
+    ) -> [(Sexp, Option<(Position, MemorySnapshot)>); 2] {
+        let cond_false = Sexp::s3(
+            "=",
+            self.operand_to_sexp(side, &br.condition, memory),
+            "#x00",
+        );
+        let cond_true = Sexp::s2("not", cond_false.clone());
+        let if_true = self.jump(side, pos, &br.true_dest, memory);
+        let if_false = self.jump(side, pos, &br.false_dest, memory);
+        [(cond_true, if_true), (cond_false, if_false)]
+    }
+
+    /// Whether some state satisfies the current assertions, i.e. the path is feasible.
+    /// An `unknown` answer counts as feasible.
+    fn is_satisfiable(&self) -> bool {
+        !self.run_z3(&[Sexp::s1("check-sat")]).starts_with("unsat")
+    }
+
+    /// Answers of the solver to `query` in the state of this path.
+    fn run_z3(&self, query: &[Sexp]) -> String {
+        self.solver.borrow_mut().run(&self.z3_state, query)
+    }
+
+    fn address_of_name(&self, name: &llvm_ir::Name, frame: usize) -> usize {
         let mut la_map = self.local_addresses.borrow_mut();
-        if let Some(x) = la_map.get(name) {
+        let key = (frame, name.clone());
+        if let Some(x) = la_map.get(&key) {
             return *x;
         }
-        const ALLOCA_SIZE: usize = 1 << 32;
-        let new_addr = (la_map.len() + 5) * ALLOCA_SIZE;
-        la_map.insert(name.clone(), new_addr);
+        const BLOCK_SIZE: usize = 1 << 32;
+        let new_addr = (la_map.len() + FIRST_REGISTER_BLOCK) * BLOCK_SIZE;
+        la_map.insert(key, new_addr);
         new_addr
     }
 
+    fn new_frame(&mut self) -> usize {
+        self.frame_counter += 1;
+        self.frame_counter
+    }
+
     fn compare_calls(
         mut self,
         left_call: Call,
@@ -406,37 +1322,269 @@ impl VerifierState {
             self.check_sat(&format!("Mismatched function call.\nLeft called function with signature {left_ty:?}\nRight called function with signature {right_ty}"));
             return;
         }
+        let read = self.call_footprint(&left_call, left_memory, |x| x.read);
+        let left_effects = self.call_effects(&left_call);
+        let right_effects = self.call_effects(&right_call);
+        let left_function = left_call.function.right().unwrap();
+        let right_function = right_call.function.right().unwrap();
+        if left_call.arguments.len() != right_call.arguments.len() {
+            let left_len = left_call.arguments.len();
+            let right_len = right_call.arguments.len();
+            self.check_sat(&format!("Mismatched number of arguments.\nLeft called {left_function} with {left_len} arguments\nRight called {right_function} with {right_len} arguments"));
+            return;
+        }
+        // Both sides continue with what the attributes of the left call allow, so the
+        // right call must not promise more about the callee than that.
+        if !right_effects.no_stronger_than(&left_effects) {
+            self.check_sat(&format!("Stronger attributes of call in new.\nLeft called {left_function} with {left_effects:?}\nRight called {right_function} with {right_effects:?}"));
+            return;
+        }
+        let arguments: Vec<_> = left_call
+            .arguments
+            .iter()
+            .zip(&right_call.arguments)
+            .map(|((left_arg, left_attrs), (right_arg, right_attrs))| {
+                (
+                    left_arg,
+                    right_arg,
+                    abi_attributes(left_attrs),
+                    abi_attributes(right_attrs),
+                )
+            })
+            .collect();
+        for (i, (_, _, left_abi, right_abi)) in arguments.iter().enumerate() {
+            if left_abi != right_abi {
+                self.check_sat(&format!("Mismatched attributes of argument {i}.\nLeft passed it with {left_abi:?}\nRight passed it with {right_abi:?}"));
+                return;
+            }
+        }
+        self.add_memory_compare("memory_before_call", left_memory, right_memory, read);
+        for (i, (left_arg, right_arg, abi, _)) in arguments.into_iter().enumerate() {
+            let ty = self.type_of(left_arg);
+            let left_value = self.operand_to_sexp(Side::Left, left_arg, left_memory);
+            let right_value = self.operand_to_sexp(Side::Right, right_arg, right_memory);
+            let name = format!("argument_{i}");
+            if let Some(pointee) = abi.iter().find_map(|x| match x {
+                ParameterAttribute::ByVal(pointee) => Some(pointee),
+                _ => None,
+            }) {
+                // The callee gets its own copy, so only the pointed-to bytes matter.
+                let left_addr = self.pointer_to_addr(left_value, &ty);
+                let right_addr = self.pointer_to_addr(right_value, &ty);
+                let left_value = self.load_from_memory(left_addr, pointee, left_memory);
+                let right_value = self.load_from_memory(right_addr, pointee, right_memory);
+                let sort = self.sort_of_ty(pointee);
+                self.add_interesting_compare(&name, sort, left_value, right_value);
+                continue;
+            }
+            let left_value = self.without_zero_sign(&ty, left_value);
+            let right_value = self.without_zero_sign(&ty, right_value);
+            let left = (left_value, self.operand_poison(left_arg, left_memory));
+            let right = (right_value, self.operand_poison(right_arg, right_memory));
+            self.add_refinement_compare(&name, self.sort_of_ty(&ty), left, right);
+        }
         self.add_interesting_compare(
             "function",
-            bv_ty(64),
-            self.operand_to_sexp(&left_call.function.right().unwrap(), left_memory),
-            self.operand_to_sexp(&right_call.function.right().unwrap(), right_memory),
+            self.sort_of_ty(&self.type_of(&left_function)),
+            self.operand_to_sexp(Side::Left, &left_function, left_memory),
+            self.operand_to_sexp(Side::Right, &right_function, right_memory),
         );
-        self.check_sat("Mismatched function or arguments");
+        self.check_sat(&format!(
+            "Call to {left_function} differs from call to {right_function}"
+        ));
     }
 }
 
-fn pos_of_bb_name(name: &llvm_ir::Name, left: &Function) -> Position {
-    let bb = left
-        .basic_blocks
+/// Parameter attributes that change how an argument is passed, and so must agree.
+fn abi_attributes(attrs: &[ParameterAttribute]) -> Vec<ParameterAttribute> {
+    attrs
         .iter()
-        .position(|x| x.name == *name)
-        .unwrap();
-    Position { bb, instr: 0 }
+        .filter(|x| {
+            matches!(
+                x,
+                ParameterAttribute::ByVal(_)
+                    | ParameterAttribute::SRet(_)
+                    | ParameterAttribute::InAlloca(_)
+                    | ParameterAttribute::Preallocated(_)
+                    | ParameterAttribute::InReg
+                    | ParameterAttribute::ZeroExt
+                    | ParameterAttribute::SignExt
+                    | ParameterAttribute::Nest
+                    | ParameterAttribute::SwiftSelf
+                    | ParameterAttribute::SwiftError
+            )
+        })
+        .cloned()
+        .collect()
+}
+
+/// Orders the definitions of a model so that each comes after the ones it uses, which
+/// the solver does not do when it prints them.
+fn definition_order(definitions: Vec<Sexp>) -> Vec<Sexp> {
+    fn atoms<'a>(x: &'a Sexp, r: &mut HashSet<&'a str>) {
+        match x {
+            Sexp::Atom(x) => {
+                r.insert(x);
+            }
+            Sexp::List(x) => x.iter().for_each(|x| atoms(x, r)),
+        }
+    }
+    let names: Vec<_> = definitions
+        .iter()
+        .map(|x| match x {
+            Sexp::List(x) if x.len() > 1 => match &x[1] {
+                Sexp::Atom(name) => Some(name.as_str()),
+                Sexp::List(_) => None,
+            },
+            _ => None,
+        })
+        .collect();
+    let defined: HashSet<_> = names.iter().flatten().copied().collect();
+    let uses: Vec<HashSet<_>> = definitions
+        .iter()
+        .zip(&names)
+        .map(|(x, name)| {
+            let mut r = HashSet::new();
+            atoms(x, &mut r);
+            r.retain(|x| defined.contains(x) && Some(*x) != *name);
+            r
+        })
+        .collect();
+    let mut done = HashSet::new();
+    let mut pending: Vec<usize> = (0..definitions.len()).collect();
+    let mut order = vec![];
+    while !pending.is_empty() {
+        let ready = pending
+            .iter()
+            .position(|&i| uses[i].iter().all(|x| done.contains(x)));
+        // Definitions that use each other are left in the order they came in.
+        let i = pending.remove(ready.unwrap_or(0));
+        done.extend(names[i]);
+        order.push(i);
+    }
+    let mut definitions: Vec<_> = definitions.into_iter().map(Some).collect();
+    order
+        .into_iter()
+        .map(|i| definitions[i].take().unwrap())
+        .collect()
+}
+
+/// Blocks without a name in the source are numbered.
+fn block_name(name: &str) -> Name {
+    match name.parse::<usize>() {
+        Ok(n) => Name::Number(n),
+        Err(_) => Name::from(name),
+    }
 }
 
 fn main() {
-    let m = Module::from_bc_path("./playground/playground.bc").unwrap();
+    let mut config = Config::default();
+    let mut from = None;
+    let mut path = "./playground/playground.bc".to_owned();
+    for arg in std::env::args().skip(1) {
+        match &*arg {
+            "--pure-calls-as-functions" => config.pure_calls_as_functions = true,
+            "--merge-paths" => config.merge_paths = true,
+            _ if arg.starts_with("--from=") => {
+                let (left, right) = arg["--from=".len()..].split_once(':').unwrap();
+                from = Some((block_name(left), block_name(right)));
+            }
+            _ if arg.starts_with("--diverging=") => {
+                let pattern = &arg["--diverging=".len()..];
+                config.diverging_functions.push(pattern.to_owned());
+            }
+            _ if arg.starts_with("--unroll=") => {
+                config.unroll_bound = arg["--unroll=".len()..].parse().unwrap();
+            }
+            _ if arg.starts_with("--inline-depth=") => {
+                config.inline_depth = arg["--inline-depth=".len()..].parse().unwrap();
+            }
+            // The fast-math flags that instructions may have. `reassoc` and the other flags
+            // whose results are not modeled are refused.
+            _ if arg.starts_with("--fast-math=") => {
+                config.fast_math = float::FastMath::parse(&arg["--fast-math=".len()..]);
+            }
+            _ if !arg.starts_with("--") => path = arg,
+            _ => panic!("unknown argument {arg}"),
+        }
+    }
+    // The module holds the functions `left` and `right`, as bitcode or as text.
+    let m = match path.ends_with(".ll") {
+        true => Module::from_ir_path(&path),
+        false => Module::from_bc_path(&path),
+    };
+    let m = Rc::new(m.unwrap());
     let mut left = None;
     let mut right = None;
-    for function in m.functions {
+    for function in &m.functions {
         if function.name == "left" {
             left = Some(function.clone());
         }
         if function.name == "right" {
-            right = Some(function);
+            right = Some(function.clone());
+        }
+    }
+    config.instruction_fast_math = float::instruction_fast_math(&path, config.fast_math);
+    let verifier = VerifierState::new(m, config, left.unwrap(), right.unwrap());
+    let (verdict, stats) = match from {
+        // Both sides start with the same memory and registers.
+        Some((left_bb, right_bb)) => {
+            verifier.compare_from(&left_bb, &right_bb, |_, l, r| Sexp::s3("=", l, r))
         }
+        None => verifier.compare_functions(),
+    };
+    println!("{verdict}");
+    println!("{stats}");
+}
+
+#[cfg(test)]
+mod tests {
+    use llvm_ir::{
+        module::{DataLayout, Endianness},
+        types::Types,
+    };
+
+    use super::*;
+
+    /// State of an empty module with the default data layout, apart from the byte order.
+    pub fn state(endianness: Endianness) -> VerifierState {
+        let module = Module {
+            name: "test".to_owned(),
+            source_file_name: "test.ll".to_owned(),
+            data_layout: DataLayout {
+                endianness,
+                ..DataLayout::default()
+            },
+            target_triple: None,
+            functions: vec![],
+            func_declarations: vec![],
+            global_vars: vec![],
+            global_aliases: vec![],
+            global_ifuncs: vec![],
+            inline_assembly: String::new(),
+            types: Types::blank_for_testing(),
+        };
+        VerifierState::new(
+            Rc::new(module),
+            Config::default(),
+            Function::new("left"),
+            Function::new("right"),
+        )
+    }
+
+    #[test]
+    fn definitions_come_after_the_ones_they_use() {
+        let model = Sexp::parse(
+            "(define-fun b () Int (+ a 1)) (define-fun c () Int (+ b a)) (define-fun a () Int 2)",
+        )
+        .unwrap();
+        let names: Vec<_> = definition_order(model)
+            .into_iter()
+            .map(|x| match x {
+                Sexp::List(x) => x[1].clone(),
+                Sexp::Atom(_) => unreachable!(),
+            })
+            .collect();
+        assert_eq!(names, ["a", "b", "c"].map(|x| x.to_sexp()));
     }
-    let verifier = VerifierState::new(left.unwrap(), right.unwrap());
-    dbg!(verifier.compare_functions());
 }
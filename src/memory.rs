@@ -0,0 +1,681 @@
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use llvm_ir::{
+    function::ParameterAttribute, instruction::Call, Constant, Function, Instruction, Name,
+    Operand, Type, TypeRef,
+};
+
+use crate::{
+    attributes::Access,
+    sexp::{Sexp, ToSexp},
+    z3_decl::{
+        bv_hex, bv_ty, declare_const, define_const, extract, if_then_else, memory_ty, resize,
+        BLOCK_BITS,
+    },
+    MemorySnapshot, Side, VerifierState,
+};
+
+/// The registers that printed IR mentions, like `%x`.
+fn registers_in(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric() && !"%$._-".contains(c))
+        .filter(|x| x.starts_with('%'))
+        .map(str::to_owned)
+        .collect()
+}
+
+// A pointer is a pair of a block id and an offset inside that block, encoded as
+// `(concat block offset)`. Memory is indexed by `(concat block offset)` with the
+// offset truncated to 32 bits, and every byte in memory carries the block id of
+// the pointer it was a part of, so provenance survives a round trip through memory.
+// Bytes of poison carry `POISON_BLOCK` instead.
+pub const NULL_BLOCK: usize = 0;
+pub const FIRST_GLOBAL_BLOCK: usize = 1;
+pub const FIRST_HEAP_BLOCK: usize = 1 << 20;
+pub const FIRST_ALLOCA_BLOCK: usize = 1 << 24;
+pub const FIRST_REGISTER_BLOCK: usize = 1 << 28;
+pub const POISON_BLOCK: usize = (1 << BLOCK_BITS) - 1;
+const ALLOCAS_PER_FRAME: usize = 1 << 12;
+
+impl VerifierState {
+    pub fn type_of(&self, t: &impl llvm_ir::types::Typed) -> TypeRef {
+        self.module.type_of(t)
+    }
+
+    pub fn pointer_bits(&self, ty: &TypeRef) -> usize {
+        match &**ty {
+            Type::PointerType { addr_space } => self.pointer_size(*addr_space) * 8,
+            _ => panic!("{ty} is not a pointer"),
+        }
+    }
+
+    pub fn value_bits(&self, ty: &TypeRef) -> usize {
+        match &**ty {
+            Type::PointerType { .. } => BLOCK_BITS + self.pointer_bits(ty),
+            _ => match self.aggregate_fields(ty) {
+                Some(fields) => fields.iter().map(|(x, _)| self.value_bits(x)).sum(),
+                None => self.size_of_ty(ty) * 8,
+            },
+        }
+    }
+
+    /// The value with the bits that integers narrower than their bytes do not use
+    /// cleared. Memory that they were not stored to may have those bits set.
+    pub fn without_unused_bits(&self, ty: &TypeRef, value: Sexp) -> Sexp {
+        if !self.has_unused_bits(ty) {
+            return value;
+        }
+        if let Some(fields) = self.aggregate_fields(ty) {
+            let fields = fields
+                .iter()
+                .enumerate()
+                .map(|(i, (field_ty, _))| {
+                    let field = self.extract_field(value.clone(), ty, i);
+                    self.without_unused_bits(field_ty, field)
+                })
+                .collect();
+            return self.aggregate_value(fields);
+        }
+        let bits = self.value_bits(ty);
+        let Type::IntegerType { bits: used } = &**ty else {
+            unreachable!("{ty} has no unused bits");
+        };
+        let used = *used as usize;
+        resize(extract(value, used - 1, 0), used, bits, false)
+    }
+
+    /// Declared width of an integer type, which its values may be narrower than.
+    pub fn int_bits(&self, ty: &TypeRef) -> usize {
+        match &**ty {
+            Type::IntegerType { bits } => *bits as usize,
+            _ => panic!("{ty} is not an integer"),
+        }
+    }
+
+    /// The integer truncated to its declared width, for operations whose result depends
+    /// on that width.
+    pub fn narrowed(&self, ty: &TypeRef, value: Sexp) -> Sexp {
+        resize(value, self.value_bits(ty), self.int_bits(ty), false)
+    }
+
+    /// The integer back at the width of its values, with the unused bits cleared.
+    pub fn widened(&self, ty: &TypeRef, value: Sexp) -> Sexp {
+        resize(value, self.int_bits(ty), self.value_bits(ty), false)
+    }
+
+    fn has_unused_bits(&self, ty: &TypeRef) -> bool {
+        match self.aggregate_fields(ty) {
+            Some(fields) => fields.iter().any(|(x, _)| self.has_unused_bits(x)),
+            None => matches!(&**ty, Type::IntegerType { bits } if bits % 8 != 0),
+        }
+    }
+
+    pub fn sort_of_ty(&self, ty: &TypeRef) -> Sexp {
+        bv_ty(self.value_bits(ty))
+    }
+
+    pub fn block_pointer(&self, block: usize, ty: &TypeRef) -> Sexp {
+        Sexp::s3(
+            "concat",
+            bv_hex(block, BLOCK_BITS / 8),
+            bv_hex(0, self.pointer_bits(ty) / 8),
+        )
+    }
+
+    pub fn null_pointer(&self, ty: &TypeRef) -> Sexp {
+        self.block_pointer(NULL_BLOCK, ty)
+    }
+
+    pub fn pointer_block(&self, ptr: impl ToSexp, ty: &TypeRef) -> Sexp {
+        let bits = self.pointer_bits(ty);
+        extract(ptr, bits + BLOCK_BITS - 1, bits)
+    }
+
+    pub fn pointer_offset(&self, ptr: impl ToSexp, ty: &TypeRef) -> Sexp {
+        extract(ptr, self.pointer_bits(ty) - 1, 0)
+    }
+
+    /// The index into the memory array that the pointer refers to.
+    pub fn pointer_to_addr(&self, ptr: Sexp, ty: &TypeRef) -> Sexp {
+        let offset = self.pointer_offset(ptr.clone(), ty);
+        Sexp::s3(
+            "concat",
+            self.pointer_block(ptr, ty),
+            resize(offset, self.pointer_bits(ty), 32, false),
+        )
+    }
+
+    /// Integer value of a pointer. Blocks are placed at unknown addresses, so nothing
+    /// can be derived from the integer value of two pointers to different blocks.
+    pub fn ptr_to_int(&self, ptr: Sexp, ty: &TypeRef, int_bits: usize) -> Sexp {
+        let bits = self.pointer_bits(ty);
+        let base = Sexp::s2("block_address", self.pointer_block(ptr.clone(), ty));
+        let address = Sexp::s3(
+            "bvadd",
+            resize(base, 64, bits, false),
+            self.pointer_offset(ptr, ty),
+        );
+        resize(address, bits, int_bits, false)
+    }
+
+    /// Pointers made up from integers have no provenance and can not be used to access
+    /// any allocated block.
+    pub fn int_to_ptr(&self, int: Sexp, int_bits: usize, ty: &TypeRef) -> Sexp {
+        let bits = self.pointer_bits(ty);
+        Sexp::s3(
+            "concat",
+            bv_hex(NULL_BLOCK, BLOCK_BITS / 8),
+            resize(int, int_bits, bits, false),
+        )
+    }
+
+    pub fn cast_pointer(&self, ptr: Sexp, from: &TypeRef, to: &TypeRef) -> Sexp {
+        Sexp::s3(
+            "concat",
+            self.pointer_block(ptr.clone(), from),
+            resize(
+                self.pointer_offset(ptr, from),
+                self.pointer_bits(from),
+                self.pointer_bits(to),
+                true,
+            ),
+        )
+    }
+
+    pub fn global_block(&self, name: &Name) -> usize {
+        let module = &self.module;
+        let index = module
+            .global_vars
+            .iter()
+            .map(|x| x.name.clone())
+            .chain(module.functions.iter().map(|x| Name::from(&*x.name)))
+            .chain(
+                module
+                    .func_declarations
+                    .iter()
+                    .map(|x| Name::from(&*x.name)),
+            )
+            .position(|x| x == *name)
+            .unwrap_or_else(|| panic!("unknown global {name}"));
+        FIRST_GLOBAL_BLOCK + index
+    }
+
+    /// Block of the alloca `dest` of `f` in `frame`. The allocas that are only ever
+    /// stored to, which nothing can observe, are numbered after the others, so that
+    /// removing them keeps the blocks of the others.
+    pub fn alloca_block(&self, f: &Function, dest: &Name, frame: usize) -> usize {
+        let index = self.alloca_indices(f)[dest];
+        FIRST_ALLOCA_BLOCK + frame * ALLOCAS_PER_FRAME + index
+    }
+
+    fn alloca_indices(&self, f: &Function) -> Rc<HashMap<Name, usize>> {
+        if let Some(r) = self.alloca_indices.borrow().get(&f.name) {
+            return r.clone();
+        }
+        let instrs = || f.basic_blocks.iter().flat_map(|bb| &bb.instrs);
+        let mut read = HashSet::new();
+        for instr in instrs() {
+            let text = match instr {
+                Instruction::Alloca(_) => continue,
+                Instruction::Store(store) => store.value.to_string(),
+                Instruction::Call(call) if self.is_ignored_intrinsic(call) => continue,
+                _ => instr.to_string(),
+            };
+            read.extend(registers_in(&text));
+        }
+        for bb in &f.basic_blocks {
+            read.extend(registers_in(&bb.term.to_string()));
+        }
+        let (mut allocas, stored_to): (Vec<_>, Vec<_>) = instrs()
+            .filter_map(|instr| match instr {
+                Instruction::Alloca(alloca) => Some(alloca.dest.clone()),
+                _ => None,
+            })
+            .partition(|x| read.contains(&x.to_string()));
+        allocas.extend(stored_to);
+        let r: Rc<HashMap<_, _>> = Rc::new(allocas.into_iter().zip(0..).collect());
+        self.alloca_indices
+            .borrow_mut()
+            .insert(f.name.clone(), r.clone());
+        r
+    }
+
+    /// Condition for an access of `len` bytes, a 32 bit length, through `ptr` to stay
+    /// inside a live block. The null block and made-up pointers have no bytes, and no
+    /// block has more than 2^32. Globals with an initializer and allocas with a constant
+    /// number of elements have a known size. `frames` are the functions being run, each
+    /// with its frame, and only their allocas are alive.
+    pub fn access_in_bounds(
+        &self,
+        ptr: Sexp,
+        ty: &TypeRef,
+        len: Sexp,
+        frames: &[(&Function, usize)],
+    ) -> Sexp {
+        let block = self.pointer_block(ptr.clone(), ty);
+        let bits = self.pointer_bits(ty);
+        // Wide enough for the end of any access, which may be 2^32 or beyond.
+        let wide = bits.max(32) + 1;
+        let end = Sexp::s3(
+            "bvadd",
+            resize(self.pointer_offset(ptr, ty), bits, wide, false),
+            resize(len, 32, wide, false),
+        );
+        let fits = |size: usize| {
+            let size = Sexp::s3("_", &*format!("bv{size}"), &*wide.to_string());
+            Sexp::s3("bvule", end.clone(), size)
+        };
+        let block_is = |x: usize| Sexp::s3("=", block.clone(), bv_hex(x, BLOCK_BITS / 8));
+        let mut r = vec![
+            "and".to_sexp(),
+            Sexp::s2("not", block_is(NULL_BLOCK)),
+            fits(1 << bits.min(32)),
+        ];
+        for (i, global) in self.module.global_vars.iter().enumerate() {
+            if let Some(init) = &global.initializer {
+                let global_size = self.size_of_ty(&self.type_of(init));
+                let block = block_is(FIRST_GLOBAL_BLOCK + i);
+                r.push(Sexp::s3("=>", block, fits(global_size)));
+            }
+        }
+        let mut allocas = vec!["or".to_sexp()];
+        for (f, frame) in frames {
+            let instrs = f.basic_blocks.iter().flat_map(|bb| &bb.instrs);
+            for instr in instrs {
+                let Instruction::Alloca(alloca) = instr else {
+                    continue;
+                };
+                let block = block_is(self.alloca_block(f, &alloca.dest, *frame));
+                let count = match &alloca.num_elements {
+                    Operand::ConstantOperand(c) => match &**c {
+                        Constant::Int { value, .. } => Some(*value as usize),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                allocas.push(match count {
+                    Some(n) => {
+                        let alloca_size = self.size_of_ty(&alloca.allocated_type) * n;
+                        Sexp::s3("and", block, fits(alloca_size))
+                    }
+                    None => block,
+                });
+            }
+        }
+        let is_alloca = Sexp::s3(
+            "and",
+            Sexp::s3(
+                "bvuge",
+                block.clone(),
+                bv_hex(FIRST_ALLOCA_BLOCK, BLOCK_BITS / 8),
+            ),
+            Sexp::s3(
+                "bvult",
+                block.clone(),
+                bv_hex(FIRST_REGISTER_BLOCK, BLOCK_BITS / 8),
+            ),
+        );
+        let allocas = match allocas.len() {
+            1 => "false".to_sexp(),
+            _ => Sexp::List(allocas),
+        };
+        r.push(Sexp::s3("=>", is_alloca, allocas));
+        Sexp::List(r)
+    }
+}
+
+/// A set of memory locations, made of every block that outlives the current function
+/// if `other_memory` is set, and the listed blocks.
+pub struct Footprint {
+    pub other_memory: bool,
+    pub blocks: Vec<Sexp>,
+}
+
+impl Footprint {
+    /// Memory that is observable after the function returns. Allocas are dead by then.
+    pub fn outliving() -> Self {
+        Footprint {
+            other_memory: true,
+            blocks: vec![],
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.other_memory && self.blocks.is_empty()
+    }
+
+    pub fn contains(&self, addr: impl ToSexp) -> Sexp {
+        let block = extract(addr, 63, 32);
+        let mut r = vec!["or".to_sexp()];
+        if self.other_memory {
+            r.push(Sexp::s3(
+                "bvult",
+                block.clone(),
+                bv_hex(FIRST_ALLOCA_BLOCK, BLOCK_BITS / 8),
+            ));
+        }
+        for b in &self.blocks {
+            r.push(Sexp::s3("=", block.clone(), b.clone()));
+        }
+        match r.len() {
+            1 => "false".to_sexp(),
+            _ => Sexp::List(r),
+        }
+    }
+}
+
+impl VerifierState {
+    /// Blocks of the pointer arguments of a call of the old function. The callee only
+    /// gets a copy of what a `byval` argument points to, so those are left out.
+    fn argument_blocks(&self, call: &Call, memory: MemorySnapshot) -> Vec<Option<Sexp>> {
+        call.arguments
+            .iter()
+            .map(|(arg, attrs)| {
+                let ty = self.type_of(arg);
+                let Type::PointerType { .. } = &*ty else {
+                    return None;
+                };
+                if attrs
+                    .iter()
+                    .any(|x| matches!(x, ParameterAttribute::ByVal(_)))
+                {
+                    return None;
+                }
+                Some(self.pointer_block(self.operand_to_sexp(Side::Left, arg, memory), &ty))
+            })
+            .collect()
+    }
+
+    /// Memory that a call may access with the given kind of access. Allocas are only
+    /// accessible if they are passed to the call or were captured earlier.
+    pub fn call_footprint(
+        &self,
+        call: &Call,
+        memory: MemorySnapshot,
+        access: impl Fn(&Access) -> bool,
+    ) -> Footprint {
+        let effects = self.call_effects(call);
+        let mut blocks = vec![];
+        if access(&effects.argmem) {
+            blocks.extend(self.argument_blocks(call, memory).into_iter().flatten());
+        }
+        if access(&effects.other) {
+            blocks.extend(self.captured());
+        }
+        Footprint {
+            other_memory: access(&effects.other),
+            blocks,
+        }
+    }
+
+    /// Continues both sides after a matched call. The callee is unknown, so both sides
+    /// observe the same fresh return value and the same arbitrary changes to the memory
+    /// that the callee can write.
+    pub fn return_from_call(
+        &mut self,
+        left_call: &Call,
+        right_call: &Call,
+        left_memory: MemorySnapshot,
+        right_memory: MemorySnapshot,
+    ) -> (MemorySnapshot, MemorySnapshot) {
+        let effects = self.call_effects(left_call);
+        let written = self.call_footprint(left_call, left_memory, |x| x.write);
+        let mut reachable = self.call_footprint(left_call, left_memory, |_| true);
+        let havoc = self.new_memory();
+        let (mut left_memory, mut right_memory) =
+            if written.blocks.is_empty() && !written.other_memory {
+                (left_memory, right_memory)
+            } else {
+                self.add_z3_line(declare_const(havoc, memory_ty()));
+                (
+                    self.havoc_memory(left_memory, havoc, &written),
+                    self.havoc_memory(right_memory, havoc, &written),
+                )
+            };
+        let arguments = self.argument_blocks(left_call, left_memory);
+        let mut captured = vec![];
+        for (block, no_capture) in arguments.into_iter().zip(&effects.no_capture) {
+            if let (Some(block), false) = (block, no_capture) {
+                reachable.blocks.push(block.clone());
+                captured.push(block);
+            }
+        }
+        self.capture(captured);
+        if let (Some(left_dest), Some(right_dest)) = (&left_call.dest, &right_call.dest) {
+            let Type::FuncType { result_type, .. } = &*left_call.function_ty else {
+                panic!("call of non function type {}", left_call.function_ty);
+            };
+            let result = format!("{havoc}_call_result");
+            self.add_z3_line(declare_const(&*result, self.sort_of_ty(result_type)));
+            if let Type::PointerType { .. } = &**result_type {
+                let block = self.pointer_block(&*result, result_type);
+                let null = Sexp::s3("=", block.clone(), bv_hex(NULL_BLOCK, BLOCK_BITS / 8));
+                let assumption = if effects.noalias_return {
+                    Sexp::s3(
+                        "=",
+                        block,
+                        bv_hex(FIRST_HEAP_BLOCK + havoc.index, BLOCK_BITS / 8),
+                    )
+                } else {
+                    reachable.contains(Sexp::s3("concat", block, bv_hex(0, 4)))
+                };
+                // Only `nonnull` and `dereferenceable` rule out null, as of a failed allocation.
+                // Pointers without provenance are reachable too, so the address must be
+                // ruled out as well.
+                let assumption = match effects.nonnull_return {
+                    true => {
+                        let bits = self.pointer_bits(result_type);
+                        let address = self.ptr_to_int(result.as_str().to_sexp(), result_type, bits);
+                        let not_null = Sexp::s3("distinct", address, bv_hex(0, bits / 8));
+                        Sexp::s3("and", not_null, assumption)
+                    }
+                    false => Sexp::s3("or", null, assumption),
+                };
+                self.add_z3_line(Sexp::s2("assert", assumption));
+            }
+            left_memory = self.store_register(
+                left_dest,
+                result_type,
+                result.as_str().to_sexp(),
+                left_memory,
+            );
+            right_memory = self.store_register(
+                right_dest,
+                result_type,
+                result.as_str().to_sexp(),
+                right_memory,
+            );
+        }
+        (left_memory, right_memory)
+    }
+
+    /// Records that the pointers in `value` of type `ty` were stored at the memory array
+    /// index `addr`. They are captured once that memory is reachable from outside the
+    /// function, which it may already be.
+    pub fn record_pointer_store(&mut self, addr: &Sexp, ty: &TypeRef, value: Sexp) {
+        let dest = extract(addr.clone(), 63, 32);
+        let stores: Vec<_> = self
+            .pointer_blocks(ty, value)
+            .into_iter()
+            .map(|x| (dest.clone(), x))
+            .collect();
+        self.add_pointer_stores(stores);
+    }
+
+    /// Records that the pointers stored in the block of the memory array index `source`
+    /// may have been copied to the block of `dest`.
+    pub fn record_pointer_copy(&mut self, dest: &Sexp, source: &Sexp) {
+        let (dest, source) = (
+            extract(dest.clone(), 63, 32),
+            extract(source.clone(), 63, 32),
+        );
+        let copied: Vec<_> = self
+            .pointer_stores
+            .iter()
+            .map(|(block, stored, _)| {
+                let null = bv_hex(NULL_BLOCK, BLOCK_BITS / 8);
+                let copied = Sexp::s3("=", block.clone(), source.clone());
+                (dest.clone(), if_then_else(copied, stored.clone(), null))
+            })
+            .collect();
+        self.add_pointer_stores(copied);
+    }
+
+    fn add_pointer_stores(&mut self, stores: Vec<(Sexp, Sexp)>) {
+        let mut added = false;
+        for (dest, stored) in stores {
+            let known = self
+                .pointer_stores
+                .iter()
+                .any(|(x, y, _)| *x == dest && *y == stored);
+            if !known {
+                let null = bv_hex(NULL_BLOCK, BLOCK_BITS / 8);
+                self.pointer_stores.push((dest, stored, null));
+                added = true;
+            }
+        }
+        if added {
+            self.capture_stored_pointers();
+        }
+    }
+
+    /// Makes the blocks accessible to code outside the function, along with the pointers
+    /// stored in them.
+    pub fn capture(&mut self, blocks: Vec<Sexp>) {
+        let mut added = false;
+        for block in blocks {
+            if !self.captured_blocks.contains(&block) {
+                self.captured_blocks.push(block);
+                added = true;
+            }
+        }
+        if added {
+            self.capture_stored_pointers();
+        }
+    }
+
+    /// Blocks that code outside the function may access, apart from the memory that
+    /// outlives it.
+    pub fn captured(&self) -> Vec<Sexp> {
+        let null = bv_hex(NULL_BLOCK, BLOCK_BITS / 8);
+        let mut r = self.captured_blocks.clone();
+        for (_, _, captured) in &self.pointer_stores {
+            if *captured != null && !r.contains(captured) {
+                r.push(captured.clone());
+            }
+        }
+        r
+    }
+
+    /// Blocks of the pointers that make up a value of type `ty`.
+    pub fn pointer_blocks(&self, ty: &TypeRef, value: Sexp) -> Vec<Sexp> {
+        if let Some(fields) = self.aggregate_fields(ty) {
+            return fields
+                .iter()
+                .enumerate()
+                .flat_map(|(i, (field_ty, _))| {
+                    self.pointer_blocks(field_ty, self.extract_field(value.clone(), ty, i))
+                })
+                .collect();
+        }
+        match &**ty {
+            Type::PointerType { .. } => vec![self.pointer_block(value, ty)],
+            _ => vec![],
+        }
+    }
+
+    /// Brings the blocks captured by the pointer stores up to date. Each round captures
+    /// the pointers stored in blocks that were captured by the previous one, so it
+    /// follows one more link of a chain of stores. The captures only grow, and the
+    /// rounds stop once one of them captures nothing more in any state. A chain is at
+    /// most as long as the number of stores, which bounds the rounds.
+    fn capture_stored_pointers(&mut self) {
+        for _ in 0..self.pointer_stores.len() {
+            let escaped = Footprint {
+                other_memory: true,
+                blocks: self.captured(),
+            };
+            let mut stores = std::mem::take(&mut self.pointer_stores);
+            let mut unchanged = vec!["and".to_sexp()];
+            for (dest, stored, captured) in &mut stores {
+                let reachable = escaped.contains(Sexp::s3("concat", dest.clone(), bv_hex(0, 4)));
+                let name = format!("{}_captured", self.new_memory());
+                let block = if_then_else(
+                    reachable,
+                    stored.clone(),
+                    bv_hex(NULL_BLOCK, BLOCK_BITS / 8),
+                );
+                self.add_z3_line(define_const(&*name, bv_ty(BLOCK_BITS), block));
+                unchanged.push(Sexp::s3("=", &*name, captured.clone()));
+                *captured = name.to_sexp();
+            }
+            self.pointer_stores = stores;
+            if self.is_valid(Sexp::List(unchanged)) {
+                break;
+            }
+        }
+    }
+
+    /// Makes a fresh exception the one in flight, as thrown by the callee.
+    pub fn start_unwinding(&mut self, landing_pad_type: Option<TypeRef>) {
+        let name = format!("{}_exception", self.new_memory());
+        // Without a landing pad on either side the exception is never looked into.
+        let sort = match landing_pad_type {
+            Some(ty) => self.sort_of_ty(&ty),
+            None => bv_ty(64),
+        };
+        self.add_z3_line(declare_const(&*name, sort.clone()));
+        self.exception = Some((name.to_sexp(), sort));
+    }
+
+    /// Memory where each of the `len` bytes starting at the memory array index `start`
+    /// is replaced by `value` of its offset from `start`. The bytes are at consecutive
+    /// indices, like the ones `add_offset` gives for a store. `len` has 32 bits and the
+    /// offsets 64.
+    pub fn store_range(
+        &mut self,
+        memory: MemorySnapshot,
+        start: Sexp,
+        len: Sexp,
+        value: impl FnOnce(Sexp) -> Sexp,
+    ) -> MemorySnapshot {
+        let nm = MemorySnapshot {
+            frame: memory.frame,
+            ..self.new_memory()
+        };
+        let offset = Sexp::s3("bvsub", "addr", start);
+        let inside = Sexp::s3("bvult", offset.clone(), resize(len, 32, 64, false));
+        let value = if_then_else(inside, value(offset), Sexp::s3("select", memory, "addr"));
+        self.add_z3_line(define_const(
+            nm,
+            memory_ty(),
+            Sexp::s3("lambda", Sexp::s1(Sexp::s2("addr", bv_ty(64))), value),
+        ));
+        nm
+    }
+
+    pub fn havoc_memory(
+        &mut self,
+        memory: MemorySnapshot,
+        havoc: MemorySnapshot,
+        footprint: &Footprint,
+    ) -> MemorySnapshot {
+        let nm = MemorySnapshot {
+            frame: memory.frame,
+            ..self.new_memory()
+        };
+        let value = if_then_else(
+            footprint.contains("addr"),
+            Sexp::s3("select", havoc, "addr"),
+            Sexp::s3("select", memory, "addr"),
+        );
+        self.add_z3_line(define_const(
+            nm,
+            memory_ty(),
+            Sexp::s3("lambda", Sexp::s1(Sexp::s2("addr", bv_ty(64))), value),
+        ));
+        nm
+    }
+}
@@ -0,0 +1,178 @@
+use std::{collections::VecDeque, rc::Rc};
+
+use crate::{
+    interpret::Position,
+    sexp::{Sexp, ToSexp},
+    z3_decl::{define_const, if_then_else, memory_ty},
+    Path, Stats,
+};
+
+/// Paths that went separate ways for longer than this many solver entries are not
+/// merged, since the `ite` terms of the merged state would cost more than exploring
+/// them one by one.
+const MERGE_LIMIT: usize = 256;
+
+/// Adds a path to be explored, merging it into a queued path at the same positions if
+/// that is enabled and looks profitable.
+pub fn enqueue(queue: &mut VecDeque<Path>, stats: &mut Stats, path: Path) {
+    if path.0.config.merge_paths {
+        if let Some(other) = queue.iter_mut().find(|x| can_merge(x, &path)) {
+            let common = common_entries(&other.0.z3_state, &path.0.z3_state);
+            let diverged = other.0.z3_state.len() + path.0.z3_state.len() - 2 * common;
+            if diverged <= MERGE_LIMIT {
+                *other = merge(other.clone(), path, common);
+                stats.merged_paths += 1;
+                return;
+            }
+        }
+    }
+    queue.push_back(path);
+}
+
+fn same_position(a: &Position, b: &Position) -> bool {
+    a.bb == b.bb
+        && a.instr == b.instr
+        && a.loop_header == b.loop_header
+        && a.unwinding == b.unwinding
+        && a.stack.is_empty()
+        && b.stack.is_empty()
+}
+
+fn can_merge(a: &Path, b: &Path) -> bool {
+    let (a_state, a_left_memory, a_right_memory, a_left_pos, a_right_pos) = a;
+    let (b_state, b_left_memory, b_right_memory, b_left_pos, b_right_pos) = b;
+    same_position(a_left_pos, b_left_pos)
+        && same_position(a_right_pos, b_right_pos)
+        && a_left_memory.frame == b_left_memory.frame
+        && a_right_memory.frame == b_right_memory.frame
+        && a_state.captured_blocks == b_state.captured_blocks
+        && a_state.pointer_stores == b_state.pointer_stores
+        && a_state.proven_loops == b_state.proven_loops
+        && a_state.exception == b_state.exception
+}
+
+/// Number of solver commands two paths have in common, from before they went separate
+/// ways.
+fn common_entries(a: &[Rc<Sexp>], b: &[Rc<Sexp>]) -> usize {
+    a.iter()
+        .zip(b)
+        .take_while(|(x, y)| Rc::ptr_eq(x, y) || x == y)
+        .count()
+}
+
+/// Separates the assertions made by a path from its definitions, returning the
+/// definitions and the conjunction of the assertions.
+fn guard_of(entries: &[Rc<Sexp>]) -> (Vec<Rc<Sexp>>, Sexp) {
+    let mut definitions = vec![];
+    let mut r = vec!["and".to_sexp()];
+    for entry in entries {
+        match &**entry {
+            Sexp::List(x) if x.len() == 2 && x[0] == "assert".to_sexp() => r.push(x[1].clone()),
+            _ => definitions.push(entry.clone()),
+        }
+    }
+    let guard = match r.len() {
+        1 => "true".to_sexp(),
+        _ => Sexp::List(r),
+    };
+    (definitions, guard)
+}
+
+/// Joins two paths at the same positions into one path that is in the state of the
+/// first path if its assertions hold, and in the state of the second otherwise.
+fn merge(first: Path, second: Path, common: usize) -> Path {
+    let (mut this, first_left, first_right, mut left_pos, mut right_pos) = first;
+    let (other, second_left, second_right, second_left_pos, second_right_pos) = second;
+    let (first_definitions, first_guard) = guard_of(&this.z3_state[common..]);
+    let (second_definitions, second_guard) = guard_of(&other.z3_state[common..]);
+    this.z3_state.truncate(common);
+    this.z3_state.extend(first_definitions.iter().cloned());
+    for entry in second_definitions {
+        // Both paths may have declared the same uninterpreted function.
+        if !first_definitions.contains(&entry) {
+            this.z3_state.push(entry);
+        }
+    }
+    let left_memory = this.new_memory();
+    let first_name = format!("{left_memory}_first_path");
+    let second_name = format!("{left_memory}_second_path");
+    this.add_z3_line(define_const(&*first_name, "Bool", first_guard));
+    this.add_z3_line(define_const(&*second_name, "Bool", second_guard));
+    this.add_z3_line(Sexp::s2(
+        "assert",
+        Sexp::s3("or", &*first_name, &*second_name),
+    ));
+    let mut memories = vec![];
+    for (first_memory, second_memory, mut merged) in [
+        (first_left, second_left, left_memory),
+        (first_right, second_right, this.new_memory()),
+    ] {
+        merged.frame = first_memory.frame;
+        let value = if_then_else(&*first_name, first_memory, second_memory);
+        this.add_z3_line(define_const(merged, memory_ty(), value));
+        memories.push(merged);
+    }
+    for (pos, other_pos) in [
+        (&mut left_pos, second_left_pos),
+        (&mut right_pos, second_right_pos),
+    ] {
+        for (key, count) in other_pos.iterations {
+            let entry = pos.iterations.entry(key).or_default();
+            *entry = (*entry).max(count);
+        }
+    }
+    this.frame_counter = this.frame_counter.max(other.frame_counter);
+    this.taken_words
+        .set(this.taken_words.get().max(other.taken_words.get()));
+    this.declared_functions.extend(other.declared_functions);
+    this.unsynchronized_loops.extend(other.unsynchronized_loops);
+    for name in other.intersting_consts {
+        if !this.intersting_consts.contains(&name) {
+            this.intersting_consts.push(name);
+        }
+    }
+    (this, memories[0], memories[1], left_pos, right_pos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(x: &[Sexp]) -> Vec<Rc<Sexp>> {
+        x.iter().cloned().map(Rc::new).collect()
+    }
+
+    #[test]
+    fn common_entries_stop_at_the_first_difference() {
+        let shared = Rc::new(Sexp::s2("assert", "a"));
+        let a = [
+            vec![shared.clone()],
+            entries(&[Sexp::s2("assert", "b"), "c".to_sexp()]),
+        ];
+        let b = [
+            vec![shared],
+            entries(&[Sexp::s2("assert", "b"), "d".to_sexp()]),
+        ];
+        assert_eq!(common_entries(&a.concat(), &b.concat()), 2);
+        assert_eq!(common_entries(&a.concat(), &[]), 0);
+    }
+
+    #[test]
+    fn guard_of_splits_assertions_from_definitions() {
+        let definition = define_const("x", "Bool", "true");
+        let (definitions, guard) = guard_of(&entries(&[
+            Sexp::s2("assert", "a"),
+            definition.clone(),
+            Sexp::s2("assert", "b"),
+        ]));
+        assert_eq!(definitions, entries(&[definition]));
+        assert_eq!(guard, Sexp::s3("and", "a", "b"));
+    }
+
+    #[test]
+    fn guard_of_nothing_is_true() {
+        let (definitions, guard) = guard_of(&[]);
+        assert!(definitions.is_empty());
+        assert_eq!(guard, "true".to_sexp());
+    }
+}
@@ -0,0 +1,315 @@
+use llvm_ir::{Constant, ConstantRef, Name, Operand, TypeRef};
+
+use crate::{
+    aggregate::no_bits,
+    memory::POISON_BLOCK,
+    sexp::{Sexp, ToSexp},
+    z3_decl::{
+        add_offset, bv_hex, bv_ty, declare_const, extract, if_then_else, resize, BLOCK_BITS,
+    },
+    MemorySnapshot, Side, VerifierState,
+};
+
+// Next to its value, each register holds a poison mask of the same width. Each scalar
+// in the value, like a lane of a vector or a field of a struct, has all the bits of the
+// mask under it set if it is poison, and all clear otherwise. Moving scalars around, as
+// `extractvalue` or `shufflevector` do, therefore moves their masks the same way. In
+// memory, each byte of a poison scalar carries `POISON_BLOCK` in place of a block id, and
+// a loaded scalar is poison if any of its bytes is. The bytes keep the bits of the
+// poison, which only matter once the loaded value is frozen.
+//
+// Freezing poison gives an arbitrary value. The new function must be right whatever
+// value it gets, while the old function may pick any value it likes, knowing those of the
+// new function. Both sides take the values from the same array of arbitrary words, the
+// n-th freeze of the old function the words of the n-th freeze of the new one. That is
+// a choice the old function may make, and it needs no quantifiers. So freezes that are
+// kept, or moved without changing their order, match.
+//
+// `undef` is handled the same way, with an array of its own, except that each use of it
+// may be a different value and takes its own words.
+
+/// Offset of the poison mask of a register in the block of the register.
+const POISON_OFFSET: usize = 1 << 31;
+
+/// An array of arbitrary words, of which each use of `undef` takes its own.
+const UNDEF_WORDS: &str = "undef_words";
+
+/// An array of arbitrary words, of which each freeze takes its own.
+const FREEZE_WORDS: &str = "freeze_words";
+
+/// Number of words of `FREEZE_WORDS` and `UNDEF_WORDS` that each side took so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TakenWords {
+    freeze: [usize; 2],
+    undef: [usize; 2],
+}
+
+impl TakenWords {
+    fn freeze(&mut self, side: Side) -> &mut usize {
+        &mut self.freeze[side as usize]
+    }
+
+    fn undef(&mut self, side: Side) -> &mut usize {
+        &mut self.undef[side as usize]
+    }
+
+    /// Words taken when `side` starts over from where it had taken `start`.
+    pub fn restart(mut self, side: Side, start: Self) -> Self {
+        *self.freeze(side) = start.freeze[side as usize];
+        *self.undef(side) = start.undef[side as usize];
+        self
+    }
+
+    /// Words taken by either of two paths, so that a path that joins them takes none of
+    /// them again.
+    pub fn max(self, other: Self) -> Self {
+        Self {
+            freeze: [0, 1].map(|i| self.freeze[i].max(other.freeze[i])),
+            undef: [0, 1].map(|i| self.undef[i].max(other.undef[i])),
+        }
+    }
+}
+
+impl VerifierState {
+    fn poison_address(&self, name: &Name, frame: usize) -> Sexp {
+        bv_hex(self.address_of_name(name, frame) + POISON_OFFSET, 8)
+    }
+
+    pub fn store_poison(
+        &mut self,
+        name: &Name,
+        ty: &TypeRef,
+        mask: Sexp,
+        memory: MemorySnapshot,
+    ) -> MemorySnapshot {
+        let addr = self.poison_address(name, memory.frame);
+        self.store_in_addr(addr, ty, mask, memory)
+    }
+
+    /// Mask of the value of type `ty` loaded from the memory array index `addr`.
+    pub fn memory_poison(&self, addr: Sexp, ty: &TypeRef, memory: MemorySnapshot) -> Sexp {
+        if self.packed_vector_size(ty).is_some() {
+            // A lane is poison if a byte that holds any of its bits is.
+            let bytes = self.memory_poison(addr, &self.packed_bytes_type(ty), memory);
+            let mask = self.unpack_vector(bytes, ty);
+            return self.lane_poison(ty, vec![(mask, ty.clone())]);
+        }
+        if let Some(fields) = self.aggregate_fields(ty) {
+            let masks = fields
+                .iter()
+                .map(|(field_ty, offset)| {
+                    self.memory_poison(add_offset(&addr, *offset), field_ty, memory)
+                })
+                .collect();
+            return self.aggregate_value(masks);
+        }
+        let poison = (0..self.size_of_ty(ty))
+            .map(|i| {
+                let byte = Sexp::s3("select", memory, add_offset(&addr, i));
+                Sexp::s3(
+                    "=",
+                    extract(byte, BLOCK_BITS + 7, 8),
+                    bv_hex(POISON_BLOCK, BLOCK_BITS / 8),
+                )
+            })
+            .collect();
+        self.poison_if(ty, any(poison))
+    }
+
+    pub fn register_poison(&self, name: &Name, ty: &TypeRef, memory: MemorySnapshot) -> Sexp {
+        self.load_from_addr(self.poison_address(name, memory.frame), ty, memory)
+    }
+
+    pub fn operand_poison(&self, operand: &Operand, memory: MemorySnapshot) -> Sexp {
+        match operand {
+            Operand::LocalOperand { name, ty } => self.register_poison(name, ty, memory),
+            Operand::ConstantOperand(c) => self.constant_poison(c),
+            Operand::MetadataOperand => todo!(),
+        }
+    }
+
+    fn constant_poison(&self, c: &ConstantRef) -> Sexp {
+        match &**c {
+            Constant::Poison(ty) if self.value_bits(ty) == 0 => no_bits(),
+            Constant::Poison(ty) => Sexp::s2("bvnot", self.no_poison(ty)),
+            Constant::Struct { values, .. } => {
+                self.aggregate_value(values.iter().map(|x| self.constant_poison(x)).collect())
+            }
+            Constant::Array { elements, .. } | Constant::Vector(elements) => {
+                self.aggregate_value(elements.iter().map(|x| self.constant_poison(x)).collect())
+            }
+            _ => self.no_poison(&self.type_of(c)),
+        }
+    }
+
+    pub fn no_poison(&self, ty: &TypeRef) -> Sexp {
+        Sexp::s3("_", "bv0", &*self.value_bits(ty).to_string())
+    }
+
+    /// Mask of a scalar of type `ty` that is poison if `condition` holds.
+    pub fn poison_if(&self, ty: &TypeRef, condition: Sexp) -> Sexp {
+        let none = self.no_poison(ty);
+        if condition == "false".to_sexp() {
+            return none;
+        }
+        if_then_else(condition, Sexp::s2("bvnot", none.clone()), none)
+    }
+
+    /// Mask of a result of type `ty` of which a lane is poison if the same lane of any
+    /// of the `masks`, given with their types, is. A mask that is not of a vector
+    /// applies to every lane.
+    pub fn lane_poison(&self, ty: &TypeRef, masks: Vec<(Sexp, TypeRef)>) -> Sexp {
+        let lane_ty = self.lane_type(ty);
+        self.lane_wise(masks, |_, x| {
+            let poison = x.into_iter().map(is_poison).collect();
+            self.poison_if(&lane_ty, any(poison))
+        })
+    }
+
+    /// Replaces each poison scalar of a value by an arbitrary one.
+    pub fn freeze(&self, side: Side, ty: &TypeRef, value: Sexp, mask: Sexp) -> Sexp {
+        let mut taken = self.taken_words.get();
+        let arbitrary = self.arbitrary_words(FREEZE_WORDS, taken.freeze(side), ty);
+        self.taken_words.set(taken);
+        let operands = vec![
+            (value, ty.clone()),
+            (mask, ty.clone()),
+            (arbitrary, ty.clone()),
+        ];
+        self.lane_wise(operands, |_, x| {
+            if_then_else(is_poison(x[1].clone()), x[2].clone(), x[0].clone())
+        })
+    }
+
+    /// A value of type `ty` made of the words of the array `words` from index `next` on,
+    /// advancing `next` past them.
+    fn arbitrary_words(&self, words: &str, next: &mut usize, ty: &TypeRef) -> Sexp {
+        let bits = self.value_bits(ty);
+        if bits == 0 {
+            return no_bits();
+        }
+        let mut r = vec!["concat".to_sexp()];
+        for _ in 0..bits.div_ceil(64) {
+            r.push(Sexp::s3("select", words, bv_hex(*next, 8)));
+            *next += 1;
+        }
+        let r = match r.len() {
+            2 => r.pop().unwrap(),
+            _ => Sexp::List(r),
+        };
+        resize(r, bits.div_ceil(64) * 64, bits, false)
+    }
+
+    pub fn declare_arbitrary_words(&mut self) {
+        for words in [UNDEF_WORDS, FREEZE_WORDS] {
+            self.add_z3_line(declare_const(
+                words,
+                Sexp::s3("Array", bv_ty(64), bv_ty(64)),
+            ));
+        }
+    }
+
+    /// Value of a use of `undef` of type `ty`.
+    pub fn undef_value(&self, side: Side, ty: &TypeRef) -> Sexp {
+        let mut taken = self.taken_words.get();
+        let r = self.arbitrary_words(UNDEF_WORDS, taken.undef(side), ty);
+        self.taken_words.set(taken);
+        r
+    }
+
+    /// Whether an index into a vector of type `ty` is poison or out of range, which makes
+    /// the result of `extractelement` or `insertelement` poison.
+    pub fn lane_index_poison(
+        &self,
+        side: Side,
+        ty: &TypeRef,
+        index: &Operand,
+        memory: MemorySnapshot,
+    ) -> Sexp {
+        let (lanes, _) = self.vector_lanes(ty).unwrap();
+        let bits = self.value_bits(&self.type_of(index));
+        Sexp::s3(
+            "or",
+            is_poison(self.operand_poison(index, memory)),
+            Sexp::s3(
+                "bvuge",
+                self.operand_to_sexp(side, index, memory),
+                bv_hex(lanes, bits / 8),
+            ),
+        )
+    }
+
+    /// Makes it undefined behavior for `operand` to be poison, as when branching on it.
+    pub fn assume_not_poison(
+        &mut self,
+        side: Side,
+        operand: &Operand,
+        memory: MemorySnapshot,
+        message: &str,
+    ) {
+        let poison = is_poison(self.operand_poison(operand, memory));
+        self.assume(side, Sexp::s2("not", poison), message);
+    }
+}
+
+pub fn is_poison(mask: Sexp) -> Sexp {
+    Sexp::s3("=", Sexp::s2("bvredor", mask), "#b1")
+}
+
+/// Whether the new value may replace the old one: every scalar that is not poison in
+/// the old value is the same and not poison in the new one.
+pub fn refines(left: Sexp, left_mask: Sexp, right: Sexp, right_mask: Sexp) -> Sexp {
+    let differs = Sexp::s3("bvor", right_mask, Sexp::s3("bvxor", left, right));
+    let wrong = Sexp::s3("bvand", Sexp::s2("bvnot", left_mask), differs);
+    Sexp::s3("=", Sexp::s2("bvredor", wrong), "#b0")
+}
+
+pub fn any(conditions: Vec<Sexp>) -> Sexp {
+    match conditions.len() {
+        0 => "false".to_sexp(),
+        1 => conditions.into_iter().next().unwrap(),
+        _ => Sexp::List([vec!["or".to_sexp()], conditions].concat()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::simplify;
+
+    fn refines_bits(left: &str, left_mask: &str, right: &str, right_mask: &str) -> String {
+        simplify(refines(
+            left.to_sexp(),
+            left_mask.to_sexp(),
+            right.to_sexp(),
+            right_mask.to_sexp(),
+        ))
+    }
+
+    #[test]
+    fn equal_values_refine() {
+        assert_eq!(refines_bits("#x1234", "#x0000", "#x1234", "#x0000"), "true");
+        assert_eq!(
+            refines_bits("#x1234", "#x0000", "#x1235", "#x0000"),
+            "false"
+        );
+    }
+
+    #[test]
+    fn anything_refines_poison() {
+        assert_eq!(refines_bits("#x1234", "#xff00", "#x9934", "#x0000"), "true");
+        assert_eq!(refines_bits("#x1234", "#xff00", "#x1234", "#xff00"), "true");
+        assert_eq!(
+            refines_bits("#x1234", "#xff00", "#x1299", "#x0000"),
+            "false"
+        );
+    }
+
+    #[test]
+    fn poison_does_not_refine_a_value() {
+        assert_eq!(
+            refines_bits("#x1234", "#x0000", "#x1234", "#x00ff"),
+            "false"
+        );
+    }
+}
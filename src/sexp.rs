@@ -1,6 +1,6 @@
 use pretty::{Doc, RcDoc};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Sexp {
     Atom(String),
     List(Vec<Sexp>),
@@ -62,6 +62,59 @@ impl Sexp {
     }
 }
 
+impl Sexp {
+    /// Parses the s-expressions in `text`, like the answers of the solver. String
+    /// literals and quoted symbols are atoms that keep their quotes. Returns `None` if
+    /// the parentheses do not match.
+    pub fn parse(text: &str) -> Option<Vec<Sexp>> {
+        let mut stack = vec![vec![]];
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '(' => stack.push(vec![]),
+                ')' => {
+                    let list = stack.pop()?;
+                    stack.last_mut()?.push(Sexp::List(list));
+                }
+                _ if c.is_whitespace() => {}
+                _ => {
+                    let mut atom = c.to_string();
+                    let quote = match c {
+                        '"' | '|' => Some(c),
+                        _ => None,
+                    };
+                    while let Some(&next) = chars.peek() {
+                        match quote {
+                            Some(quote) => {
+                                atom.push(chars.next()?);
+                                if next != quote {
+                                    continue;
+                                }
+                                // A string literal escapes its quote by doubling it.
+                                let escaped = match quote {
+                                    '"' => chars.next_if_eq(&'"'),
+                                    _ => None,
+                                };
+                                match escaped {
+                                    Some(escaped) => atom.push(escaped),
+                                    None => break,
+                                }
+                            }
+                            None if next == '(' || next == ')' || next.is_whitespace() => break,
+                            None => atom.push(chars.next()?),
+                        }
+                    }
+                    stack.last_mut()?.push(Sexp::Atom(atom));
+                }
+            }
+        }
+        match <[_; 1]>::try_from(stack) {
+            Ok([r]) => Some(r),
+            Err(_) => None,
+        }
+    }
+}
+
 pub trait ToSexp {
     fn to_sexp(self) -> Sexp;
 }
@@ -77,3 +130,38 @@ impl ToSexp for Sexp {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_nested_lists() {
+        let r = Sexp::parse("sat\n((x #x01) (y (_ bv3 8)))\n").unwrap();
+        assert_eq!(
+            r,
+            vec![
+                "sat".to_sexp(),
+                Sexp::s2(
+                    Sexp::s2("x", "#x01"),
+                    Sexp::s2("y", Sexp::s3("_", "bv3", "8"))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_quoted_atoms() {
+        let r = Sexp::parse(r#"(echo "a ""b"" (c") |x y|"#).unwrap();
+        assert_eq!(
+            r,
+            vec![Sexp::s2("echo", r#""a ""b"" (c""#), "|x y|".to_sexp(),]
+        );
+    }
+
+    #[test]
+    fn parse_unmatched_parentheses() {
+        assert_eq!(Sexp::parse("(a (b)"), None);
+        assert_eq!(Sexp::parse("(a))"), None);
+    }
+}
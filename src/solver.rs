@@ -0,0 +1,99 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+    rc::Rc,
+};
+
+use crate::sexp::Sexp;
+
+/// A z3 process shared by all paths. Paths mostly extend the commands of the path they
+/// were split from, so the commands of each query are kept in the solver and only the
+/// ones that differ from the previous query are popped and pushed.
+#[derive(Default)]
+pub struct Solver {
+    process: Option<(Child, ChildStdin, BufReader<ChildStdout>)>,
+    /// The commands in effect, in the groups they were pushed in, one scope each.
+    scopes: Vec<Vec<Rc<Sexp>>>,
+}
+
+/// Printed after the answers to a query, so that the end of them can be found.
+const END_OF_ANSWER: &str = "end-of-answer";
+
+impl Solver {
+    /// Runs `query` after `commands`, and returns what the solver printed for it.
+    pub fn run(&mut self, commands: &[Rc<Sexp>], query: &[Sexp]) -> String {
+        let mut input = String::new();
+        self.load(commands, &mut input);
+        input += "(push)\n";
+        for x in query {
+            input += &x.to_pretty(100);
+            input.push('\n');
+        }
+        input += &format!("(pop)\n(echo \"{END_OF_ANSWER}\")\n");
+        let (_, stdin, stdout) = self.process.get_or_insert_with(|| {
+            let mut child = Command::new("z3")
+                .arg("-in")
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn()
+                .unwrap();
+            let stdin = child.stdin.take().unwrap();
+            let stdout = BufReader::new(child.stdout.take().unwrap());
+            (child, stdin, stdout)
+        });
+        stdin.write_all(input.as_bytes()).unwrap();
+        stdin.flush().unwrap();
+        let mut r = String::new();
+        loop {
+            let mut line = String::new();
+            if stdout.read_line(&mut line).unwrap() == 0 {
+                panic!("z3 exited while answering:\n{r}");
+            }
+            if line.trim_end() == END_OF_ANSWER {
+                return r;
+            }
+            r += &line;
+        }
+    }
+
+    /// Adds the commands to `input` that make `commands` the ones in effect.
+    fn load(&mut self, commands: &[Rc<Sexp>], input: &mut String) {
+        let common = self
+            .scopes
+            .iter()
+            .flatten()
+            .zip(commands)
+            .take_while(|(x, y)| Rc::ptr_eq(x, y) || x == y)
+            .count();
+        let mut loaded: usize = self.scopes.iter().map(|x| x.len()).sum();
+        while loaded > common {
+            loaded -= self.scopes.pop().unwrap().len();
+            *input += "(pop)\n";
+        }
+        if loaded == commands.len() {
+            return;
+        }
+        *input += "(push)\n";
+        for x in &commands[loaded..] {
+            *input += &x.to_pretty(100);
+            input.push('\n');
+        }
+        self.scopes.push(commands[loaded..].to_vec());
+    }
+}
+
+impl Drop for Solver {
+    fn drop(&mut self) {
+        if let Some((mut child, stdin, _)) = self.process.take() {
+            drop(stdin);
+            child.wait().unwrap();
+        }
+    }
+}
+
+/// What the solver simplifies `x` to, like `#x03` or `true` for closed terms.
+#[cfg(test)]
+pub fn simplify(x: Sexp) -> String {
+    let r = Solver::default().run(&[], &[Sexp::s2("simplify", x)]);
+    r.trim().to_owned()
+}
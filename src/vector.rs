@@ -0,0 +1,393 @@
+use llvm_ir::{Constant, ConstantRef, Type, TypeRef};
+
+use crate::{
+    sexp::{Sexp, ToSexp},
+    z3_decl::{bit_to_byte, bv_hex, declare_const, extract, if_then_else, resize},
+    Side, VerifierState,
+};
+
+// Vectors are encoded like arrays, see `aggregate`: the value of a vector is the
+// concatenation of its lanes, lane 0 being the most significant. In memory, lanes are
+// stored one after another without padding, except that lanes narrower than a byte, as
+// in `<8 x i1>`, are packed like `bitcast` packs them. Registers do not pack them.
+
+impl VerifierState {
+    /// Number of lanes and the type of a lane of a fixed size vector type.
+    pub fn vector_lanes(&self, ty: &TypeRef) -> Option<(usize, TypeRef)> {
+        match &**ty {
+            Type::VectorType {
+                element_type,
+                num_elements,
+                scalable: false,
+            } => Some((*num_elements, element_type.clone())),
+            Type::VectorType { scalable: true, .. } => {
+                panic!("scalable vector {ty} is not supported, since its size is unknown")
+            }
+            _ => None,
+        }
+    }
+
+    /// Type of a lane of a vector type, or the type itself if it is not a vector.
+    pub fn lane_type(&self, ty: &TypeRef) -> TypeRef {
+        match self.vector_lanes(ty) {
+            Some((_, lane_ty)) => lane_ty,
+            None => ty.clone(),
+        }
+    }
+
+    /// Applies a scalar operation to each lane of the operands, which are given with
+    /// their types. Operands that are not vectors are passed as they are to each lane.
+    /// The operation gets the type of a lane of the first vector operand, or the type
+    /// of the first operand if there are no vectors.
+    pub fn lane_wise(
+        &self,
+        operands: Vec<(Sexp, TypeRef)>,
+        mut op: impl FnMut(&TypeRef, Vec<Sexp>) -> Sexp,
+    ) -> Sexp {
+        let vector = operands
+            .iter()
+            .find_map(|(_, ty)| Some((ty, self.vector_lanes(ty)?)));
+        let Some((_, (lanes, lane_ty))) = vector else {
+            let ty = operands[0].1.clone();
+            return op(&ty, operands.into_iter().map(|(x, _)| x).collect());
+        };
+        let r = (0..lanes)
+            .map(|i| {
+                let args = operands
+                    .iter()
+                    .map(|(x, ty)| match self.vector_lanes(ty) {
+                        Some(_) => self.extract_field(x.clone(), ty, i),
+                        None => x.clone(),
+                    })
+                    .collect();
+                op(&lane_ty, args)
+            })
+            .collect();
+        self.aggregate_value(r)
+    }
+
+    /// The lane of a vector at an index that need not be constant. An index out of
+    /// range gives poison, for which the last lane stands in.
+    pub fn lane_at(&self, vector: Sexp, ty: &TypeRef, index: Sexp, index_bits: usize) -> Sexp {
+        let (lanes, _) = self.vector_lanes(ty).unwrap();
+        let mut r = self.extract_field(vector.clone(), ty, lanes - 1);
+        for i in (0..lanes - 1).rev() {
+            let lane = self.extract_field(vector.clone(), ty, i);
+            r = if_then_else(
+                Sexp::s3("=", index.clone(), bv_hex(i, index_bits / 8)),
+                lane,
+                r,
+            );
+        }
+        r
+    }
+
+    /// Replaces the lane of a vector at an index that need not be constant.
+    pub fn insert_lane_at(
+        &self,
+        vector: Sexp,
+        ty: &TypeRef,
+        index: Sexp,
+        index_bits: usize,
+        element: Sexp,
+    ) -> Sexp {
+        let (lanes, _) = self.vector_lanes(ty).unwrap();
+        let r = (0..lanes)
+            .map(|i| {
+                if_then_else(
+                    Sexp::s3("=", index.clone(), bv_hex(i, index_bits / 8)),
+                    element.clone(),
+                    self.extract_field(vector.clone(), ty, i),
+                )
+            })
+            .collect();
+        self.aggregate_value(r)
+    }
+
+    pub fn arbitrary_value(&mut self, ty: &TypeRef) -> Sexp {
+        let name = format!("{}_arbitrary", self.new_memory());
+        self.add_z3_line(declare_const(&*name, self.sort_of_ty(ty)));
+        name.to_sexp()
+    }
+
+    /// Result of `shufflevector`. Lanes with an undefined mask element are taken from
+    /// `undefined`, which is of the result type.
+    pub fn shuffle(
+        &self,
+        first: Sexp,
+        second: Sexp,
+        ty: &TypeRef,
+        mask: &ConstantRef,
+        result_ty: &TypeRef,
+        undefined: Sexp,
+    ) -> Sexp {
+        let (lanes, _) = self.vector_lanes(ty).unwrap();
+        let (result_lanes, _) = self.vector_lanes(result_ty).unwrap();
+        let mask: Vec<Option<usize>> = match &**mask {
+            Constant::Vector(elements) => elements
+                .iter()
+                .map(|x| match &**x {
+                    Constant::Int { value, .. } => Some(*value as usize),
+                    Constant::Undef(_) | Constant::Poison(_) => None,
+                    _ => panic!("shuffle mask element {x} is not an integer"),
+                })
+                .collect(),
+            Constant::AggregateZero(_) => vec![Some(0); result_lanes],
+            Constant::Undef(_) | Constant::Poison(_) => vec![None; result_lanes],
+            _ => panic!("shuffle mask {mask} is not a constant vector"),
+        };
+        let r = mask
+            .iter()
+            .enumerate()
+            .map(|(j, x)| match *x {
+                Some(i) if i < lanes => self.extract_field(first.clone(), ty, i),
+                Some(i) => self.extract_field(second.clone(), ty, i - lanes),
+                None => self.extract_field(undefined.clone(), result_ty, j),
+            })
+            .collect();
+        self.aggregate_value(r)
+    }
+
+    /// Result of `bitcast`, which keeps the bytes that a store of the value would write.
+    /// Lane 0 of a vector is the most significant in its value but at the lowest address
+    /// in memory, so the lanes swap ends on little endian targets. Vectors of integers
+    /// narrower than a byte, like `<8 x i1>`, are packed to the bits of the integers.
+    pub fn bitcast(&self, value: Sexp, from: &TypeRef, to: &TypeRef) -> Sexp {
+        let same_lanes = match (self.vector_lanes(from), self.vector_lanes(to)) {
+            (Some((x, x_ty)), Some((y, y_ty))) => {
+                x == y && self.value_bits(&x_ty) == self.value_bits(&y_ty)
+            }
+            (None, None) => true,
+            _ => false,
+        };
+        if same_lanes {
+            return value;
+        }
+        self.value_of_memory_bits(self.memory_bits(value, from), to)
+    }
+
+    /// Bytes that a vector of type `ty` takes in memory, if its lanes are packed.
+    pub fn packed_vector_size(&self, ty: &TypeRef) -> Option<usize> {
+        let (lanes, lane_ty) = self.vector_lanes(ty)?;
+        Some(bit_to_byte(lanes * self.packed_lane_bits(&lane_ty)?))
+    }
+
+    /// The array of bytes that a packed vector of type `ty` is stored as.
+    pub fn packed_bytes_type(&self, ty: &TypeRef) -> TypeRef {
+        let size = self.packed_vector_size(ty).unwrap();
+        self.module.types.array_of(self.module.types.i8(), size)
+    }
+
+    /// The bytes of a packed vector, as a value of `packed_bytes_type`.
+    pub fn pack_vector(&self, value: Sexp, ty: &TypeRef) -> Sexp {
+        let size = self.packed_vector_size(ty).unwrap();
+        let bits = resize(
+            self.memory_bits(value, ty),
+            self.packed_bits(ty),
+            size * 8,
+            false,
+        );
+        self.bytes_of_memory_bits(bits, size)
+    }
+
+    /// Inverse of `pack_vector`.
+    pub fn unpack_vector(&self, bytes: Sexp, ty: &TypeRef) -> Sexp {
+        let size = self.packed_vector_size(ty).unwrap();
+        let bytes_ty = self.packed_bytes_type(ty);
+        let mut r: Vec<_> = (0..size)
+            .map(|i| self.extract_field(bytes.clone(), &bytes_ty, i))
+            .collect();
+        if !self.is_big_endian() {
+            r.reverse();
+        }
+        let bits = resize(
+            self.aggregate_value(r),
+            size * 8,
+            self.packed_bits(ty),
+            false,
+        );
+        self.value_of_memory_bits(bits, ty)
+    }
+
+    /// Poison mask of the bytes of a packed vector with the poison mask `mask`. A byte
+    /// that holds only some poison lanes is poison in the new function. The old one may
+    /// pick any value for those lanes, and keeps their bits instead.
+    pub fn pack_vector_poison(&self, side: Side, mask: Sexp, ty: &TypeRef) -> Sexp {
+        let size = self.packed_vector_size(ty).unwrap();
+        let bits = self.packed_bits(ty);
+        let mask = self.memory_bits(mask, ty);
+        let padded = match (side, size * 8 - bits) {
+            (_, 0) => mask,
+            (Side::Left, padding) => {
+                let ones = Sexp::s2("bvnot", Sexp::s3("_", "bv0", &*padding.to_string()));
+                Sexp::s3("concat", ones, mask)
+            }
+            (Side::Right, _) => resize(mask, bits, size * 8, false),
+        };
+        let bytes_ty = self.packed_bytes_type(ty);
+        let byte_ty = self.module.types.i8();
+        let bytes = self.bytes_of_memory_bits(padded, size);
+        let r = (0..size)
+            .map(|i| {
+                let byte = self.extract_field(bytes.clone(), &bytes_ty, i);
+                let poison = match side {
+                    Side::Left => Sexp::s3("=", byte, "#xff"),
+                    Side::Right => Sexp::s3("distinct", byte, "#x00"),
+                };
+                self.poison_if(&byte_ty, poison)
+            })
+            .collect();
+        self.aggregate_value(r)
+    }
+
+    /// Bits of the lanes of a packed vector.
+    fn packed_bits(&self, ty: &TypeRef) -> usize {
+        let (lanes, lane_ty) = self.vector_lanes(ty).unwrap();
+        lanes * self.packed_lane_bits(&lane_ty).unwrap()
+    }
+
+    /// The bytes of the integer `bits` of `size` bytes, from the lowest address.
+    fn bytes_of_memory_bits(&self, bits: Sexp, size: usize) -> Sexp {
+        let r = (0..size)
+            .map(|i| {
+                let significance = match self.is_big_endian() {
+                    true => size - 1 - i,
+                    false => i,
+                };
+                extract(bits.clone(), significance * 8 + 7, significance * 8)
+            })
+            .collect();
+        self.aggregate_value(r)
+    }
+
+    /// Bits that a lane of type `ty` takes in a packed vector, when it is not a whole
+    /// number of bytes.
+    fn packed_lane_bits(&self, ty: &TypeRef) -> Option<usize> {
+        match &**ty {
+            Type::IntegerType { bits } if bits % 8 != 0 => Some(*bits as usize),
+            _ => None,
+        }
+    }
+
+    /// The value as the integer that loading its bytes from memory would give.
+    fn memory_bits(&self, value: Sexp, ty: &TypeRef) -> Sexp {
+        let Some((lanes, lane_ty)) = self.vector_lanes(ty) else {
+            return match self.packed_lane_bits(ty) {
+                Some(bits) => extract(value, bits - 1, 0),
+                None => value,
+            };
+        };
+        let mut r: Vec<Sexp> = (0..lanes)
+            .map(|i| {
+                let lane = self.extract_field(value.clone(), ty, i);
+                match self.packed_lane_bits(&lane_ty) {
+                    Some(bits) => extract(lane, bits - 1, 0),
+                    None => lane,
+                }
+            })
+            .collect();
+        if !self.is_big_endian() {
+            r.reverse();
+        }
+        self.aggregate_value(r)
+    }
+
+    /// Inverse of `memory_bits`.
+    fn value_of_memory_bits(&self, bits: Sexp, ty: &TypeRef) -> Sexp {
+        let Some((lanes, lane_ty)) = self.vector_lanes(ty) else {
+            let value_bits = self.value_bits(ty);
+            let packed = self.packed_lane_bits(ty).unwrap_or(value_bits);
+            return resize(bits, packed, value_bits, false);
+        };
+        let value_bits = self.value_bits(&lane_ty);
+        let lane_bits = self.packed_lane_bits(&lane_ty).unwrap_or(value_bits);
+        let r = (0..lanes)
+            .map(|i| {
+                let position = match self.is_big_endian() {
+                    true => lanes - 1 - i,
+                    false => i,
+                };
+                let lane = extract(
+                    bits.clone(),
+                    (position + 1) * lane_bits - 1,
+                    position * lane_bits,
+                );
+                resize(lane, lane_bits, value_bits, false)
+            })
+            .collect();
+        self.aggregate_value(r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use llvm_ir::{module::Endianness, types::Types};
+
+    use super::*;
+    use crate::{solver::simplify, tests::state};
+
+    #[test]
+    fn bitcast_puts_lane_zero_at_the_lowest_address() {
+        let types = Types::blank_for_testing();
+        let vector = types.vector_of(types.i16(), 2, false);
+        let value = "#x12345678".to_sexp();
+        let little = state(Endianness::LittleEndian);
+        let r = little.bitcast(value.clone(), &vector, &types.i32());
+        assert_eq!(simplify(r.clone()), "#x56781234");
+        assert_eq!(
+            simplify(little.bitcast(r, &types.i32(), &vector)),
+            "#x12345678"
+        );
+        let big = state(Endianness::BigEndian);
+        assert_eq!(
+            simplify(big.bitcast(value, &vector, &types.i32())),
+            "#x12345678"
+        );
+    }
+
+    #[test]
+    fn bitcast_packs_booleans() {
+        let types = Types::blank_for_testing();
+        let vector = types.vector_of(types.bool(), 8, false);
+        let state = state(Endianness::LittleEndian);
+        // Lanes 0 and 2 are set.
+        let value = "#x0100010000000000".to_sexp();
+        let r = state.bitcast(value, &vector, &types.i8());
+        assert_eq!(simplify(r.clone()), "#x05");
+        let r = state.bitcast(r, &types.i8(), &vector);
+        assert_eq!(simplify(r), "#x0100010000000000");
+    }
+
+    #[test]
+    fn booleans_are_packed_in_memory() {
+        let types = Types::blank_for_testing();
+        let vector = types.vector_of(types.bool(), 10, false);
+        let state = state(Endianness::LittleEndian);
+        assert_eq!(state.size_of_ty(&vector), 2);
+        // Lanes 0 and 9 are set, which are the lowest bits of the first and second byte.
+        let value = "#x01000000000000000001".to_sexp();
+        let bytes = state.pack_vector(value.clone(), &vector);
+        assert_eq!(simplify(bytes.clone()), "#x0102");
+        assert_eq!(
+            simplify(state.unpack_vector(bytes, &vector)),
+            "#x01000000000000000001"
+        );
+        // The byte with only a poison lane 9 is poison only in the new function.
+        let mask = "#x000000000000000000ff".to_sexp();
+        let left = state.pack_vector_poison(Side::Left, mask.clone(), &vector);
+        assert_eq!(simplify(left), "#x0000");
+        let right = state.pack_vector_poison(Side::Right, mask, &vector);
+        assert_eq!(simplify(right), "#x00ff");
+    }
+
+    #[test]
+    fn bitcast_between_vectors() {
+        let types = Types::blank_for_testing();
+        let from = types.vector_of(types.i16(), 2, false);
+        let to = types.vector_of(types.i8(), 4, false);
+        let state = state(Endianness::LittleEndian);
+        // Lane 0 of `from` holds the lanes 0 and 1 of `to`, low byte first.
+        let r = state.bitcast("#x12345678".to_sexp(), &from, &to);
+        assert_eq!(simplify(r), "#x34127856");
+    }
+}
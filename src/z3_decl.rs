@@ -16,8 +16,16 @@ pub fn if_then_else(
     Sexp::s4("ite", condition, true_value, false_value)
 }
 
+/// A string literal with the given contents.
+pub fn string_literal(text: &str) -> Sexp {
+    Sexp::Atom(format!("\"{}\"", text.replace('"', "\"\"")))
+}
+
+/// Width of a block id, which is stored next to each byte of memory as its provenance.
+pub const BLOCK_BITS: usize = 32;
+
 pub fn memory_ty() -> Sexp {
-    Sexp::s3("Array", bv_ty(64), bv_ty(8))
+    Sexp::s3("Array", bv_ty(64), bv_ty(BLOCK_BITS + 8))
 }
 
 pub fn bv_ty(arg: usize) -> Sexp {
@@ -33,3 +41,34 @@ pub fn bv_hex(arg: usize, size: usize) -> Sexp {
 pub fn bit_to_byte(bits: usize) -> usize {
     (bits + 7) / 8
 }
+
+pub fn extract(value: impl ToSexp, high: usize, low: usize) -> Sexp {
+    Sexp::s2(
+        Sexp::s4("_", "extract", &*high.to_string(), &*low.to_string()),
+        value,
+    )
+}
+
+pub fn resize(value: impl ToSexp, from: usize, to: usize, signed: bool) -> Sexp {
+    if from == to {
+        value.to_sexp()
+    } else if from > to {
+        extract(value, to - 1, 0)
+    } else {
+        let op = if signed { "sign_extend" } else { "zero_extend" };
+        Sexp::s2(Sexp::s3("_", op, &*(to - from).to_string()), value)
+    }
+}
+
+/// Address `offset` bytes after `addr`, folded when `addr` is a literal.
+pub fn add_offset(addr: &Sexp, offset: usize) -> Sexp {
+    if offset == 0 {
+        return addr.clone();
+    }
+    if let Sexp::Atom(x) = addr {
+        if let Some(Ok(base)) = x.strip_prefix("#x").map(|x| usize::from_str_radix(x, 16)) {
+            return bv_hex(base + offset, 8);
+        }
+    }
+    Sexp::s3("bvadd", addr.clone(), bv_hex(offset, 8))
+}
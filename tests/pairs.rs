@@ -0,0 +1,59 @@
+//! Runs the checker on the modules in `tests/pairs`, which are grouped in a directory
+//! per feature. Each module holds a `left` and a `right` function and says on its
+//! `; expect:` lines what the checker must print. A verdict, like `equivalent`, on the
+//! first of them must be the first line of a successful run, anything else, like the
+//! goals that failed, a line of the report of a counterexample. The lines of the other
+//! ones, like the statistics of the exploration, must be printed too. A module passes
+//! extra arguments on a `; flags:` line.
+
+use std::{fs, path::Path, process::Command};
+
+/// The values of the `; <key>:` lines of a module.
+fn headers<'a>(text: &'a str, key: &'a str) -> impl Iterator<Item = &'a str> {
+    text.lines()
+        .filter_map(move |x| x.strip_prefix("; ")?.strip_prefix(key)?.strip_prefix(": "))
+}
+
+/// Whether the checker succeeded on the module, and what it printed.
+fn check(path: &Path, flags: &str) -> (bool, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_llvm-transform-checker"))
+        .args(flags.split_whitespace())
+        .arg(path)
+        .output()
+        .unwrap();
+    let printed = String::from_utf8_lossy(&output.stdout) + String::from_utf8_lossy(&output.stderr);
+    (output.status.success(), printed.into_owned())
+}
+
+#[test]
+fn pairs() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/pairs");
+    let mut modules = vec![];
+    for dir in fs::read_dir(root).unwrap() {
+        for module in fs::read_dir(dir.unwrap().path()).unwrap() {
+            modules.push(module.unwrap().path());
+        }
+    }
+    modules.sort();
+    let mut failures = vec![];
+    for module in modules {
+        let text = fs::read_to_string(&module).unwrap();
+        let expected: Vec<_> = headers(&text, "expect").collect();
+        let Some(outcome) = expected.first() else {
+            panic!("{} has no expect line", module.display());
+        };
+        let flags = headers(&text, "flags").next().unwrap_or_default();
+        let (success, printed) = check(&module, flags);
+        let passed = match outcome.starts_with("equivalent") {
+            true => success && printed.lines().next() == Some(*outcome),
+            false => !success,
+        } && expected.iter().all(|x| printed.lines().any(|y| y == *x));
+        if !passed {
+            failures.push(format!(
+                "{} did not print {expected:?}:\n{printed}",
+                module.display()
+            ));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
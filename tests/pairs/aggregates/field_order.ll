@@ -0,0 +1,17 @@
+; The pair is built field by field in a different order on each side.
+; expect: equivalent
+define { i32, i32 } @left(i32 noundef %x, i32 noundef %y) {
+entry:
+  %a = insertvalue { i32, i32 } undef, i32 %x, 0
+  %b = insertvalue { i32, i32 } %a, i32 %y, 1
+  ret { i32, i32 } %b
+}
+
+define { i32, i32 } @right(i32 noundef %x, i32 noundef %y) {
+entry:
+  %a = insertvalue { i32, i32 } undef, i32 %y, 1
+  %b = insertvalue { i32, i32 } %a, i32 %x, 0
+  %first = extractvalue { i32, i32 } %b, 0
+  %c = insertvalue { i32, i32 } %b, i32 %first, 0
+  ret { i32, i32 } %c
+}
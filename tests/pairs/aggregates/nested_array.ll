@@ -0,0 +1,15 @@
+; Fields of an array in a struct are reached with a path of indices.
+; expect: equivalent
+define i32 @left(i32 noundef %x, i32 noundef %y) {
+entry:
+  %a = insertvalue { i8, [2 x i32] } undef, i32 %x, 1, 0
+  %b = insertvalue { i8, [2 x i32] } %a, i32 %y, 1, 1
+  %arr = extractvalue { i8, [2 x i32] } %b, 1
+  %r = extractvalue [2 x i32] %arr, 1
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %x, i32 noundef %y) {
+entry:
+  ret i32 %y
+}
@@ -0,0 +1,17 @@
+; The overflow flag of an unsigned addition tells whether the sum wrapped around.
+; expect: equivalent
+declare { i32, i1 } @llvm.uadd.with.overflow.i32(i32, i32)
+
+define i1 @left(i32 noundef %a, i32 noundef %b) {
+entry:
+  %s = call { i32, i1 } @llvm.uadd.with.overflow.i32(i32 %a, i32 %b)
+  %r = extractvalue { i32, i1 } %s, 1
+  ret i1 %r
+}
+
+define i1 @right(i32 noundef %a, i32 noundef %b) {
+entry:
+  %s = add i32 %a, %b
+  %r = icmp ult i32 %s, %a
+  ret i1 %r
+}
@@ -0,0 +1,14 @@
+; The second field of the left is poison, so the right may give it any value.
+; expect: equivalent
+define { i32, i32 } @left(i32 noundef %x) {
+entry:
+  %a = insertvalue { i32, i32 } poison, i32 %x, 0
+  ret { i32, i32 } %a
+}
+
+define { i32, i32 } @right(i32 noundef %x) {
+entry:
+  %a = insertvalue { i32, i32 } poison, i32 %x, 0
+  %b = insertvalue { i32, i32 } %a, i32 7, 1
+  ret { i32, i32 } %b
+}
@@ -0,0 +1,20 @@
+; A stored struct is laid out with the padding of its fields, so its second field is
+; four bytes after the first.
+; expect: equivalent
+define i32 @left(ptr noundef %p, i8 noundef %x, i32 noundef %y) {
+entry:
+  %a = insertvalue { i8, i32 } undef, i8 %x, 0
+  %b = insertvalue { i8, i32 } %a, i32 %y, 1
+  store { i8, i32 } %b, ptr %p
+  %q = getelementptr i8, ptr %p, i64 4
+  %r = load i32, ptr %q
+  ret i32 %r
+}
+
+define i32 @right(ptr noundef %p, i8 noundef %x, i32 noundef %y) {
+entry:
+  %a = insertvalue { i8, i32 } undef, i8 %x, 0
+  %b = insertvalue { i8, i32 } %a, i32 %y, 1
+  store { i8, i32 } %b, ptr %p
+  ret i32 %y
+}
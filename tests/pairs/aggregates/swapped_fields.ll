@@ -0,0 +1,15 @@
+; The right swaps the fields, and both of them are reported.
+; expect: Failed: return_0, return_1
+define { i32, i32 } @left(i32 noundef %x, i32 noundef %y) {
+entry:
+  %a = insertvalue { i32, i32 } undef, i32 %x, 0
+  %b = insertvalue { i32, i32 } %a, i32 %y, 1
+  ret { i32, i32 } %b
+}
+
+define { i32, i32 } @right(i32 noundef %x, i32 noundef %y) {
+entry:
+  %a = insertvalue { i32, i32 } undef, i32 %x, 1
+  %b = insertvalue { i32, i32 } %a, i32 %y, 0
+  ret { i32, i32 } %b
+}
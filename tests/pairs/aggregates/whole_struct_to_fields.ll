@@ -0,0 +1,28 @@
+; Storing the whole struct makes its padding poison, so stores of the fields may
+; replace it even when the padding is read.
+; expect: equivalent
+declare void @llvm.memset.p0.i64(ptr, i8, i64, i1)
+
+define i8 @left(i8 noundef %x, i32 noundef %y) {
+entry:
+  %p = alloca { i8, i32 }
+  call void @llvm.memset.p0.i64(ptr %p, i8 0, i64 8, i1 false)
+  %a = insertvalue { i8, i32 } poison, i8 %x, 0
+  %b = insertvalue { i8, i32 } %a, i32 %y, 1
+  store { i8, i32 } %b, ptr %p
+  %r = getelementptr i8, ptr %p, i64 1
+  %v = load i8, ptr %r
+  ret i8 %v
+}
+
+define i8 @right(i8 noundef %x, i32 noundef %y) {
+entry:
+  %p = alloca { i8, i32 }
+  call void @llvm.memset.p0.i64(ptr %p, i8 0, i64 8, i1 false)
+  store i8 %x, ptr %p
+  %q = getelementptr i8, ptr %p, i64 4
+  store i32 %y, ptr %q
+  %r = getelementptr i8, ptr %p, i64 1
+  %v = load i8, ptr %r
+  ret i8 %v
+}
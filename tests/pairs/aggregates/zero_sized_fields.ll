@@ -0,0 +1,21 @@
+; Zero-sized fields, as rustc emits for alignment and for unit values, have no bits in
+; the value, and stores of them write no bytes.
+; expect: equivalent
+define { i32, [0 x i8] } @left(i32 noundef %x) {
+entry:
+  %p = alloca { i32, [0 x i8] }
+  %a = insertvalue { i32, [0 x i8] } undef, i32 %x, 0
+  %b = insertvalue { i32, [0 x i8] } %a, [0 x i8] zeroinitializer, 1
+  store { i32, [0 x i8] } %b, ptr %p
+  store {} zeroinitializer, ptr %p
+  %l = load { i32, [0 x i8] }, ptr %p
+  %e = extractvalue { i32, [0 x i8] } %l, 1
+  %r = insertvalue { i32, [0 x i8] } %l, [0 x i8] %e, 1
+  ret { i32, [0 x i8] } %r
+}
+
+define { i32, [0 x i8] } @right(i32 noundef %x) {
+entry:
+  %a = insertvalue { i32, [0 x i8] } undef, i32 %x, 0
+  ret { i32, [0 x i8] } %a
+}
@@ -0,0 +1,29 @@
+; An assumption the right adds may state what a branch of both sides already checked.
+; expect: equivalent
+declare void @llvm.assume(i1)
+
+define i32 @left(i32 noundef %x) {
+entry:
+  %small = icmp ult i32 %x, 10
+  br i1 %small, label %then, label %else
+
+then:
+  ret i32 %x
+
+else:
+  ret i32 0
+}
+
+define i32 @right(i32 noundef %x) {
+entry:
+  %small = icmp ult i32 %x, 10
+  br i1 %small, label %then, label %else
+
+then:
+  %tiny = icmp ult i32 %x, 11
+  call void @llvm.assume(i1 %tiny)
+  ret i32 %x
+
+else:
+  ret i32 0
+}
@@ -0,0 +1,15 @@
+; The right may drop an assumption, since it only gives the optimizer information.
+; expect: equivalent
+declare void @llvm.assume(i1)
+
+define i32 @left(i32 noundef %x) {
+entry:
+  %small = icmp ult i32 %x, 10
+  call void @llvm.assume(i1 %small)
+  ret i32 %x
+}
+
+define i32 @right(i32 noundef %x) {
+entry:
+  ret i32 %x
+}
@@ -0,0 +1,23 @@
+; `llvm.expect` returns its argument and the lifetime markers have no effect.
+; expect: equivalent
+declare i32 @llvm.expect.i32(i32, i32)
+
+declare void @llvm.lifetime.start.p0(i64, ptr)
+
+declare void @llvm.lifetime.end.p0(i64, ptr)
+
+define i32 @left(i32 noundef %x) {
+entry:
+  %p = alloca i32
+  call void @llvm.lifetime.start.p0(i64 4, ptr %p)
+  store i32 %x, ptr %p
+  %v = load i32, ptr %p
+  call void @llvm.lifetime.end.p0(i64 4, ptr %p)
+  %r = call i32 @llvm.expect.i32(i32 %v, i32 0)
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %x) {
+entry:
+  ret i32 %x
+}
@@ -0,0 +1,18 @@
+; What the assumption of the left implies need not be computed on the right.
+; expect: equivalent
+declare void @llvm.assume(i1)
+
+define i1 @left(i32 noundef %x) {
+entry:
+  %small = icmp ult i32 %x, 10
+  call void @llvm.assume(i1 %small)
+  %r = icmp ult i32 %x, 20
+  ret i1 %r
+}
+
+define i1 @right(i32 noundef %x) {
+entry:
+  %small = icmp ult i32 %x, 10
+  call void @llvm.assume(i1 %small)
+  ret i1 true
+}
@@ -0,0 +1,18 @@
+; The assumption is too weak for the right to return true.
+; expect: Failed: return
+declare void @llvm.assume(i1)
+
+define i1 @left(i32 noundef %x) {
+entry:
+  %small = icmp ult i32 %x, 30
+  call void @llvm.assume(i1 %small)
+  %r = icmp ult i32 %x, 20
+  ret i1 %r
+}
+
+define i1 @right(i32 noundef %x) {
+entry:
+  %small = icmp ult i32 %x, 30
+  call void @llvm.assume(i1 %small)
+  ret i1 true
+}
@@ -0,0 +1,14 @@
+; The expected value is only a hint and not what `llvm.expect` returns.
+; expect: Failed: return
+declare i32 @llvm.expect.i32(i32, i32)
+
+define i32 @left(i32 noundef %x) {
+entry:
+  %r = call i32 @llvm.expect.i32(i32 %x, i32 0)
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %x) {
+entry:
+  ret i32 0
+}
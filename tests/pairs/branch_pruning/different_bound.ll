@@ -0,0 +1,32 @@
+; The right takes the small case for 10 too.
+; expect: Failed: return
+define i32 @left(i32 noundef %x) {
+entry:
+  %small = icmp ult i32 %x, 10
+  br i1 %small, label %inner, label %large
+
+inner:
+  %smaller = icmp ult i32 %x, 20
+  br i1 %smaller, label %one, label %two
+
+one:
+  ret i32 1
+
+two:
+  ret i32 2
+
+large:
+  ret i32 3
+}
+
+define i32 @right(i32 noundef %x) {
+entry:
+  %small = icmp ult i32 %x, 11
+  br i1 %small, label %one, label %large
+
+one:
+  ret i32 1
+
+large:
+  ret i32 3
+}
@@ -0,0 +1,34 @@
+; The inner branch of the left always goes the same way, so of the combinations with
+; the branch of the right only the feasible ones are explored.
+; expect: equivalent
+; expect: explored 4 paths, pruned 1 infeasible branch combination, matched 1 corresponding branch, merged 0 paths
+define i32 @left(i32 noundef %x) {
+entry:
+  %small = icmp ult i32 %x, 10
+  br i1 %small, label %inner, label %large
+
+inner:
+  %smaller = icmp ult i32 %x, 20
+  br i1 %smaller, label %one, label %two
+
+one:
+  ret i32 1
+
+two:
+  ret i32 2
+
+large:
+  ret i32 3
+}
+
+define i32 @right(i32 noundef %x) {
+entry:
+  %small = icmp ult i32 %x, 10
+  br i1 %small, label %one, label %large
+
+one:
+  ret i32 1
+
+large:
+  ret i32 3
+}
@@ -0,0 +1,39 @@
+; The left branches twice on the same condition and the right once, so a path that
+; goes different ways at the two branches of the left is infeasible.
+; expect: equivalent
+; expect: explored 9 paths, pruned 2 infeasible branch combinations, matched 1 corresponding branch, merged 0 paths
+declare void @f()
+
+declare void @g()
+
+define void @left(i1 noundef %c) {
+entry:
+  br i1 %c, label %first, label %middle
+
+first:
+  call void @f()
+  br label %middle
+
+middle:
+  br i1 %c, label %second, label %exit
+
+second:
+  call void @g()
+  br label %exit
+
+exit:
+  ret void
+}
+
+define void @right(i1 noundef %c) {
+entry:
+  br i1 %c, label %both, label %exit
+
+both:
+  call void @f()
+  call void @g()
+  br label %exit
+
+exit:
+  ret void
+}
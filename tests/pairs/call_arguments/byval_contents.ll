@@ -0,0 +1,17 @@
+; The bytes that a `byval` argument points to are passed along with it.
+; expect: Failed: argument_0
+declare void @foo(ptr byval(i32))
+
+define void @left(i32 noundef %x) {
+  %a = alloca i32
+  store i32 %x, ptr %a
+  call void @foo(ptr byval(i32) %a)
+  ret void
+}
+
+define void @right(i32 noundef %x) {
+  %a = alloca i32
+  store i32 0, ptr %a
+  call void @foo(ptr byval(i32) %a)
+  ret void
+}
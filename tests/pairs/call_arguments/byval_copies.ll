@@ -0,0 +1,19 @@
+; The callee gets its own copy of a `byval` argument, so copies with the same bytes at
+; different places are the same argument.
+; expect: equivalent
+declare void @foo(ptr byval(i32))
+
+define void @left(i32 noundef %x) {
+  %a = alloca i32
+  store i32 %x, ptr %a
+  call void @foo(ptr byval(i32) %a)
+  ret void
+}
+
+define void @right(i32 noundef %x) {
+  %a = alloca i32
+  %b = alloca i32
+  store i32 %x, ptr %b
+  call void @foo(ptr byval(i32) %b)
+  ret void
+}
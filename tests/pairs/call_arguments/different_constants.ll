@@ -0,0 +1,13 @@
+; The calls pass different constants.
+; expect: Failed: argument_0
+declare void @foo(i32)
+
+define void @left(i32 noundef %x) {
+  call void @foo(i32 1)
+  ret void
+}
+
+define void @right(i32 noundef %x) {
+  call void @foo(i32 2)
+  ret void
+}
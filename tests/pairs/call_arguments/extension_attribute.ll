@@ -0,0 +1,13 @@
+; Whether a narrow argument is sign or zero extended is part of how it is passed.
+; expect: Mismatched attributes of argument 0.
+declare void @foo(i8)
+
+define void @left(i8 noundef %x) {
+  call void @foo(i8 zeroext %x)
+  ret void
+}
+
+define void @right(i8 noundef %x) {
+  call void @foo(i8 signext %x)
+  ret void
+}
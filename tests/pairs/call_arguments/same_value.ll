@@ -0,0 +1,16 @@
+; The argument is computed differently but has the same value.
+; expect: equivalent
+declare void @foo(i32)
+
+define void @left(i32 noundef %x) {
+  %y = add i32 %x, %x
+  call void @foo(i32 %y)
+  ret void
+}
+
+define void @right(i32 noundef %x) {
+  %y = add i32 %x, %x
+  %z = sub i32 %y, 0
+  call void @foo(i32 %z)
+  ret void
+}
@@ -0,0 +1,13 @@
+; Only the arguments that differ are reported.
+; expect: Failed: argument_1, argument_2
+declare void @foo(i32, i32, i32)
+
+define void @left(i32 noundef %x, i32 noundef %y) {
+  call void @foo(i32 0, i32 %x, i32 %y)
+  ret void
+}
+
+define void @right(i32 noundef %x, i32 noundef %y) {
+  call void @foo(i32 0, i32 %y, i32 %x)
+  ret void
+}
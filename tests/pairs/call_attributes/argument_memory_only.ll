@@ -0,0 +1,20 @@
+; A call that only accesses memory through its arguments leaves globals alone, as long
+; as it gets no pointer to them.
+; expect: equivalent
+@g = global i32 0
+
+declare void @foo(ptr)
+
+define i32 @left() {
+  %p = alloca i32
+  %x = load i32, ptr @g
+  call void @foo(ptr %p) memory(argmem: readwrite) nounwind
+  ret i32 %x
+}
+
+define i32 @right() {
+  %p = alloca i32
+  call void @foo(ptr %p) memory(argmem: readwrite) nounwind
+  %x = load i32, ptr @g
+  ret i32 %x
+}
@@ -0,0 +1,15 @@
+; A call that may only access the memory of its arguments and gets no pointer accesses
+; no memory, and still has to get the same arguments.
+; expect: Failed: argument_0
+declare i32 @foo(i32)
+
+define i32 @left(i32 noundef %x) {
+  %r = call i32 @foo(i32 %x) memory(argmem: read)
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %x) {
+  %y = add i32 %x, 1
+  %r = call i32 @foo(i32 %y) memory(argmem: read)
+  ret i32 %r
+}
@@ -0,0 +1,16 @@
+; A `nonnull` result need not point to an allocation, like a dangling pointer made up
+; from its alignment, so it may have no provenance and be any address but null.
+; expect: Failed: return
+declare ptr @f()
+
+define i1 @left() {
+  %p = call nonnull ptr @f()
+  %i = ptrtoint ptr %p to i64
+  %r = icmp eq i64 %i, 4
+  ret i1 %r
+}
+
+define i1 @right() {
+  %p = call nonnull ptr @f()
+  ret i1 false
+}
@@ -0,0 +1,20 @@
+; A callee that does not capture the alloca can not reach it from a later call.
+; expect: equivalent
+declare void @f(ptr)
+
+declare void @g()
+
+define void @left(i32 noundef %x) {
+  %a = alloca i32
+  call void @f(ptr nocapture %a)
+  store i32 %x, ptr %a
+  call void @g()
+  ret void
+}
+
+define void @right(i32 noundef %x) {
+  %a = alloca i32
+  call void @f(ptr nocapture %a)
+  call void @g()
+  ret void
+}
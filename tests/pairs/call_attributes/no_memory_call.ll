@@ -0,0 +1,16 @@
+; A call that accesses no memory and does not unwind does not see the store that the
+; right moved after it.
+; expect: equivalent
+declare i32 @foo(i32)
+
+define i32 @left(ptr noundef %p, i32 noundef %x) {
+  store i32 %x, ptr %p
+  %r = call i32 @foo(i32 %x) memory(none) nounwind
+  ret i32 %r
+}
+
+define i32 @right(ptr noundef %p, i32 noundef %x) {
+  %r = call i32 @foo(i32 %x) memory(none) nounwind
+  store i32 %x, ptr %p
+  ret i32 %r
+}
@@ -0,0 +1,14 @@
+; A call that accesses no memory still has to get the same arguments. It is not pure,
+; since it may not return, so the calls are matched as effects.
+; expect: Failed: argument_0
+declare i32 @foo(i32)
+
+define i32 @left() {
+  %r = call i32 @foo(i32 1) memory(none)
+  ret i32 %r
+}
+
+define i32 @right() {
+  %r = call i32 @foo(i32 2) memory(none)
+  ret i32 %r
+}
@@ -0,0 +1,16 @@
+; A `noalias` result points to a new block, which no global is.
+; expect: equivalent
+@g = global i32 0
+
+declare ptr @f()
+
+define i1 @left() {
+  %p = call noalias nonnull ptr @f()
+  %r = icmp eq ptr %p, @g
+  ret i1 %r
+}
+
+define i1 @right() {
+  %p = call noalias nonnull ptr @f()
+  ret i1 false
+}
@@ -0,0 +1,14 @@
+; A `nonnull` result is not null.
+; expect: equivalent
+declare ptr @f()
+
+define i1 @left() {
+  %p = call nonnull ptr @f()
+  %r = icmp eq ptr %p, null
+  ret i1 %r
+}
+
+define i1 @right() {
+  %p = call nonnull ptr @f()
+  ret i1 false
+}
@@ -0,0 +1,13 @@
+; The right says the callee does not unwind, which the left does not promise.
+; expect: Stronger attributes of call in new.
+declare void @foo(ptr)
+
+define void @left(ptr noundef %p) {
+  call void @foo(ptr %p)
+  ret void
+}
+
+define void @right(ptr noundef %p) {
+  call void @foo(ptr %p) nounwind
+  ret void
+}
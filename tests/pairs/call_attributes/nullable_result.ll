@@ -0,0 +1,15 @@
+; Without `nonnull` or `dereferenceable`, a returned pointer may be null, as of a failed
+; allocation.
+; expect: Failed: return
+declare noalias ptr @malloc(i64)
+
+define i1 @left() {
+  %p = call noalias ptr @malloc(i64 4)
+  %r = icmp eq ptr %p, null
+  ret i1 %r
+}
+
+define i1 @right() {
+  %p = call noalias ptr @malloc(i64 4)
+  ret i1 false
+}
@@ -0,0 +1,18 @@
+; A call that only reads memory does not change what the pointer points to, so the load
+; may move across it.
+; expect: equivalent
+declare i32 @foo(ptr)
+
+define i32 @left(ptr noundef %p) {
+  %x = load i32, ptr %p
+  %r = call i32 @foo(ptr %p) memory(read) nounwind
+  %s = add i32 %r, %x
+  ret i32 %s
+}
+
+define i32 @right(ptr noundef %p) {
+  %r = call i32 @foo(ptr %p) memory(read) nounwind
+  %x = load i32, ptr %p
+  %s = add i32 %r, %x
+  ret i32 %s
+}
@@ -0,0 +1,13 @@
+; The right may drop attributes of the call, since it then assumes less.
+; expect: equivalent
+declare i32 @foo(ptr)
+
+define i32 @left(ptr noundef %p) {
+  %r = call i32 @foo(ptr nocapture %p) memory(read) nounwind
+  ret i32 %r
+}
+
+define i32 @right(ptr noundef %p) {
+  %r = call i32 @foo(ptr %p)
+  ret i32 %r
+}
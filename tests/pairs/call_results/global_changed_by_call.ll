@@ -0,0 +1,17 @@
+; An external function may write any global.
+; expect: Failed: return
+@g = global i32 0
+
+declare void @foo()
+
+define i32 @left() {
+  %x = load i32, ptr @g
+  call void @foo()
+  ret i32 %x
+}
+
+define i32 @right() {
+  call void @foo()
+  %x = load i32, ptr @g
+  ret i32 %x
+}
@@ -0,0 +1,17 @@
+; The call may change what the pointer points to.
+; expect: Failed: return
+declare i32 @foo(ptr)
+
+define i32 @left(ptr noundef %p) {
+  %x = load i32, ptr %p
+  %r = call i32 @foo(ptr %p)
+  %s = add i32 %r, %x
+  ret i32 %s
+}
+
+define i32 @right(ptr noundef %p) {
+  %r = call i32 @foo(ptr %p)
+  %x = load i32, ptr %p
+  %s = add i32 %r, %x
+  ret i32 %s
+}
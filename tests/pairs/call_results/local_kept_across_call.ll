@@ -0,0 +1,16 @@
+; A call can not change an alloca that it has no pointer to.
+; expect: equivalent
+declare void @foo()
+
+define i32 @left(i32 noundef %x) {
+  %a = alloca i32
+  store i32 %x, ptr %a
+  call void @foo()
+  %r = load i32, ptr %a
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %x) {
+  call void @foo()
+  ret i32 %x
+}
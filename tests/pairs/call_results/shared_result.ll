@@ -0,0 +1,18 @@
+; Both sides continue with the same result and memory after the call, so loading
+; through the pointer after the call gives the same value on both sides.
+; expect: equivalent
+declare i32 @foo(ptr)
+
+define i32 @left(ptr noundef %p) {
+  %r = call i32 @foo(ptr %p)
+  %x = load i32, ptr %p
+  %s = add i32 %r, %x
+  ret i32 %s
+}
+
+define i32 @right(ptr noundef %p) {
+  %r = call i32 @foo(ptr %p)
+  %x = load i32, ptr %p
+  %s = add i32 %x, %r
+  ret i32 %s
+}
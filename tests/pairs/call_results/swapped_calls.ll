@@ -0,0 +1,17 @@
+; Calls to unknown functions happen in order.
+; expect: Failed: function
+declare void @f()
+
+declare void @g()
+
+define void @left() {
+  call void @f()
+  call void @g()
+  ret void
+}
+
+define void @right() {
+  call void @g()
+  call void @f()
+  ret void
+}
@@ -0,0 +1,41 @@
+; The right branches on the negated condition with its arms swapped, so each way of
+; one branch corresponds to a single way of the other.
+; expect: equivalent
+; expect: explored 7 paths, pruned 0 infeasible branch combinations, matched 1 corresponding branch, merged 0 paths
+declare void @f()
+
+declare void @g()
+
+define void @left(i32 noundef %x, i32 noundef %y) {
+entry:
+  %c = icmp slt i32 %x, %y
+  br i1 %c, label %then, label %else
+
+then:
+  call void @f()
+  br label %exit
+
+else:
+  call void @g()
+  br label %exit
+
+exit:
+  ret void
+}
+
+define void @right(i32 noundef %x, i32 noundef %y) {
+entry:
+  %c = icmp sge i32 %x, %y
+  br i1 %c, label %then, label %else
+
+then:
+  call void @g()
+  br label %exit
+
+else:
+  call void @f()
+  br label %exit
+
+exit:
+  ret void
+}
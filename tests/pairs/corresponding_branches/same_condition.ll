@@ -0,0 +1,40 @@
+; The right compares the other way around, which is the same condition.
+; expect: equivalent
+; expect: explored 7 paths, pruned 0 infeasible branch combinations, matched 1 corresponding branch, merged 0 paths
+declare void @f()
+
+declare void @g()
+
+define void @left(i32 noundef %x, i32 noundef %y) {
+entry:
+  %c = icmp slt i32 %x, %y
+  br i1 %c, label %then, label %else
+
+then:
+  call void @f()
+  br label %exit
+
+else:
+  call void @g()
+  br label %exit
+
+exit:
+  ret void
+}
+
+define void @right(i32 noundef %x, i32 noundef %y) {
+entry:
+  %c = icmp sgt i32 %y, %x
+  br i1 %c, label %then, label %else
+
+then:
+  call void @f()
+  br label %exit
+
+else:
+  call void @g()
+  br label %exit
+
+exit:
+  ret void
+}
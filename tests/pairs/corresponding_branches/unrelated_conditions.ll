@@ -0,0 +1,27 @@
+; The conditions are unrelated, so all four combinations of the branches are explored,
+; and each returns the same value.
+; expect: equivalent
+; expect: explored 5 paths, pruned 0 infeasible branch combinations, matched 0 corresponding branches, merged 0 paths
+define i32 @left(i1 noundef %c, i1 noundef %d, i32 noundef %x) {
+entry:
+  br i1 %c, label %then, label %else
+
+then:
+  ret i32 %x
+
+else:
+  %y = add i32 %x, 0
+  ret i32 %y
+}
+
+define i32 @right(i1 noundef %c, i1 noundef %d, i32 noundef %x) {
+entry:
+  br i1 %d, label %then, label %else
+
+then:
+  ret i32 %x
+
+else:
+  %y = sub i32 %x, 0
+  ret i32 %y
+}
@@ -0,0 +1,39 @@
+; The negation is wrong when x and y are equal.
+; expect: Failed: function
+declare void @f()
+
+declare void @g()
+
+define void @left(i32 noundef %x, i32 noundef %y) {
+entry:
+  %c = icmp slt i32 %x, %y
+  br i1 %c, label %then, label %else
+
+then:
+  call void @f()
+  br label %exit
+
+else:
+  call void @g()
+  br label %exit
+
+exit:
+  ret void
+}
+
+define void @right(i32 noundef %x, i32 noundef %y) {
+entry:
+  %c = icmp sgt i32 %x, %y
+  br i1 %c, label %then, label %else
+
+then:
+  call void @g()
+  br label %exit
+
+else:
+  call void @f()
+  br label %exit
+
+exit:
+  ret void
+}
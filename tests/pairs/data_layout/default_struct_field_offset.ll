@@ -0,0 +1,16 @@
+; Without a data layout `i64` is aligned to 4 bytes only, so the second field of the
+; struct starts at offset 4 and the load at offset 8 reads memory that is partly
+; uninitialized.
+; expect: Failed: return
+define i64 @left(i64 noundef %x) {
+  %s = alloca { i8, i64, i32 }
+  %field = getelementptr { i8, i64, i32 }, ptr %s, i64 0, i32 1
+  store i64 %x, ptr %field
+  %p = getelementptr i8, ptr %s, i64 8
+  %r = load i64, ptr %p
+  ret i64 %r
+}
+
+define i64 @right(i64 noundef %x) {
+  ret i64 %x
+}
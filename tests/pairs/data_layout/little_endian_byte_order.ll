@@ -0,0 +1,16 @@
+; On a little endian target, the first byte of a word is its least significant one.
+; expect: Failed: return
+target datalayout = "e"
+
+define i32 @left(i32 noundef %x) {
+  %p = alloca i32
+  store i32 %x, ptr %p
+  store i8 0, ptr %p
+  %r = load i32, ptr %p
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %x) {
+  %r = and i32 %x, 16777215
+  ret i32 %r
+}
@@ -0,0 +1,17 @@
+; The data layout aligns `i64` to 8 bytes, so the second field of the struct starts at
+; offset 8.
+; expect: equivalent
+target datalayout = "e-i64:64"
+
+define i64 @left(i64 noundef %x) {
+  %s = alloca { i8, i64 }
+  %field = getelementptr { i8, i64 }, ptr %s, i64 0, i32 1
+  store i64 %x, ptr %field
+  %p = getelementptr i8, ptr %s, i64 8
+  %r = load i64, ptr %p
+  ret i64 %r
+}
+
+define i64 @right(i64 noundef %x) {
+  ret i64 %x
+}
@@ -0,0 +1,29 @@
+; The right panics for an argument where the left returns.
+; expect: Diverging call to _ZN4core9panicking5panic17h0123456789abcdefE happened in new
+declare void @_ZN4core9panicking5panic17h0123456789abcdefE() noreturn
+
+define i32 @left(i32 noundef %x) {
+entry:
+  %small = icmp ult i32 %x, 10
+  br i1 %small, label %panic, label %done
+
+panic:
+  call void @_ZN4core9panicking5panic17h0123456789abcdefE()
+  unreachable
+
+done:
+  ret i32 %x
+}
+
+define i32 @right(i32 noundef %x) {
+entry:
+  %small = icmp ule i32 %x, 10
+  br i1 %small, label %panic, label %done
+
+panic:
+  call void @_ZN4core9panicking5panic17h0123456789abcdefE()
+  unreachable
+
+done:
+  ret i32 %x
+}
@@ -0,0 +1,39 @@
+; flags: --diverging=mycrate::fail*
+; A function of the crate is made diverging by a pattern, so the two calls of the left
+; may be merged.
+; expect: equivalent
+declare void @_ZN7mycrate9fail_fast17h0123456789abcdefE()
+
+define i32 @left(i32 noundef %x) {
+entry:
+  %small = icmp ult i32 %x, 10
+  br i1 %small, label %fail1, label %next
+
+fail1:
+  call void @_ZN7mycrate9fail_fast17h0123456789abcdefE()
+  unreachable
+
+next:
+  %medium = icmp ult i32 %x, 20
+  br i1 %medium, label %fail2, label %done
+
+fail2:
+  call void @_ZN7mycrate9fail_fast17h0123456789abcdefE()
+  unreachable
+
+done:
+  ret i32 %x
+}
+
+define i32 @right(i32 noundef %x) {
+entry:
+  %medium = icmp ult i32 %x, 20
+  br i1 %medium, label %fail, label %done
+
+fail:
+  call void @_ZN7mycrate9fail_fast17h0123456789abcdefE()
+  unreachable
+
+done:
+  ret i32 %x
+}
@@ -0,0 +1,32 @@
+; A panic unwinds and reports where it happened, an abort does not, so one may not
+; replace the other.
+; expect: Diverging call to _ZN4core9panicking5panic17h0123456789abcdefE replaced by abort
+declare void @_ZN4core9panicking5panic17h0123456789abcdefE() noreturn
+
+declare void @abort() noreturn
+
+define i32 @left(i32 noundef %x) {
+entry:
+  %small = icmp ult i32 %x, 10
+  br i1 %small, label %panic, label %done
+
+panic:
+  call void @_ZN4core9panicking5panic17h0123456789abcdefE()
+  unreachable
+
+done:
+  ret i32 %x
+}
+
+define i32 @right(i32 noundef %x) {
+entry:
+  %small = icmp ult i32 %x, 10
+  br i1 %small, label %panic, label %done
+
+panic:
+  call void @abort()
+  unreachable
+
+done:
+  ret i32 %x
+}
@@ -0,0 +1,18 @@
+; A store to a global right before a panic is observable by whoever catches it, so it
+; can't be dropped.
+; expect: Diverging with different memory
+; expect: Failed: final_memory
+@counter = global i32 0
+
+declare void @_ZN4core9panicking5panic17h0123456789abcdefE() noreturn
+
+define void @left() {
+  store i32 1, ptr @counter
+  call void @_ZN4core9panicking5panic17h0123456789abcdefE()
+  unreachable
+}
+
+define void @right() {
+  call void @_ZN4core9panicking5panic17h0123456789abcdefE()
+  unreachable
+}
@@ -0,0 +1,37 @@
+; The two panics of the left are merged into one on the right.
+; expect: equivalent
+declare void @_ZN4core9panicking5panic17h0123456789abcdefE() noreturn
+
+define i32 @left(i32 noundef %x) {
+entry:
+  %small = icmp ult i32 %x, 10
+  br i1 %small, label %panic1, label %next
+
+panic1:
+  call void @_ZN4core9panicking5panic17h0123456789abcdefE()
+  unreachable
+
+next:
+  %medium = icmp ult i32 %x, 20
+  br i1 %medium, label %panic2, label %done
+
+panic2:
+  call void @_ZN4core9panicking5panic17h0123456789abcdefE()
+  unreachable
+
+done:
+  ret i32 %x
+}
+
+define i32 @right(i32 noundef %x) {
+entry:
+  %medium = icmp ult i32 %x, 20
+  br i1 %medium, label %panic, label %done
+
+panic:
+  call void @_ZN4core9panicking5panic17h0123456789abcdefE()
+  unreachable
+
+done:
+  ret i32 %x
+}
@@ -0,0 +1,29 @@
+; The panic reports the index it is called with, so the index must be the same.
+; expect: Failed: argument_0
+declare void @_ZN4core9panicking18panic_bounds_check17h0123456789abcdefE(i64, i64) noreturn
+
+define i32 @left(i64 noundef %i) {
+entry:
+  %small = icmp ult i64 %i, 10
+  br i1 %small, label %done, label %panic
+
+panic:
+  call void @_ZN4core9panicking18panic_bounds_check17h0123456789abcdefE(i64 %i, i64 10)
+  unreachable
+
+done:
+  ret i32 0
+}
+
+define i32 @right(i64 noundef %i) {
+entry:
+  %small = icmp ult i64 %i, 10
+  br i1 %small, label %done, label %panic
+
+panic:
+  call void @_ZN4core9panicking18panic_bounds_check17h0123456789abcdefE(i64 10, i64 10)
+  unreachable
+
+done:
+  ret i32 0
+}
@@ -0,0 +1,34 @@
+; The store is sunk into both successors, including the one that panics.
+; expect: equivalent
+@counter = global i32 0
+
+declare void @_ZN4core9panicking5panic17h0123456789abcdefE() noreturn
+
+define i32 @left(i32 noundef %x) {
+entry:
+  store i32 %x, ptr @counter
+  %small = icmp ult i32 %x, 10
+  br i1 %small, label %panic, label %done
+
+panic:
+  call void @_ZN4core9panicking5panic17h0123456789abcdefE()
+  unreachable
+
+done:
+  ret i32 %x
+}
+
+define i32 @right(i32 noundef %x) {
+entry:
+  %small = icmp ult i32 %x, 10
+  br i1 %small, label %panic, label %done
+
+panic:
+  store i32 %x, ptr @counter
+  call void @_ZN4core9panicking5panic17h0123456789abcdefE()
+  unreachable
+
+done:
+  store i32 %x, ptr @counter
+  ret i32 %x
+}
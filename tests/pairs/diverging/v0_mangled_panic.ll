@@ -0,0 +1,29 @@
+; Panics are recognized by their path in symbols of the v0 mangling scheme too.
+; expect: Diverging call to _RNvNtCs1234_4core9panicking18panic_bounds_check happened in new
+declare void @_RNvNtCs1234_4core9panicking18panic_bounds_check(i64, i64, ptr)
+
+define i32 @left(i64 noundef %i) {
+entry:
+  %ok = icmp ult i64 %i, 4
+  br i1 %ok, label %done, label %panic
+
+panic:
+  call void @_RNvNtCs1234_4core9panicking18panic_bounds_check(i64 %i, i64 4, ptr null)
+  unreachable
+
+done:
+  ret i32 0
+}
+
+define i32 @right(i64 noundef %i) {
+entry:
+  %ok = icmp ult i64 %i, 3
+  br i1 %ok, label %done, label %panic
+
+panic:
+  call void @_RNvNtCs1234_4core9panicking18panic_bounds_check(i64 %i, i64 4, ptr null)
+  unreachable
+
+done:
+  ret i32 0
+}
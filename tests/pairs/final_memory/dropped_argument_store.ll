@@ -0,0 +1,10 @@
+; The store through the pointer argument is visible to the caller.
+; expect: Failed: final_memory
+define void @left(ptr noundef %p, i32 noundef %x) {
+  store i32 %x, ptr %p
+  ret void
+}
+
+define void @right(ptr noundef %p, i32 noundef %x) {
+  ret void
+}
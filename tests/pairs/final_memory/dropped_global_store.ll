@@ -0,0 +1,12 @@
+; Globals outlive the function.
+; expect: Failed: final_memory
+@g = global i32 0
+
+define void @left(i32 noundef %x) {
+  store i32 %x, ptr @g
+  ret void
+}
+
+define void @right(i32 noundef %x) {
+  ret void
+}
@@ -0,0 +1,20 @@
+; The alloca that escapes comes second in the old function, after one that is only
+; stored to, and first in the new one, which removes the other.
+; expect: equivalent
+declare void @f(ptr)
+
+define void @left(i32 noundef %x) {
+  %unused = alloca i64
+  store i64 0, ptr %unused
+  %a = alloca i32
+  store i32 %x, ptr %a
+  call void @f(ptr %a)
+  ret void
+}
+
+define void @right(i32 noundef %x) {
+  %a = alloca i32
+  store i32 %x, ptr %a
+  call void @f(ptr %a)
+  ret void
+}
@@ -0,0 +1,21 @@
+; Once an alloca was passed to a call, a later call may read it through the pointer
+; that the first one kept.
+; expect: Failed: memory_before_call
+declare void @f(ptr)
+
+declare void @g()
+
+define void @left(i32 noundef %x) {
+  %a = alloca i32
+  call void @f(ptr %a)
+  store i32 %x, ptr %a
+  call void @g()
+  ret void
+}
+
+define void @right(i32 noundef %x) {
+  %a = alloca i32
+  call void @f(ptr %a)
+  call void @g()
+  ret void
+}
@@ -0,0 +1,29 @@
+; The alloca escapes through a chain of stores whose links are made after its end was
+; already reachable, so the call may read it.
+; expect: Failed: memory_before_call
+@g = global ptr null
+
+declare void @f()
+
+define void @left(i32 noundef %x) {
+  %a = alloca i32
+  %b = alloca ptr
+  %c = alloca ptr
+  store ptr %c, ptr @g
+  store ptr %b, ptr %c
+  store ptr %a, ptr %b
+  store i32 %x, ptr %a
+  call void @f()
+  ret void
+}
+
+define void @right(i32 noundef %x) {
+  %a = alloca i32
+  %b = alloca ptr
+  %c = alloca ptr
+  store ptr %c, ptr @g
+  store ptr %b, ptr %c
+  store ptr %a, ptr %b
+  call void @f()
+  ret void
+}
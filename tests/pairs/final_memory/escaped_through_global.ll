@@ -0,0 +1,20 @@
+; Storing the address of an alloca in a global lets any call reach it.
+; expect: Failed: memory_before_call
+@g = global ptr null
+
+declare void @f()
+
+define void @left(i32 noundef %x) {
+  %a = alloca i32
+  store ptr %a, ptr @g
+  store i32 %x, ptr %a
+  call void @f()
+  ret void
+}
+
+define void @right(i32 noundef %x) {
+  %a = alloca i32
+  store ptr %a, ptr @g
+  call void @f()
+  ret void
+}
@@ -0,0 +1,16 @@
+; A call can not see an alloca whose address was never given away.
+; expect: equivalent
+declare void @f()
+
+define void @left(i32 noundef %x) {
+  %a = alloca i32
+  store i32 %x, ptr %a
+  call void @f()
+  ret void
+}
+
+define void @right(i32 noundef %x) {
+  %a = alloca i32
+  call void @f()
+  ret void
+}
@@ -0,0 +1,15 @@
+; Stores that are overwritten before returning do not matter, and neither do stores to
+; a local allocation that does not escape.
+; expect: equivalent
+define void @left(ptr noundef %p, i32 noundef %x) {
+  %a = alloca i32
+  store i32 %x, ptr %a
+  store i32 0, ptr %p
+  store i32 %x, ptr %p
+  ret void
+}
+
+define void @right(ptr noundef %p, i32 noundef %x) {
+  store i32 %x, ptr %p
+  ret void
+}
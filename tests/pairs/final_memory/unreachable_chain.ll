@@ -0,0 +1,27 @@
+; The allocas only point to each other, in a cycle that nothing outside the function
+; can reach, so the call can not read the dropped store.
+; expect: equivalent
+declare void @f()
+
+define void @left(i32 noundef %x) {
+  %a = alloca ptr
+  %b = alloca ptr
+  %c = alloca ptr
+  store ptr %b, ptr %a
+  store ptr %c, ptr %b
+  store ptr %a, ptr %c
+  store i32 %x, ptr %a
+  call void @f()
+  ret void
+}
+
+define void @right(i32 noundef %x) {
+  %a = alloca ptr
+  %b = alloca ptr
+  %c = alloca ptr
+  store ptr %b, ptr %a
+  store ptr %c, ptr %b
+  store ptr %a, ptr %c
+  call void @f()
+  ret void
+}
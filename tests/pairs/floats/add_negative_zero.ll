@@ -0,0 +1,13 @@
+; flags: --fast-math=nnan
+; Adding a negative zero changes no number, not even a negative zero.
+; expect: equivalent
+define float @left(float noundef %x) {
+entry:
+  %r = fadd nnan float %x, -0.0
+  ret float %r
+}
+
+define float @right(float noundef %x) {
+entry:
+  ret float %x
+}
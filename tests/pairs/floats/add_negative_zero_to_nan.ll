@@ -0,0 +1,13 @@
+; A NaN computed by the left is the one NaN the solver gives, while the right returns
+; the payload of its argument.
+; expect: Failed: return
+define float @left(float noundef %x) {
+entry:
+  %r = fadd float %x, -0.0
+  ret float %r
+}
+
+define float @right(float noundef %x) {
+entry:
+  ret float %x
+}
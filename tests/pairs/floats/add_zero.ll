@@ -0,0 +1,12 @@
+; Without the flags a negative zero tells the sides apart.
+; expect: Failed: return
+define float @left(float noundef %x) {
+entry:
+  %r = fadd float %x, 0.0
+  ret float %r
+}
+
+define float @right(float noundef %x) {
+entry:
+  ret float %x
+}
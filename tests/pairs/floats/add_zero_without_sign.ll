@@ -0,0 +1,13 @@
+; flags: --fast-math=nnan,nsz
+; Adding zero only changes the sign of a negative zero, which nsz ignores.
+; expect: equivalent
+define float @left(float noundef %x) {
+entry:
+  %r = fadd nnan nsz float %x, 0.0
+  ret float %r
+}
+
+define float @right(float noundef %x) {
+entry:
+  ret float %x
+}
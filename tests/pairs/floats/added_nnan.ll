@@ -0,0 +1,14 @@
+; flags: --fast-math=nnan
+; nnan makes the NaN results of the new function poison, where the old one has NaN.
+; expect: Failed: return
+define float @left(float noundef %a, float noundef %b) {
+entry:
+  %r = fadd float %a, %b
+  ret float %r
+}
+
+define float @right(float noundef %a, float noundef %b) {
+entry:
+  %r = fadd nnan float %a, %b
+  ret float %r
+}
@@ -0,0 +1,13 @@
+; Multiplying by two is exact, so it is the same as adding the value to itself.
+; expect: equivalent
+define double @left(double noundef %x) {
+entry:
+  %r = fmul double %x, 2.0
+  ret double %r
+}
+
+define double @right(double noundef %x) {
+entry:
+  %r = fadd double %x, %x
+  ret double %r
+}
@@ -0,0 +1,13 @@
+; A double holds every i32, so converting there and back gives the value back.
+; expect: equivalent
+define i32 @left(i32 noundef %x) {
+entry:
+  %f = sitofp i32 %x to double
+  %r = fptosi double %f to i32
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %x) {
+entry:
+  ret i32 %x
+}
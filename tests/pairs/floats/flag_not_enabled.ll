@@ -0,0 +1,13 @@
+; The flags of instructions must be enabled, so that they are modeled.
+; expect: fast-math flag ninf of `%r = fmul ninf float %a, %b` is not enabled, see --fast-math
+define float @left(float noundef %a, float noundef %b) {
+entry:
+  %r = fmul ninf float %a, %b
+  ret float %r
+}
+
+define float @right(float noundef %a, float noundef %b) {
+entry:
+  %r = fmul ninf float %a, %b
+  ret float %r
+}
@@ -0,0 +1,13 @@
+; 64 does not fit in an i7, though it does in the byte holding it, so the conversion is
+; poison.
+; expect: Failed: return
+define i7 @left() {
+entry:
+  ret i7 -64
+}
+
+define i7 @right() {
+entry:
+  %r = fptosi float 6.400000e+01 to i7
+  ret i7 %r
+}
@@ -0,0 +1,13 @@
+; A float does not hold every i32, so converting there and back loses the low bits.
+; expect: Failed: return
+define i32 @left(i32 noundef %x) {
+entry:
+  %f = sitofp i32 %x to float
+  %r = fptosi float %f to i32
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %x) {
+entry:
+  ret i32 %x
+}
@@ -0,0 +1,16 @@
+; flags: --fast-math=nnan
+; Only the instruction with nnan is poison for NaN, so dropping the flag is fine.
+; expect: equivalent
+define float @left(float noundef %a, float noundef %b) {
+entry:
+  %r = fadd nnan float %a, %b
+  %s = fadd float %r, %b
+  ret float %s
+}
+
+define float @right(float noundef %a, float noundef %b) {
+entry:
+  %r = fadd float %a, %b
+  %s = fadd float %r, %b
+  ret float %s
+}
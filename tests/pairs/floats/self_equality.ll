@@ -0,0 +1,13 @@
+; A value only differs from itself when it is NaN, which is what `ord` tells.
+; expect: equivalent
+define i1 @left(float noundef %x) {
+entry:
+  %r = fcmp oeq float %x, %x
+  ret i1 %r
+}
+
+define i1 @right(float noundef %x) {
+entry:
+  %r = fcmp ord float %x, 0.0
+  ret i1 %r
+}
@@ -0,0 +1,12 @@
+; A NaN does not equal itself.
+; expect: Failed: return
+define i1 @left(float noundef %x) {
+entry:
+  %r = fcmp oeq float %x, %x
+  ret i1 %r
+}
+
+define i1 @right(float noundef %x) {
+entry:
+  ret i1 true
+}
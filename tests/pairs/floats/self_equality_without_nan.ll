@@ -0,0 +1,13 @@
+; flags: --fast-math=nnan
+; Without NaNs a value always equals itself.
+; expect: equivalent
+define i1 @left(float noundef %x) {
+entry:
+  %r = fcmp nnan oeq float %x, %x
+  ret i1 %r
+}
+
+define i1 @right(float noundef %x) {
+entry:
+  ret i1 true
+}
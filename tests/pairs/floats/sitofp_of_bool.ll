@@ -0,0 +1,12 @@
+; As a signed integer, an i1 that is true is -1.
+; expect: equivalent
+define float @left() {
+entry:
+  %r = sitofp i1 true to float
+  ret float %r
+}
+
+define float @right() {
+entry:
+  ret float -1.000000e+00
+}
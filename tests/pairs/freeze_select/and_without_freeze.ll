@@ -0,0 +1,13 @@
+; Without the freeze a poison x makes the and poison even when c does not hold.
+; expect: Failed: return
+define i1 @left(i1 noundef %c, i1 %x) {
+entry:
+  %r = select i1 %c, i1 %x, i1 false
+  ret i1 %r
+}
+
+define i1 @right(i1 noundef %c, i1 %x) {
+entry:
+  %r = and i1 %c, %x
+  ret i1 %r
+}
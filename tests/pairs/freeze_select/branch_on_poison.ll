@@ -0,0 +1,19 @@
+; Branching on poison is undefined behavior, so the right may go either way then.
+; expect: equivalent
+define i32 @left(i32 noundef %x) {
+entry:
+  %y = add nsw i32 %x, 1
+  %c = icmp sgt i32 %y, %x
+  br i1 %c, label %then, label %else
+
+then:
+  ret i32 1
+
+else:
+  ret i32 0
+}
+
+define i32 @right(i32 noundef %x) {
+entry:
+  ret i32 1
+}
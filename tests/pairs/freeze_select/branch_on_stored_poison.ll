@@ -0,0 +1,33 @@
+; Storing poison does not freeze it, so the new function branches on poison when it
+; loads the value back.
+; expect: Branch on poison in new
+define i1 @left(i32 %x) {
+entry:
+  %p = alloca i32
+  %f = freeze i32 %x
+  store i32 %f, ptr %p
+  %v = load i32, ptr %p
+  %c = icmp eq i32 %v, 0
+  br i1 %c, label %zero, label %other
+
+zero:
+  ret i1 true
+
+other:
+  ret i1 false
+}
+
+define i1 @right(i32 %x) {
+entry:
+  %p = alloca i32
+  store i32 %x, ptr %p
+  %v = load i32, ptr %p
+  %c = icmp eq i32 %v, 0
+  br i1 %c, label %zero, label %other
+
+zero:
+  ret i1 true
+
+other:
+  ret i1 false
+}
@@ -0,0 +1,14 @@
+; The select only uses x when c does not hold, so x must be frozen to use it in an or.
+; expect: equivalent
+define i1 @left(i1 noundef %c, i1 %x) {
+entry:
+  %r = select i1 %c, i1 true, i1 %x
+  ret i1 %r
+}
+
+define i1 @right(i1 noundef %c, i1 %x) {
+entry:
+  %f = freeze i1 %x
+  %r = or i1 %c, %f
+  ret i1 %r
+}
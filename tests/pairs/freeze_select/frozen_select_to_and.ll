@@ -0,0 +1,16 @@
+; The old function already freezes x, so the select may become an and of the same
+; frozen value.
+; expect: equivalent
+define i1 @left(i1 noundef %c, i1 %x) {
+entry:
+  %f = freeze i1 %x
+  %r = select i1 %c, i1 %f, i1 false
+  ret i1 %r
+}
+
+define i1 @right(i1 noundef %c, i1 %x) {
+entry:
+  %f = freeze i1 %x
+  %r = and i1 %c, %f
+  ret i1 %r
+}
@@ -0,0 +1,16 @@
+; Each use of a frozen value sees the same value, so it can not be frozen twice.
+; expect: Failed: return
+define i32 @left(i32 %x) {
+entry:
+  %f = freeze i32 %x
+  %r = sub i32 %f, %f
+  ret i32 %r
+}
+
+define i32 @right(i32 %x) {
+entry:
+  %f = freeze i32 %x
+  %g = freeze i32 %x
+  %r = sub i32 %f, %g
+  ret i32 %r
+}
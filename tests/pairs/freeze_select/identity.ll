@@ -0,0 +1,13 @@
+; A freeze that is kept picks the same value on both sides, whatever that is.
+; expect: equivalent
+define i32 @left(i32 %x) {
+entry:
+  %f = freeze i32 %x
+  ret i32 %f
+}
+
+define i32 @right(i32 %x) {
+entry:
+  %f = freeze i32 %x
+  ret i32 %f
+}
@@ -0,0 +1,19 @@
+; The right drops a branch after a freeze, whose value both sides return.
+; expect: equivalent
+define i32 @left(i1 noundef %c, i32 %x) {
+entry:
+  %f = freeze i32 %x
+  br i1 %c, label %then, label %else
+
+then:
+  ret i32 %f
+
+else:
+  ret i32 %f
+}
+
+define i32 @right(i1 noundef %c, i32 %x) {
+entry:
+  %f = freeze i32 %x
+  ret i32 %f
+}
@@ -0,0 +1,21 @@
+; A memset of a poison byte fills memory with poison, so the new function returns
+; poison where the old one returns a frozen value.
+; expect: Failed: return
+declare void @llvm.memset.p0.i64(ptr, i8, i64, i1)
+
+define i8 @left(i8 %x) {
+entry:
+  %p = alloca i8
+  %f = freeze i8 %x
+  call void @llvm.memset.p0.i64(ptr %p, i8 %f, i64 1, i1 false)
+  %v = load i8, ptr %p
+  ret i8 %v
+}
+
+define i8 @right(i8 %x) {
+entry:
+  %p = alloca i8
+  call void @llvm.memset.p0.i64(ptr %p, i8 %x, i64 1, i1 false)
+  %v = load i8, ptr %p
+  ret i8 %v
+}
@@ -0,0 +1,13 @@
+; Without the freeze a poison x makes the or poison even when c holds.
+; expect: Failed: return
+define i1 @left(i1 noundef %c, i1 %x) {
+entry:
+  %r = select i1 %c, i1 true, i1 %x
+  ret i1 %r
+}
+
+define i1 @right(i1 noundef %c, i1 %x) {
+entry:
+  %r = or i1 %c, %x
+  ret i1 %r
+}
@@ -0,0 +1,13 @@
+; The left is poison when the addition overflows, so the right may always return true.
+; expect: equivalent
+define i1 @left(i32 noundef %x) {
+entry:
+  %y = add nsw i32 %x, 1
+  %r = icmp sgt i32 %y, %x
+  ret i1 %r
+}
+
+define i1 @right(i32 noundef %x) {
+entry:
+  ret i1 true
+}
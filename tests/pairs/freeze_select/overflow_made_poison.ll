@@ -0,0 +1,13 @@
+; The right adds the nsw flag, which makes the overflowing sum poison.
+; expect: Failed: return
+define i32 @left(i32 noundef %x) {
+entry:
+  %y = add i32 %x, 1
+  ret i32 %y
+}
+
+define i32 @right(i32 noundef %x) {
+entry:
+  %y = add nsw i32 %x, 1
+  ret i32 %y
+}
@@ -0,0 +1,15 @@
+; Memory keeps poison, so storing a value that may be poison is the same on both sides.
+; expect: equivalent
+@g = global i32 0
+
+define void @left(i32 %x) {
+entry:
+  store i32 %x, ptr @g
+  ret void
+}
+
+define void @right(i32 %x) {
+entry:
+  store i32 %x, ptr @g
+  ret void
+}
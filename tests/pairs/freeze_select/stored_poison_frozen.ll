@@ -0,0 +1,16 @@
+; Poison that the old function leaves in memory may be replaced by any value.
+; expect: equivalent
+@g = global i32 0
+
+define void @left(i32 %x) {
+entry:
+  store i32 %x, ptr @g
+  ret void
+}
+
+define void @right(i32 %x) {
+entry:
+  %f = freeze i32 %x
+  store i32 %f, ptr @g
+  ret void
+}
@@ -0,0 +1,13 @@
+; A poison operand that the select does not choose does not make it poison.
+; expect: equivalent
+define i32 @left(i1 noundef %c, i32 noundef %x, i32 %y) {
+entry:
+  %r = select i1 %c, i32 %x, i32 %y
+  %s = select i1 true, i32 %x, i32 %r
+  ret i32 %s
+}
+
+define i32 @right(i1 noundef %c, i32 noundef %x, i32 %y) {
+entry:
+  ret i32 %x
+}
@@ -0,0 +1,21 @@
+; flags: --inline-depth=1
+; An inlined function stores through the pointer it was given, and has allocas of its
+; own.
+; expect: equivalent
+define internal void @set(ptr %p, i32 %x) {
+  %tmp = alloca i32
+  store i32 %x, ptr %tmp
+  %y = load i32, ptr %tmp
+  store i32 %y, ptr %p
+  ret void
+}
+
+define void @left(ptr noundef %p, i32 noundef %x) {
+  call void @set(ptr %p, i32 %x)
+  ret void
+}
+
+define void @right(ptr noundef %p, i32 noundef %x) {
+  store i32 %x, ptr %p
+  ret void
+}
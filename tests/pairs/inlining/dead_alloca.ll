@@ -0,0 +1,19 @@
+; flags: --inline-depth=1
+; The allocas of an inlined call are dead once it returns, and loading from them is
+; undefined behavior.
+; expect: equivalent
+define internal ptr @local() {
+  %a = alloca i32
+  store i32 1, ptr %a
+  ret ptr %a
+}
+
+define i32 @left() {
+  %p = call ptr @local()
+  %r = load i32, ptr %p
+  ret i32 %r
+}
+
+define i32 @right() {
+  ret i32 7
+}
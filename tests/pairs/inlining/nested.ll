@@ -0,0 +1,23 @@
+; flags: --inline-depth=2
+; Both levels of calls are inlined.
+; expect: equivalent
+define internal i32 @increment(i32 %x) {
+  %r = add i32 %x, 1
+  ret i32 %r
+}
+
+define internal i32 @add_two(i32 %x) {
+  %y = call i32 @increment(i32 %x)
+  %r = call i32 @increment(i32 %y)
+  ret i32 %r
+}
+
+define i32 @left(i32 noundef %x) {
+  %r = call i32 @add_two(i32 %x)
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %x) {
+  %r = add i32 %x, 2
+  ret i32 %r
+}
@@ -0,0 +1,16 @@
+; Without a depth, calls to functions of the module are external calls too.
+; expect: Call missed in new
+define internal i32 @increment(i32 %x) {
+  %r = add i32 %x, 1
+  ret i32 %r
+}
+
+define i32 @left(i32 noundef %x) {
+  %r = call i32 @increment(i32 %x)
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %x) {
+  %r = add i32 %x, 1
+  ret i32 %r
+}
@@ -0,0 +1,17 @@
+; flags: --inline-depth=1
+; The call is inlined on one side only.
+; expect: equivalent
+define internal i32 @increment(i32 %x) {
+  %r = add i32 %x, 1
+  ret i32 %r
+}
+
+define i32 @left(i32 noundef %x) {
+  %r = call i32 @increment(i32 %x)
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %x) {
+  %r = add i32 %x, 1
+  ret i32 %r
+}
@@ -0,0 +1,13 @@
+; Multiplying by a power of two is shifting.
+; expect: equivalent
+define i32 @left(i32 noundef %x) {
+entry:
+  %r = mul i32 %x, 8
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %x) {
+entry:
+  %r = shl i32 %x, 3
+  ret i32 %r
+}
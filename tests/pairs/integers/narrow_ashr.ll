@@ -0,0 +1,15 @@
+; An arithmetic shift of an i7 fills in its own sign bit, not that of the byte holding it.
+; expect: equivalent
+define i7 @left(i7 noundef %x) {
+entry:
+  %wide = sext i7 %x to i32
+  %s = ashr i32 %wide, 6
+  %r = trunc i32 %s to i7
+  ret i7 %r
+}
+
+define i7 @right(i7 noundef %x) {
+entry:
+  %r = ashr i7 %x, 6
+  ret i7 %r
+}
@@ -0,0 +1,13 @@
+; Extending the sign of true gives all ones.
+; expect: equivalent
+define i8 @left(i1 noundef %c) {
+entry:
+  %r = sext i1 %c to i8
+  ret i8 %r
+}
+
+define i8 @right(i1 noundef %c) {
+entry:
+  %r = select i1 %c, i8 -1, i8 0
+  ret i8 %r
+}
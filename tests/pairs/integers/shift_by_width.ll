@@ -0,0 +1,15 @@
+; A shift by the width of the type is poison, not zero.
+; expect: Failed: return
+define i32 @left(i32 noundef %x, i32 noundef %n) {
+entry:
+  %small = icmp ult i32 %n, 32
+  %s = lshr i32 %x, %n
+  %r = select i1 %small, i32 %s, i32 0
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %x, i32 noundef %n) {
+entry:
+  %r = lshr i32 %x, %n
+  ret i32 %r
+}
@@ -0,0 +1,13 @@
+; The right adds the nuw flag, which makes the shift poison when bits are shifted out.
+; expect: Failed: return
+define i32 @left(i32 noundef %x) {
+entry:
+  %r = shl i32 %x, 3
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %x) {
+entry:
+  %r = shl nuw i32 %x, 3
+  ret i32 %r
+}
@@ -0,0 +1,12 @@
+; As a signed integer, an i1 that is true is -1, which is less than 0.
+; expect: equivalent
+define i1 @left() {
+entry:
+  %r = icmp slt i1 true, false
+  ret i1 %r
+}
+
+define i1 @right() {
+entry:
+  ret i1 true
+}
@@ -0,0 +1,13 @@
+; Truncating a zero extended value gives it back.
+; expect: equivalent
+define i7 @left(i7 noundef %x) {
+entry:
+  %wide = zext i7 %x to i64
+  %r = trunc i64 %wide to i7
+  ret i7 %r
+}
+
+define i7 @right(i7 noundef %x) {
+entry:
+  ret i7 %x
+}
@@ -0,0 +1,13 @@
+; Flipping all bits is subtracting from -1.
+; expect: equivalent
+define i32 @left(i32 noundef %x) {
+entry:
+  %r = xor i32 %x, -1
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %x) {
+entry:
+  %r = sub i32 -1, %x
+  ret i32 %r
+}
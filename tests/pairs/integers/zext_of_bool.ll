@@ -0,0 +1,13 @@
+; Extending true with zeros gives one, so it is not the same as extending its sign.
+; expect: Failed: return
+define i8 @left(i1 noundef %c) {
+entry:
+  %r = zext i1 %c to i8
+  ret i8 %r
+}
+
+define i8 @right(i1 noundef %c) {
+entry:
+  %r = sext i1 %c to i8
+  ret i8 %r
+}
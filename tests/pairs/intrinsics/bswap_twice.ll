@@ -0,0 +1,15 @@
+; Swapping the bytes twice gives the value back.
+; expect: equivalent
+declare i32 @llvm.bswap.i32(i32)
+
+define i32 @left(i32 noundef %x) {
+entry:
+  %s = call i32 @llvm.bswap.i32(i32 %x)
+  %r = call i32 @llvm.bswap.i32(i32 %s)
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %x) {
+entry:
+  ret i32 %x
+}
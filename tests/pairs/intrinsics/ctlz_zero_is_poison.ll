@@ -0,0 +1,15 @@
+; The count of the left is poison for zero, so the right may return anything for it.
+; expect: equivalent
+declare i32 @llvm.ctlz.i32(i32, i1)
+
+define i32 @left(i32 noundef %x) {
+entry:
+  %r = call i32 @llvm.ctlz.i32(i32 %x, i1 true)
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %x) {
+entry:
+  %r = call i32 @llvm.ctlz.i32(i32 %x, i1 false)
+  ret i32 %r
+}
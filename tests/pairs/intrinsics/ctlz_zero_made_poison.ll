@@ -0,0 +1,15 @@
+; The right makes the count of zero poison, which the left defines as the width.
+; expect: Failed: return
+declare i32 @llvm.ctlz.i32(i32, i1)
+
+define i32 @left(i32 noundef %x) {
+entry:
+  %r = call i32 @llvm.ctlz.i32(i32 %x, i1 false)
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %x) {
+entry:
+  %r = call i32 @llvm.ctlz.i32(i32 %x, i1 true)
+  ret i32 %r
+}
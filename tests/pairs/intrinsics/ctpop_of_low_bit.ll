@@ -0,0 +1,16 @@
+; The number of set bits of a single bit is that bit.
+; expect: equivalent
+declare i32 @llvm.ctpop.i32(i32)
+
+define i32 @left(i32 noundef %x) {
+entry:
+  %b = and i32 %x, 1
+  %r = call i32 @llvm.ctpop.i32(i32 %b)
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %x) {
+entry:
+  %r = and i32 %x, 1
+  ret i32 %r
+}
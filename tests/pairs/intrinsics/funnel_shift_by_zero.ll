@@ -0,0 +1,14 @@
+; A funnel shift to the left by a multiple of the width returns its first operand.
+; expect: equivalent
+declare i32 @llvm.fshl.i32(i32, i32, i32)
+
+define i32 @left(i32 noundef %a, i32 noundef %b) {
+entry:
+  %r = call i32 @llvm.fshl.i32(i32 %a, i32 %b, i32 64)
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %a, i32 noundef %b) {
+entry:
+  ret i32 %a
+}
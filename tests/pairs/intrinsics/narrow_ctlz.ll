@@ -0,0 +1,15 @@
+; The leading zeros of an i7 are counted from its seventh bit, not from the top of the
+; byte holding it.
+; expect: equivalent
+declare i7 @llvm.ctlz.i7(i7, i1)
+
+define i7 @left() {
+entry:
+  %r = call i7 @llvm.ctlz.i7(i7 1, i1 false)
+  ret i7 %r
+}
+
+define i7 @right() {
+entry:
+  ret i7 6
+}
@@ -0,0 +1,14 @@
+; -1 is smaller than 0 as an i7 too, though its byte is not negative.
+; expect: equivalent
+declare i7 @llvm.smax.i7(i7, i7)
+
+define i7 @left() {
+entry:
+  %r = call i7 @llvm.smax.i7(i7 -1, i7 0)
+  ret i7 %r
+}
+
+define i7 @right() {
+entry:
+  ret i7 0
+}
@@ -0,0 +1,17 @@
+; An addition that is clamped when it wraps around is an unsigned saturating addition.
+; expect: equivalent
+declare i8 @llvm.uadd.sat.i8(i8, i8)
+
+define i8 @left(i8 noundef %a, i8 noundef %b) {
+entry:
+  %s = add i8 %a, %b
+  %wrapped = icmp ult i8 %s, %a
+  %r = select i1 %wrapped, i8 -1, i8 %s
+  ret i8 %r
+}
+
+define i8 @right(i8 noundef %a, i8 noundef %b) {
+entry:
+  %r = call i8 @llvm.uadd.sat.i8(i8 %a, i8 %b)
+  ret i8 %r
+}
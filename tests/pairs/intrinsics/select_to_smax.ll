@@ -0,0 +1,16 @@
+; The select of the left is canonicalized into the intrinsic on the right.
+; expect: equivalent
+declare i32 @llvm.smax.i32(i32, i32)
+
+define i32 @left(i32 noundef %a, i32 noundef %b) {
+entry:
+  %c = icmp sgt i32 %a, %b
+  %r = select i1 %c, i32 %a, i32 %b
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %a, i32 noundef %b) {
+entry:
+  %r = call i32 @llvm.smax.i32(i32 %a, i32 %b)
+  ret i32 %r
+}
@@ -0,0 +1,34 @@
+; flags: --unroll=2
+; The right loop passes the next value of its counter, which is never the same.
+; expect: Failed: argument_0
+declare void @use(i32)
+
+define void @left(i32 noundef %n) {
+entry:
+  br label %loop
+
+loop:
+  %i = phi i32 [ 0, %entry ], [ %next, %loop ]
+  call void @use(i32 %i)
+  %next = add i32 %i, 1
+  %done = icmp eq i32 %next, %n
+  br i1 %done, label %exit, label %loop
+
+exit:
+  ret void
+}
+
+define void @right(i32 noundef %n) {
+entry:
+  br label %loop
+
+loop:
+  %i = phi i32 [ 0, %entry ], [ %next, %loop ]
+  %next = sub i32 %i, -1
+  call void @use(i32 %next)
+  %done = icmp eq i32 %n, %next
+  br i1 %done, label %exit, label %loop
+
+exit:
+  ret void
+}
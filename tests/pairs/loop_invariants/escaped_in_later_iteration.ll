@@ -0,0 +1,56 @@
+; flags: --unroll=2
+; The alloca escapes only in the second iteration, so after the loop a call may read
+; it, whatever iteration the loop is left in.
+; expect: Failed: memory_before_call
+@g = global ptr null
+
+declare void @f()
+
+define void @left(i32 noundef %n, i32 noundef %x) {
+entry:
+  %a = alloca i32
+  br label %loop
+
+loop:
+  %i = phi i32 [ 0, %entry ], [ %i.next, %latch ]
+  %second = icmp eq i32 %i, 1
+  br i1 %second, label %leak, label %latch
+
+leak:
+  store ptr %a, ptr @g
+  br label %latch
+
+latch:
+  %i.next = add i32 %i, 1
+  %done = icmp uge i32 %i.next, %n
+  br i1 %done, label %exit, label %loop
+
+exit:
+  store i32 %x, ptr %a
+  call void @f()
+  ret void
+}
+
+define void @right(i32 noundef %n, i32 noundef %x) {
+entry:
+  %a = alloca i32
+  br label %loop
+
+loop:
+  %i = phi i32 [ 0, %entry ], [ %i.next, %latch ]
+  %second = icmp eq i32 %i, 1
+  br i1 %second, label %leak, label %latch
+
+leak:
+  store ptr %a, ptr @g
+  br label %latch
+
+latch:
+  %i.next = add i32 %i, 1
+  %done = icmp uge i32 %i.next, %n
+  br i1 %done, label %exit, label %loop
+
+exit:
+  call void @f()
+  ret void
+}
@@ -0,0 +1,39 @@
+; flags: --unroll=2
+; The sum of the arguments does not change in the loop, so it can be computed once
+; before it.
+; expect: equivalent
+declare void @use(i32)
+
+define void @left(i32 noundef %n, i32 noundef %a, i32 noundef %b) {
+entry:
+  br label %loop
+
+loop:
+  %i = phi i32 [ 0, %entry ], [ %next, %loop ]
+  %k = add i32 %a, %b
+  %v = add i32 %i, %k
+  call void @use(i32 %v)
+  %next = add i32 %i, 1
+  %done = icmp eq i32 %next, %n
+  br i1 %done, label %exit, label %loop
+
+exit:
+  ret void
+}
+
+define void @right(i32 noundef %n, i32 noundef %a, i32 noundef %b) {
+entry:
+  %k = add i32 %a, %b
+  br label %loop
+
+loop:
+  %i = phi i32 [ 0, %entry ], [ %next, %loop ]
+  %v = add i32 %i, %k
+  call void @use(i32 %v)
+  %next = add i32 %i, 1
+  %done = icmp eq i32 %next, %n
+  br i1 %done, label %exit, label %loop
+
+exit:
+  ret void
+}
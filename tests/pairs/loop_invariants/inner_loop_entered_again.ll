@@ -0,0 +1,47 @@
+; flags: --unroll=2
+; The inner loop is proven when the outer loop enters it first, but entering it again
+; starts from another state, and the returns after it differ.
+; expect: Failed: return
+define i32 @left(i32 noundef %n) {
+entry:
+  br label %outer
+
+outer:
+  %o = phi i32 [ 0, %entry ], [ 1, %latch ]
+  br label %inner
+
+inner:
+  %i = phi i32 [ 0, %outer ], [ %i.next, %inner ]
+  %i.next = add i32 %i, 1
+  %done = icmp uge i32 %i.next, %n
+  br i1 %done, label %latch, label %inner
+
+latch:
+  %last = icmp eq i32 %o, 1
+  br i1 %last, label %exit, label %outer
+
+exit:
+  ret i32 1
+}
+
+define i32 @right(i32 noundef %n) {
+entry:
+  br label %outer
+
+outer:
+  %o = phi i32 [ 0, %entry ], [ 1, %latch ]
+  br label %inner
+
+inner:
+  %i = phi i32 [ 0, %outer ], [ %i.next, %inner ]
+  %i.next = add i32 %i, 1
+  %done = icmp uge i32 %i.next, %n
+  br i1 %done, label %latch, label %inner
+
+latch:
+  %last = icmp eq i32 %o, 1
+  br i1 %last, label %exit, label %outer
+
+exit:
+  ret i32 0
+}
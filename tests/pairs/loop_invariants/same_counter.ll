@@ -0,0 +1,35 @@
+; flags: --unroll=2
+; Both loops count the same way, so the equality of their phis is an invariant and
+; the loops are verified for any number of iterations.
+; expect: equivalent
+declare void @use(i32)
+
+define void @left(i32 noundef %n) {
+entry:
+  br label %loop
+
+loop:
+  %i = phi i32 [ 0, %entry ], [ %next, %loop ]
+  call void @use(i32 %i)
+  %next = add i32 %i, 1
+  %done = icmp eq i32 %next, %n
+  br i1 %done, label %exit, label %loop
+
+exit:
+  ret void
+}
+
+define void @right(i32 noundef %n) {
+entry:
+  br label %loop
+
+loop:
+  %i = phi i32 [ 0, %entry ], [ %next, %loop ]
+  call void @use(i32 %i)
+  %next = sub i32 %i, -1
+  %done = icmp eq i32 %n, %next
+  br i1 %done, label %exit, label %loop
+
+exit:
+  ret void
+}
@@ -0,0 +1,25 @@
+; Two allocas never overlap, so a memmove between them may become a memcpy.
+; expect: equivalent
+declare void @llvm.memmove.p0.p0.i64(ptr, ptr, i64, i1)
+
+declare void @llvm.memcpy.p0.p0.i64(ptr, ptr, i64, i1)
+
+define i32 @left(i32 noundef %x) {
+entry:
+  %p = alloca i32
+  %q = alloca i32
+  store i32 %x, ptr %p
+  call void @llvm.memmove.p0.p0.i64(ptr %q, ptr %p, i64 4, i1 false)
+  %r = load i32, ptr %q
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %x) {
+entry:
+  %p = alloca i32
+  %q = alloca i32
+  store i32 %x, ptr %p
+  call void @llvm.memcpy.p0.p0.i64(ptr %q, ptr %p, i64 4, i1 false)
+  %r = load i32, ptr %q
+  ret i32 %r
+}
@@ -0,0 +1,19 @@
+; The right stores zeros to q where the left moves ones there.
+; expect: Failed: final_memory
+declare void @llvm.memset.p0.i64(ptr, i8, i64, i1)
+
+declare void @llvm.memmove.p0.p0.i64(ptr, ptr, i64, i1)
+
+define void @left(ptr noundef %p, ptr noundef %q) {
+entry:
+  call void @llvm.memset.p0.i64(ptr %p, i8 1, i64 4, i1 false)
+  call void @llvm.memmove.p0.p0.i64(ptr %q, ptr %p, i64 4, i1 false)
+  ret void
+}
+
+define void @right(ptr noundef %p, ptr noundef %q) {
+entry:
+  store i32 16843009, ptr %p
+  store i32 0, ptr %q
+  ret void
+}
@@ -0,0 +1,17 @@
+; The ranges may overlap, so the memmove of the left can not become a memcpy.
+; expect: Call to llvm.memcpy.p0.p0.i64 in new may copy overlapping ranges
+declare void @llvm.memmove.p0.p0.i64(ptr, ptr, i64, i1)
+
+declare void @llvm.memcpy.p0.p0.i64(ptr, ptr, i64, i1)
+
+define void @left(ptr noundef %p, ptr noundef %q) {
+entry:
+  call void @llvm.memmove.p0.p0.i64(ptr %q, ptr %p, i64 4, i1 false)
+  ret void
+}
+
+define void @right(ptr noundef %p, ptr noundef %q) {
+entry:
+  call void @llvm.memcpy.p0.p0.i64(ptr %q, ptr %p, i64 4, i1 false)
+  ret void
+}
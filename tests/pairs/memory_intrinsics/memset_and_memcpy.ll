@@ -0,0 +1,20 @@
+; A memset of four bytes is the same as a store of a zero i32, and so is a copy of
+; them to a range they can not overlap.
+; expect: equivalent
+declare void @llvm.memset.p0.i64(ptr, i8, i64, i1)
+
+declare void @llvm.memcpy.p0.p0.i64(ptr, ptr, i64, i1)
+
+define void @left(ptr noundef %p, ptr noundef %q) {
+entry:
+  call void @llvm.memset.p0.i64(ptr %p, i8 0, i64 4, i1 false)
+  call void @llvm.memcpy.p0.p0.i64(ptr %q, ptr %p, i64 4, i1 false)
+  ret void
+}
+
+define void @right(ptr noundef %p, ptr noundef %q) {
+entry:
+  store i32 0, ptr %p
+  store i32 0, ptr %q
+  ret void
+}
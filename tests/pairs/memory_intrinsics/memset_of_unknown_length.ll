@@ -0,0 +1,16 @@
+; The length need not be a constant, and setting no bytes does nothing.
+; expect: equivalent
+declare void @llvm.memset.p0.i64(ptr, i8, i64, i1)
+
+define void @left(ptr noundef %p, i8 noundef %x, i64 noundef %n) {
+entry:
+  call void @llvm.memset.p0.i64(ptr %p, i8 %x, i64 %n, i1 false)
+  ret void
+}
+
+define void @right(ptr noundef %p, i8 noundef %x, i64 noundef %n) {
+entry:
+  call void @llvm.memset.p0.i64(ptr %p, i8 %x, i64 %n, i1 false)
+  call void @llvm.memset.p0.i64(ptr %p, i8 0, i64 0, i1 false)
+  ret void
+}
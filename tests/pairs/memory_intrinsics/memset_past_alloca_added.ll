@@ -0,0 +1,15 @@
+; The new function may not set bytes past the end of an alloca, while an empty range
+; may be anywhere.
+; expect: Call to llvm.memset.p0.i64 in new may access memory out of bounds
+declare void @llvm.memset.p0.i64(ptr, i8, i64, i1)
+
+define void @left() {
+  ret void
+}
+
+define void @right() {
+  %a = alloca i32
+  call void @llvm.memset.p0.i64(ptr null, i8 0, i64 0, i1 false)
+  call void @llvm.memset.p0.i64(ptr %a, i8 0, i64 5, i1 false)
+  ret void
+}
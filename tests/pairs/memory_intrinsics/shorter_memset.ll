@@ -0,0 +1,15 @@
+; The right sets fewer bytes than the left.
+; expect: Failed: final_memory
+declare void @llvm.memset.p0.i64(ptr, i8, i64, i1)
+
+define void @left(ptr noundef %p, i8 noundef %x) {
+entry:
+  call void @llvm.memset.p0.i64(ptr %p, i8 %x, i64 8, i1 false)
+  ret void
+}
+
+define void @right(ptr noundef %p, i8 noundef %x) {
+entry:
+  call void @llvm.memset.p0.i64(ptr %p, i8 %x, i64 7, i1 false)
+  ret void
+}
@@ -0,0 +1,15 @@
+; The right stores and returns other values, for every argument, so the report names
+; both goals.
+; expect: Failed: final_memory, return
+define i32 @left(ptr noundef %p, i32 noundef %x) {
+entry:
+  store i32 %x, ptr %p
+  ret i32 %x
+}
+
+define i32 @right(ptr noundef %p, i32 noundef %x) {
+entry:
+  %y = add i32 %x, 1
+  store i32 %y, ptr %p
+  ret i32 %y
+}
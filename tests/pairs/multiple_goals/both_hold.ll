@@ -0,0 +1,15 @@
+; Both the returned value and the final memory are compared in one check.
+; expect: equivalent
+define i32 @left(ptr noundef %p, i32 noundef %x) {
+entry:
+  store i32 %x, ptr %p
+  %r = add i32 %x, 1
+  ret i32 %r
+}
+
+define i32 @right(ptr noundef %p, i32 noundef %x) {
+entry:
+  %r = sub i32 %x, -1
+  store i32 %x, ptr %p
+  ret i32 %r
+}
@@ -0,0 +1,16 @@
+; The right returns the same value but stores another one, and the report names only
+; the goal on memory.
+; expect: Failed: final_memory
+define i32 @left(ptr noundef %p, i32 noundef %x) {
+entry:
+  store i32 %x, ptr %p
+  %r = add i32 %x, 1
+  ret i32 %r
+}
+
+define i32 @right(ptr noundef %p, i32 noundef %x) {
+entry:
+  %r = add i32 %x, 1
+  store i32 %r, ptr %p
+  ret i32 %r
+}
@@ -0,0 +1,15 @@
+; The right stores the same value but returns another one, and the report names only
+; the goal on the returned value.
+; expect: Failed: return
+define i32 @left(ptr noundef %p, i32 noundef %x) {
+entry:
+  store i32 %x, ptr %p
+  %r = add i32 %x, 1
+  ret i32 %r
+}
+
+define i32 @right(ptr noundef %p, i32 noundef %x) {
+entry:
+  store i32 %x, ptr %p
+  ret i32 %x
+}
@@ -0,0 +1,22 @@
+; The branch of the left is turned into a select on the right.
+; expect: equivalent
+define i32 @left(i1 noundef %c, i32 noundef %x, i32 noundef %y) {
+entry:
+  br i1 %c, label %then, label %else
+
+then:
+  br label %exit
+
+else:
+  br label %exit
+
+exit:
+  %r = phi i32 [ %x, %then ], [ %y, %else ]
+  ret i32 %r
+}
+
+define i32 @right(i1 noundef %c, i32 noundef %x, i32 noundef %y) {
+entry:
+  %r = select i1 %c, i32 %x, i32 %y
+  ret i32 %r
+}
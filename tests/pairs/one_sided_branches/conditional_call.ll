@@ -0,0 +1,21 @@
+; The right calls the function even when the left skips it.
+; expect: Call happened in new
+declare void @f()
+
+define void @left(i1 noundef %c) {
+entry:
+  br i1 %c, label %then, label %exit
+
+then:
+  call void @f()
+  br label %exit
+
+exit:
+  ret void
+}
+
+define void @right(i1 noundef %c) {
+entry:
+  call void @f()
+  ret void
+}
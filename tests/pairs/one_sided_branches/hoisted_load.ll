@@ -0,0 +1,23 @@
+; The right hoists the load both branches of the left do above the branch.
+; expect: equivalent
+define i32 @left(i1 noundef %c, ptr noundef %p) {
+entry:
+  br i1 %c, label %then, label %else
+
+then:
+  %v = load i32, ptr %p
+  ret i32 %v
+
+else:
+  %w = load i32, ptr %p
+  %x = add i32 %w, 1
+  ret i32 %x
+}
+
+define i32 @right(i1 noundef %c, ptr noundef %p) {
+entry:
+  %v = load i32, ptr %p
+  %x = add i32 %v, 1
+  %r = select i1 %c, i32 %v, i32 %x
+  ret i32 %r
+}
@@ -0,0 +1,48 @@
+; Without the flag, each of the eight ways through the ifs is a path of its own.
+; expect: equivalent
+; expect: explored 15 paths, pruned 0 infeasible branch combinations, matched 0 corresponding branches, merged 0 paths
+define i32 @left(i32 noundef %a, i32 noundef %b, i32 noundef %c) {
+entry:
+  %c1 = icmp ult i32 %a, 10
+  br i1 %c1, label %then1, label %join1
+
+then1:
+  %a1 = add i32 %c, 1
+  br label %join1
+
+join1:
+  %v1 = phi i32 [ %c, %entry ], [ %a1, %then1 ]
+  %c2 = icmp ult i32 %b, 10
+  br i1 %c2, label %then2, label %join2
+
+then2:
+  %a2 = add i32 %v1, 2
+  br label %join2
+
+join2:
+  %v2 = phi i32 [ %v1, %join1 ], [ %a2, %then2 ]
+  %c3 = icmp ult i32 %c, 10
+  br i1 %c3, label %then3, label %join3
+
+then3:
+  %a3 = sub i32 %v2, 3
+  br label %join3
+
+join3:
+  %v3 = phi i32 [ %v2, %join2 ], [ %a3, %then3 ]
+  ret i32 %v3
+}
+
+define i32 @right(i32 noundef %a, i32 noundef %b, i32 noundef %c) {
+entry:
+  %c1 = icmp ult i32 %a, 10
+  %a1 = add i32 %c, 1
+  %v1 = select i1 %c1, i32 %a1, i32 %c
+  %c2 = icmp ult i32 %b, 10
+  %a2 = add i32 %v1, 2
+  %v2 = select i1 %c2, i32 %a2, i32 %v1
+  %c3 = icmp ult i32 %c, 10
+  %a3 = sub i32 %v2, 3
+  %v3 = select i1 %c3, i32 %a3, i32 %v2
+  ret i32 %v3
+}
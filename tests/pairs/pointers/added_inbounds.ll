@@ -0,0 +1,11 @@
+; inbounds makes a pointer that leaves its block poison, which it is not without it.
+; expect: Failed: return
+define ptr @left(ptr noundef %p, i64 noundef %i) {
+  %q = getelementptr i8, ptr %p, i64 %i
+  ret ptr %q
+}
+
+define ptr @right(ptr noundef %p, i64 noundef %i) {
+  %q = getelementptr inbounds i8, ptr %p, i64 %i
+  ret ptr %q
+}
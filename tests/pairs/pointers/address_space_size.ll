@@ -0,0 +1,16 @@
+; Pointers of address space 1 take 4 bytes, so storing one leaves the next word alone.
+; expect: equivalent
+target datalayout = "e-p1:32:32"
+
+define i32 @left(i32 noundef %x, ptr addrspace(1) noundef %q) {
+  %a = alloca [8 x i8]
+  %high = getelementptr i8, ptr %a, i64 4
+  store i32 %x, ptr %high
+  store ptr addrspace(1) %q, ptr %a
+  %r = load i32, ptr %high
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %x, ptr addrspace(1) noundef %q) {
+  ret i32 %x
+}
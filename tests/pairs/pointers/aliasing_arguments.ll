@@ -0,0 +1,10 @@
+; Two pointer arguments may point to the same place.
+; expect: Failed: return
+define i1 @left(ptr noundef %p, ptr noundef %q) {
+  %same = icmp eq ptr %p, %q
+  ret i1 %same
+}
+
+define i1 @right(ptr noundef %p, ptr noundef %q) {
+  ret i1 false
+}
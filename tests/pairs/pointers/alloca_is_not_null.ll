@@ -0,0 +1,11 @@
+; No allocated block is at the null address.
+; expect: equivalent
+define i1 @left() {
+  %a = alloca i32
+  %r = icmp eq ptr %a, null
+  ret i1 %r
+}
+
+define i1 @right() {
+  ret i1 false
+}
@@ -0,0 +1,11 @@
+; An argument may point to no memory at all, so the new function may not load from it
+; where the old one did not.
+; expect: Load out of bounds in new
+define i32 @left(ptr noundef %p) {
+  ret i32 0
+}
+
+define i32 @right(ptr noundef %p) {
+  %x = load i32, ptr %p
+  ret i32 0
+}
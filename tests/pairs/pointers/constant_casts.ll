@@ -0,0 +1,16 @@
+; Casts of constant pointers are computed like the instructions they fold.
+; expect: equivalent
+@g = global i32 0
+
+define i64 @left() {
+  %i = ptrtoint ptr @g to i64
+  %p = inttoptr i64 %i to ptr addrspace(1)
+  %q = ptrtoint ptr addrspace(1) %p to i64
+  %d = sub i64 %q, %i
+  ret i64 %d
+}
+
+define i64 @right() {
+  %d = sub i64 ptrtoint (ptr addrspace(1) inttoptr (i64 ptrtoint (ptr @g to i64) to ptr addrspace(1)) to i64), ptrtoint (ptr @g to i64)
+  ret i64 %d
+}
@@ -0,0 +1,14 @@
+; Blocks are placed at unknown addresses, so two allocas are not known to be adjacent.
+; expect: Failed: return
+define i64 @left() {
+  %a = alloca i8
+  %b = alloca i8
+  %i = ptrtoint ptr %a to i64
+  %j = ptrtoint ptr %b to i64
+  %d = sub i64 %j, %i
+  ret i64 %d
+}
+
+define i64 @right() {
+  ret i64 1
+}
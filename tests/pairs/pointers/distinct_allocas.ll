@@ -0,0 +1,13 @@
+; Pointers into different blocks are never equal.
+; expect: equivalent
+define i1 @left(i64 noundef %i) {
+  %a = alloca [4 x i8]
+  %b = alloca [4 x i8]
+  %p = getelementptr i8, ptr %a, i64 %i
+  %r = icmp eq ptr %p, %b
+  ret i1 %r
+}
+
+define i1 @right(i64 noundef %i) {
+  ret i1 false
+}
@@ -0,0 +1,11 @@
+; Dropping inbounds only makes a pointer that was poison a value.
+; expect: equivalent
+define ptr @left(ptr noundef %p, i64 noundef %i) {
+  %q = getelementptr inbounds i32, ptr %p, i64 %i
+  ret ptr %q
+}
+
+define ptr @right(ptr noundef %p, i64 noundef %i) {
+  %q = getelementptr i32, ptr %p, i64 %i
+  ret ptr %q
+}
@@ -0,0 +1,11 @@
+; A pointer made up from an integer is null if the integer is zero.
+; expect: Failed: return
+define i1 @left(i64 noundef %i) {
+  %p = inttoptr i64 %i to ptr
+  %r = icmp eq ptr %p, null
+  ret i1 %r
+}
+
+define i1 @right(i64 noundef %i) {
+  ret i1 false
+}
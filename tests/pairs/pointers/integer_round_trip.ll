@@ -0,0 +1,17 @@
+; Pointers compare by address once an integer round trip loses their provenance, and
+; their integer values differ by the offset between them.
+; expect: equivalent
+define i64 @left(ptr noundef %p) {
+  %q = getelementptr i8, ptr %p, i64 4
+  %i = ptrtoint ptr %q to i64
+  %j = ptrtoint ptr %p to i64
+  %d = sub i64 %i, %j
+  %back = inttoptr i64 %j to ptr
+  %same = icmp eq ptr %back, %p
+  %r = select i1 %same, i64 %d, i64 0
+  ret i64 %r
+}
+
+define i64 @right(ptr noundef %p) {
+  ret i64 4
+}
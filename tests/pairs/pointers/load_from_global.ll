@@ -0,0 +1,13 @@
+; Loads inside a global with an initializer are not undefined behavior.
+; expect: Failed: return
+@g = global [2 x i32] zeroinitializer
+
+define i32 @left() {
+  %p = getelementptr i32, ptr @g, i64 1
+  %r = load i32, ptr %p
+  ret i32 %r
+}
+
+define i32 @right() {
+  ret i32 7
+}
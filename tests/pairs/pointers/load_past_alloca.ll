@@ -0,0 +1,12 @@
+; Loading past the end of an alloca is undefined behavior.
+; expect: equivalent
+define i32 @left() {
+  %a = alloca i32
+  %p = getelementptr i8, ptr %a, i64 2
+  %r = load i32, ptr %p
+  ret i32 %r
+}
+
+define i32 @right() {
+  ret i32 7
+}
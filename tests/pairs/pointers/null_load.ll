@@ -0,0 +1,10 @@
+; Loading from null is undefined behavior, so the new function may do anything instead.
+; expect: equivalent
+define i32 @left() {
+  %r = load i32, ptr null
+  ret i32 %r
+}
+
+define i32 @right() {
+  ret i32 7
+}
@@ -0,0 +1,12 @@
+; The new function may not store past the end of an alloca where the old one did not.
+; expect: Store out of bounds in new
+define i32 @left() {
+  ret i32 0
+}
+
+define i32 @right() {
+  %a = alloca [2 x i32]
+  %p = getelementptr i32, ptr %a, i64 2
+  store i32 1, ptr %p
+  ret i32 0
+}
@@ -0,0 +1,16 @@
+; Removing an alloca that is never used keeps the blocks of the others.
+; expect: equivalent
+declare void @f(ptr)
+
+define void @left() {
+  %a = alloca i32
+  %b = alloca i32
+  call void @f(ptr %b)
+  ret void
+}
+
+define void @right() {
+  %b = alloca i32
+  call void @f(ptr %b)
+  ret void
+}
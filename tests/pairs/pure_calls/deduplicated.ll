@@ -0,0 +1,17 @@
+; flags: --pure-calls-as-functions
+; Calling a pure function twice with the same argument gives the same result twice.
+; expect: equivalent
+declare i32 @f(i32)
+
+define i32 @left(i32 noundef %x) {
+  %a = call i32 @f(i32 %x) memory(none) nounwind willreturn
+  %b = call i32 @f(i32 %x) memory(none) nounwind willreturn
+  %r = add i32 %a, %b
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %x) {
+  %a = call i32 @f(i32 %x) memory(none) nounwind willreturn
+  %r = add i32 %a, %a
+  ret i32 %r
+}
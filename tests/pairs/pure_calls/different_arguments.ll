@@ -0,0 +1,18 @@
+; flags: --pure-calls-as-functions
+; A pure function may give different results for different arguments.
+; expect: Failed: return
+declare i32 @f(i32)
+
+define i32 @left(i32 noundef %x) {
+  %y = add i32 %x, 1
+  %a = call i32 @f(i32 %x) memory(none) nounwind willreturn
+  %b = call i32 @f(i32 %y) memory(none) nounwind willreturn
+  %r = add i32 %a, %b
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %x) {
+  %a = call i32 @f(i32 %x) memory(none) nounwind willreturn
+  %r = add i32 %a, %a
+  ret i32 %r
+}
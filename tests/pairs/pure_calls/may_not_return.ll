@@ -0,0 +1,14 @@
+; flags: --pure-calls-as-functions
+; A call that may loop forever is not pure, even if it touches no memory, so it can not
+; be removed.
+; expect: Call missed in new
+declare i32 @f(i32)
+
+define i32 @left(i32 noundef %x) {
+  %a = call i32 @f(i32 %x) memory(none) nounwind
+  ret i32 %x
+}
+
+define i32 @right(i32 noundef %x) {
+  ret i32 %x
+}
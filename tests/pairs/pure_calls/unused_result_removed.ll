@@ -0,0 +1,13 @@
+; flags: --pure-calls-as-functions
+; A pure call whose result is not used can be removed.
+; expect: equivalent
+declare i32 @f(i32)
+
+define i32 @left(i32 noundef %x) {
+  %a = call i32 @f(i32 %x) memory(none) nounwind willreturn
+  ret i32 %x
+}
+
+define i32 @right(i32 noundef %x) {
+  ret i32 %x
+}
@@ -0,0 +1,16 @@
+; Without the flag, calls are matched one to one even if they are pure.
+; expect: Call missed in new
+declare i32 @f(i32)
+
+define i32 @left(i32 noundef %x) {
+  %a = call i32 @f(i32 %x) memory(none) nounwind willreturn
+  %b = call i32 @f(i32 %x) memory(none) nounwind willreturn
+  %r = add i32 %a, %b
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %x) {
+  %a = call i32 @f(i32 %x) memory(none) nounwind willreturn
+  %r = add i32 %a, %a
+  ret i32 %r
+}
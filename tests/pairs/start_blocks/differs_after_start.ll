@@ -0,0 +1,22 @@
+; flags: --from=body:body
+; Starting at the later blocks still finds the difference after them.
+; expect: Failed: return
+define i32 @left(i32 noundef %x) {
+entry:
+  %a = add i32 %x, 1
+  br label %body
+
+body:
+  %r = add i32 %a, %a
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %x) {
+entry:
+  %a = add i32 %x, 1
+  br label %body
+
+body:
+  %r = sub i32 %a, %a
+  ret i32 %r
+}
@@ -0,0 +1,23 @@
+; flags: --from=body:body
+; The functions only differ before the blocks the comparison starts from, where both
+; sides have the same registers.
+; expect: equivalent
+define i32 @left(i32 noundef %x) {
+entry:
+  %a = add i32 %x, 1
+  br label %body
+
+body:
+  %r = add i32 %a, %a
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %x) {
+entry:
+  %a = add i32 %x, 2
+  br label %body
+
+body:
+  %r = add i32 %a, %a
+  ret i32 %r
+}
@@ -0,0 +1,21 @@
+; The same functions compared from their entry blocks differ.
+; expect: Failed: return
+define i32 @left(i32 noundef %x) {
+entry:
+  %a = add i32 %x, 1
+  br label %body
+
+body:
+  %r = add i32 %a, %a
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %x) {
+entry:
+  %a = add i32 %x, 2
+  br label %body
+
+body:
+  %r = add i32 %a, %a
+  ret i32 %r
+}
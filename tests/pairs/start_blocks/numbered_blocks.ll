@@ -0,0 +1,24 @@
+; flags: --from=1:2
+; Blocks without a name are given by number, and the blocks the sides start from need
+; not have the same name.
+; expect: equivalent
+define i32 @left(i32 noundef %x) {
+  %a = add i32 %x, 1
+  br label %1
+
+1:
+  %r = add i32 %a, %a
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %x) {
+  %a = add i32 %x, 3
+  br label %1
+
+1:
+  br label %2
+
+2:
+  %r = add i32 %a, %a
+  ret i32 %r
+}
@@ -0,0 +1,30 @@
+; flags: --from=join:join
+; The phis of the starting blocks are part of the shared state, so which way the
+; functions came does not matter.
+; expect: equivalent
+define i32 @left(i1 noundef %c, i32 noundef %x) {
+entry:
+  br i1 %c, label %then, label %join
+
+then:
+  br label %join
+
+join:
+  %p = phi i32 [ 1, %then ], [ %x, %entry ]
+  %r = add i32 %p, %p
+  ret i32 %r
+}
+
+define i32 @right(i1 noundef %c, i32 noundef %x) {
+entry:
+  br i1 %c, label %then, label %join
+
+then:
+  br label %join
+
+join:
+  %p = phi i32 [ 7, %then ], [ %x, %entry ]
+  %d = sub i32 %p, 0
+  %r = add i32 %d, %p
+  ret i32 %r
+}
@@ -0,0 +1,11 @@
+; Undef may be any value, so it does not refine a constant.
+; expect: Failed: return
+define i32 @left() {
+entry:
+  ret i32 0
+}
+
+define i32 @right() {
+entry:
+  ret i32 undef
+}
@@ -0,0 +1,11 @@
+; An undef that is kept may be the same value on both sides.
+; expect: equivalent
+define i32 @left() {
+entry:
+  ret i32 undef
+}
+
+define i32 @right() {
+entry:
+  ret i32 undef
+}
@@ -0,0 +1,15 @@
+; Storing undef on both sides leaves memory that may be the same.
+; expect: equivalent
+@g = global { i32, i64 } zeroinitializer
+
+define void @left() {
+entry:
+  store { i32, i64 } { i32 undef, i64 1 }, ptr @g
+  ret void
+}
+
+define void @right() {
+entry:
+  store { i32, i64 } { i32 undef, i64 1 }, ptr @g
+  ret void
+}
@@ -0,0 +1,26 @@
+; flags: --unroll=1
+; The loop goes around its back-edge more often than the bound allows.
+; expect: equivalent up to 1 iteration (1 path exceeding the bound is incomplete)
+declare void @use(i32)
+
+define void @left() {
+entry:
+  br label %loop
+
+loop:
+  %i = phi i32 [ 0, %entry ], [ %next, %loop ]
+  call void @use(i32 %i)
+  %next = add i32 %i, 1
+  %done = icmp eq i32 %next, 3
+  br i1 %done, label %exit, label %loop
+
+exit:
+  ret void
+}
+
+define void @right() {
+  call void @use(i32 0)
+  call void @use(i32 1)
+  call void @use(i32 2)
+  ret void
+}
@@ -0,0 +1,38 @@
+; flags: --unroll=3
+; The right loop counts down, so no phi is equal on both sides to prove the loops
+; equivalent, and they are only compared for a bounded number of iterations.
+; expect: equivalent up to 3 iterations (1 path exceeding the bound is incomplete)
+declare void @use(i32)
+
+define void @left(i32 noundef %n) {
+entry:
+  %empty = icmp eq i32 %n, 0
+  br i1 %empty, label %exit, label %loop
+
+loop:
+  %i = phi i32 [ 0, %entry ], [ %next, %loop ]
+  call void @use(i32 %i)
+  %next = add i32 %i, 1
+  %done = icmp eq i32 %next, %n
+  br i1 %done, label %exit, label %loop
+
+exit:
+  ret void
+}
+
+define void @right(i32 noundef %n) {
+entry:
+  %empty = icmp eq i32 %n, 0
+  br i1 %empty, label %exit, label %loop
+
+loop:
+  %j = phi i32 [ %n, %entry ], [ %next, %loop ]
+  %i = sub i32 %n, %j
+  call void @use(i32 %i)
+  %next = sub i32 %j, 1
+  %done = icmp eq i32 %next, 0
+  br i1 %done, label %exit, label %loop
+
+exit:
+  ret void
+}
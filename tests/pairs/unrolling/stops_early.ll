@@ -0,0 +1,37 @@
+; flags: --unroll=3
+; The right loop stops one iteration early, or runs on past the end when n is 1.
+; expect: Call happened in new
+declare void @use(i32)
+
+define void @left(i32 noundef %n) {
+entry:
+  %empty = icmp eq i32 %n, 0
+  br i1 %empty, label %exit, label %loop
+
+loop:
+  %i = phi i32 [ 0, %entry ], [ %next, %loop ]
+  call void @use(i32 %i)
+  %next = add i32 %i, 1
+  %done = icmp eq i32 %next, %n
+  br i1 %done, label %exit, label %loop
+
+exit:
+  ret void
+}
+
+define void @right(i32 noundef %n) {
+entry:
+  %empty = icmp eq i32 %n, 0
+  br i1 %empty, label %exit, label %loop
+
+loop:
+  %j = phi i32 [ %n, %entry ], [ %next, %loop ]
+  %i = sub i32 %n, %j
+  call void @use(i32 %i)
+  %next = sub i32 %j, 1
+  %done = icmp eq i32 %next, 1
+  br i1 %done, label %exit, label %loop
+
+exit:
+  ret void
+}
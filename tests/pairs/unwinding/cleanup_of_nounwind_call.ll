@@ -0,0 +1,26 @@
+; The call does not unwind, so the store in the landing pad of the left never happens.
+; expect: equivalent
+declare void @may_throw()
+
+declare i32 @__gxx_personality_v0(...)
+
+define void @left(ptr noundef %p, i32 noundef %x) personality ptr @__gxx_personality_v0 {
+entry:
+  invoke void @may_throw() nounwind
+          to label %done unwind label %cleanup
+
+done:
+  ret void
+
+cleanup:
+  %lp = landingpad { ptr, i32 }
+          cleanup
+  store i32 %x, ptr %p
+  resume { ptr, i32 } %lp
+}
+
+define void @right(ptr noundef %p, i32 noundef %x) personality ptr @__gxx_personality_v0 {
+entry:
+  call void @may_throw() nounwind
+  ret void
+}
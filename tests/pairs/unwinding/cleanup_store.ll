@@ -0,0 +1,36 @@
+; The cleanup stores the same value on both sides before resuming the unwinding.
+; expect: equivalent
+declare void @may_throw()
+
+declare i32 @__gxx_personality_v0(...)
+
+define void @left(ptr noundef %p, i32 noundef %x) personality ptr @__gxx_personality_v0 {
+entry:
+  invoke void @may_throw()
+          to label %done unwind label %cleanup
+
+done:
+  ret void
+
+cleanup:
+  %lp = landingpad { ptr, i32 }
+          cleanup
+  store i32 %x, ptr %p
+  resume { ptr, i32 } %lp
+}
+
+define void @right(ptr noundef %p, i32 noundef %x) personality ptr @__gxx_personality_v0 {
+entry:
+  invoke void @may_throw()
+          to label %done unwind label %cleanup
+
+done:
+  ret void
+
+cleanup:
+  %lp = landingpad { ptr, i32 }
+          cleanup
+  %y = add i32 %x, 0
+  store i32 %y, ptr %p
+  resume { ptr, i32 } %lp
+}
@@ -0,0 +1,26 @@
+; A landing pad that only resumes is the same as letting the exception pass through a
+; plain call.
+; expect: equivalent
+declare void @may_throw()
+
+declare i32 @__gxx_personality_v0(...)
+
+define i32 @left(i32 noundef %x) personality ptr @__gxx_personality_v0 {
+entry:
+  invoke void @may_throw()
+          to label %done unwind label %cleanup
+
+done:
+  ret i32 %x
+
+cleanup:
+  %lp = landingpad { ptr, i32 }
+          cleanup
+  resume { ptr, i32 } %lp
+}
+
+define i32 @right(i32 noundef %x) personality ptr @__gxx_personality_v0 {
+entry:
+  call void @may_throw()
+  ret i32 %x
+}
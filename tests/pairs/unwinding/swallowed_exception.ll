@@ -0,0 +1,33 @@
+; The right catches the exception and returns instead of unwinding.
+; expect: Unwinding missed in new
+declare void @may_throw()
+
+declare i32 @__gxx_personality_v0(...)
+
+define i32 @left(i32 noundef %x) personality ptr @__gxx_personality_v0 {
+entry:
+  invoke void @may_throw()
+          to label %done unwind label %cleanup
+
+done:
+  ret i32 %x
+
+cleanup:
+  %lp = landingpad { ptr, i32 }
+          cleanup
+  resume { ptr, i32 } %lp
+}
+
+define i32 @right(i32 noundef %x) personality ptr @__gxx_personality_v0 {
+entry:
+  invoke void @may_throw()
+          to label %done unwind label %cleanup
+
+done:
+  ret i32 %x
+
+cleanup:
+  %lp = landingpad { ptr, i32 }
+          cleanup
+  ret i32 %x
+}
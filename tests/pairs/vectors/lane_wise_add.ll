@@ -0,0 +1,16 @@
+; Adding two vectors adds their lanes, so one lane of the sum is the sum of the lanes.
+; expect: equivalent
+define i32 @left(<4 x i32> noundef %a, <4 x i32> noundef %b) {
+entry:
+  %s = add <4 x i32> %a, %b
+  %r = extractelement <4 x i32> %s, i32 2
+  ret i32 %r
+}
+
+define i32 @right(<4 x i32> noundef %a, <4 x i32> noundef %b) {
+entry:
+  %x = extractelement <4 x i32> %a, i32 2
+  %y = extractelement <4 x i32> %b, i32 2
+  %r = add i32 %x, %y
+  ret i32 %r
+}
@@ -0,0 +1,24 @@
+; Loading a vector of booleans unpacks the bits that a bitcast would, and storing it
+; back only writes the low bits of the byte that holds them. On a little endian target
+; lane 0 is the lowest bit.
+; expect: equivalent
+target datalayout = "e"
+
+define i16 @left(i8 noundef %x) {
+entry:
+  %p = alloca i16
+  store i16 -1, ptr %p
+  store i8 %x, ptr %p
+  %v = load <4 x i1>, ptr %p
+  store <4 x i1> %v, ptr %p
+  %r = load i16, ptr %p
+  ret i16 %r
+}
+
+define i16 @right(i8 noundef %x) {
+entry:
+  %low = and i8 %x, 15
+  %wide = zext i8 %low to i16
+  %r = or i16 %wide, -256
+  ret i16 %r
+}
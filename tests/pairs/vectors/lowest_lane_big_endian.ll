@@ -0,0 +1,19 @@
+; On a big endian target lane 0 is still the byte at the lowest address, which is the
+; highest byte of the i32.
+; expect: equivalent
+target datalayout = "E"
+
+define i8 @left(i32 noundef %x) {
+entry:
+  %v = bitcast i32 %x to <4 x i8>
+  %r = extractelement <4 x i8> %v, i32 0
+  ret i8 %r
+}
+
+define i8 @right(i32 noundef %x) {
+entry:
+  %p = alloca i32
+  store i32 %x, ptr %p
+  %r = load i8, ptr %p
+  ret i8 %r
+}
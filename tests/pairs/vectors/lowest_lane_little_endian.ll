@@ -0,0 +1,18 @@
+; Lane 0 of the bytes of an i32 is its lowest byte on a little endian target.
+; expect: equivalent
+target datalayout = "e"
+
+define i8 @left(i32 noundef %x) {
+entry:
+  %v = bitcast i32 %x to <4 x i8>
+  %r = extractelement <4 x i8> %v, i32 0
+  ret i8 %r
+}
+
+define i8 @right(i32 noundef %x) {
+entry:
+  %p = alloca i32
+  store i32 %x, ptr %p
+  %r = load i8, ptr %p
+  ret i8 %r
+}
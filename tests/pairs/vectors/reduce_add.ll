@@ -0,0 +1,21 @@
+; The reduction adds all lanes.
+; expect: equivalent
+declare i32 @llvm.vector.reduce.add.v4i32(<4 x i32>)
+
+define i32 @left(<4 x i32> noundef %a) {
+entry:
+  %r = call i32 @llvm.vector.reduce.add.v4i32(<4 x i32> %a)
+  ret i32 %r
+}
+
+define i32 @right(<4 x i32> noundef %a) {
+entry:
+  %x = extractelement <4 x i32> %a, i32 0
+  %y = extractelement <4 x i32> %a, i32 1
+  %z = extractelement <4 x i32> %a, i32 2
+  %w = extractelement <4 x i32> %a, i32 3
+  %s = add i32 %x, %y
+  %t = add i32 %z, %w
+  %r = add i32 %s, %t
+  ret i32 %r
+}
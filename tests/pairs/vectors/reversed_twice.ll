@@ -0,0 +1,13 @@
+; Reversing the lanes twice gives the vector back.
+; expect: equivalent
+define <4 x i32> @left(<4 x i32> noundef %a) {
+entry:
+  %r = shufflevector <4 x i32> %a, <4 x i32> poison, <4 x i32> <i32 3, i32 2, i32 1, i32 0>
+  %s = shufflevector <4 x i32> %r, <4 x i32> poison, <4 x i32> <i32 3, i32 2, i32 1, i32 0>
+  ret <4 x i32> %s
+}
+
+define <4 x i32> @right(<4 x i32> noundef %a) {
+entry:
+  ret <4 x i32> %a
+}
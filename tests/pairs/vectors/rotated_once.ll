@@ -0,0 +1,13 @@
+; Only lane 1 of the rotated vector differs from the reversed one.
+; expect: Failed: return_1
+define <4 x i32> @left(<4 x i32> noundef %a) {
+entry:
+  %r = shufflevector <4 x i32> %a, <4 x i32> poison, <4 x i32> <i32 3, i32 2, i32 1, i32 0>
+  ret <4 x i32> %r
+}
+
+define <4 x i32> @right(<4 x i32> noundef %a) {
+entry:
+  %r = shufflevector <4 x i32> %a, <4 x i32> poison, <4 x i32> <i32 3, i32 0, i32 1, i32 0>
+  ret <4 x i32> %r
+}
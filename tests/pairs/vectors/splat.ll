@@ -0,0 +1,14 @@
+; A value inserted into lane 0 and shuffled to all lanes is in each of them.
+; expect: equivalent
+define i32 @left(i32 noundef %x) {
+entry:
+  %v = insertelement <4 x i32> poison, i32 %x, i32 0
+  %s = shufflevector <4 x i32> %v, <4 x i32> poison, <4 x i32> zeroinitializer
+  %r = extractelement <4 x i32> %s, i32 3
+  ret i32 %r
+}
+
+define i32 @right(i32 noundef %x) {
+entry:
+  ret i32 %x
+}
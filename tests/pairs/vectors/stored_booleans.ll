@@ -0,0 +1,17 @@
+; Booleans in a stored vector are packed to bits, as a bitcast packs them.
+; expect: equivalent
+target datalayout = "e"
+
+define i8 @left(<8 x i1> noundef %v) {
+entry:
+  %p = alloca i8
+  store <8 x i1> %v, ptr %p
+  %r = load i8, ptr %p
+  ret i8 %r
+}
+
+define i8 @right(<8 x i1> noundef %v) {
+entry:
+  %r = bitcast <8 x i1> %v to i8
+  ret i8 %r
+}
@@ -0,0 +1,12 @@
+; A lane that the mask of the left leaves undefined may take any value on the right.
+; expect: equivalent
+define <2 x i32> @left(<2 x i32> noundef %a) {
+entry:
+  %r = shufflevector <2 x i32> %a, <2 x i32> poison, <2 x i32> <i32 0, i32 poison>
+  ret <2 x i32> %r
+}
+
+define <2 x i32> @right(<2 x i32> noundef %a) {
+entry:
+  ret <2 x i32> %a
+}